@@ -2,15 +2,55 @@
 ///
 /// Preserve state between executions
 use crate::error::{Error, ErrorKind, Result};
-use crate::task::Tasks;
+use crate::task::{Task, Tasks};
 use crate::vars::Vars;
 
-#[cfg(test)]
-use crate::task::Task;
+use std::io::{BufRead, Write};
 
 #[cfg(test)]
 use crate::vars;
 
+/// Answer to the confirmation asked before each task in step mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepAnswer {
+    /// Execute the task.
+    Yes,
+    /// Skip the task.
+    No,
+    /// Execute the task and the rest of them without asking again.
+    Continue,
+}
+
+/// Ask in `writer` whether `task_name` must be executed, reading the answer from `reader`.
+/// Empty answers skip the task and invalid ones are asked again.
+pub fn prompt_step<R, W>(reader: &mut R, writer: &mut W, task_name: &str) -> Result<StepAnswer>
+where
+    R: BufRead,
+    W: Write,
+{
+    loop {
+        write!(
+            writer,
+            "Perform task: {} (N)o/(y)es/(c)ontinue: ",
+            task_name
+        )?;
+        writer.flush()?;
+        let mut answer = String::new();
+        if reader.read_line(&mut answer)? == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("no answer to perform task '{}'", task_name),
+            ));
+        };
+        match answer.trim().to_lowercase().as_str() {
+            "" | "n" | "no" => return Ok(StepAnswer::No),
+            "y" | "yes" => return Ok(StepAnswer::Yes),
+            "c" | "continue" => return Ok(StepAnswer::Continue),
+            answer => writeln!(writer, "invalid answer: {}", answer)?,
+        };
+    }
+}
+
 /// Main data structure in `rash`.
 /// It contents all [`task::Tasks`] with their [`vars::Vars`] to be executed
 ///
@@ -44,19 +84,26 @@ impl Context {
 
         let mut next_tasks = self.tasks.clone();
         let next_task = next_tasks.remove(0);
+        let task_name = self.get_task_name(&next_task);
         info!(target: "task",
             "[{}] - {} to go - ",
-            next_task.get_rendered_name(self.vars.clone())
-                .unwrap_or_else(|_| next_task.get_module().get_name().to_string()),
+            task_name,
             self.tasks.len(),
         );
-        let vars = next_task.exec(self.vars.clone())?;
+        let vars = next_task
+            .exec(self.vars.clone())
+            .map_err(|e| e.context(format!("task '{}'", task_name)))?;
         Ok(Self {
             tasks: next_tasks,
             vars,
         })
     }
 
+    fn get_task_name(&self, task: &Task) -> String {
+        task.get_rendered_name(self.vars.clone())
+            .unwrap_or_else(|_| task.get_module().get_name().to_string())
+    }
+
     /// Execute all Tasks in Context until empty.
     ///
     /// If this finishes correctly, it will return an [`error::Error`] with [`ErrorKind::EmptyTaskStack`]
@@ -68,6 +115,43 @@ impl Context {
         Self::exec(context.exec_task()?)
     }
 
+    /// Execute all Tasks in Context and return final [`vars::Vars`].
+    ///
+    /// [`vars::Vars`]: ../vars/type.Vars.html
+    pub fn exec_all(context: Self) -> Result<Vars> {
+        if context.tasks.is_empty() {
+            return Ok(context.vars);
+        }
+        Self::exec_all(context.exec_task()?)
+    }
+
+    /// Execute all Tasks in Context like [`Context::exec_all`], asking `prompt` before each one
+    /// whether it must be executed. Skipped tasks do not change [`vars::Vars`].
+    ///
+    /// [`Context::exec_all`]: struct.Context.html#method.exec_all
+    /// [`vars::Vars`]: ../vars/type.Vars.html
+    pub fn exec_all_step<F>(context: Self, prompt: &mut F) -> Result<Vars>
+    where
+        F: FnMut(&str) -> Result<StepAnswer>,
+    {
+        let mut context = context;
+        while let Some(next_task) = context.tasks.first() {
+            let task_name = context.get_task_name(next_task);
+            context = match prompt(&task_name)? {
+                StepAnswer::Yes => context.exec_task()?,
+                StepAnswer::No => {
+                    info!(target: "task", "[{}] - skipped", task_name);
+                    Self {
+                        tasks: context.tasks[1..].to_vec(),
+                        vars: context.vars,
+                    }
+                }
+                StepAnswer::Continue => return Self::exec_all(context),
+            };
+        }
+        Ok(context.vars)
+    }
+
     #[cfg(test)]
     pub fn test_example() -> Self {
         Context {
@@ -76,3 +160,86 @@ impl Context {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::task::parse_tasks;
+
+    use std::io::Cursor;
+
+    use yaml_rust::YamlLoader;
+
+    fn get_context() -> Context {
+        let s = r#"
+        - name: first
+          set_vars:
+            foo: boo
+        - name: second
+          set_vars:
+            bar: yea
+        - name: third
+          set_vars:
+            baz: "{{ foo | default(value='none') }}"
+        "#;
+        let out = YamlLoader::load_from_str(s).unwrap();
+        Context::new(parse_tasks(out.first().unwrap()).unwrap(), Vars::new())
+    }
+
+    /// Prompt answering with `input` lines and recording asked task names in `asked`.
+    fn scripted_prompt<'a>(
+        input: &'a str,
+        asked: &'a mut Vec<String>,
+    ) -> impl FnMut(&str) -> Result<StepAnswer> + 'a {
+        let mut reader = Cursor::new(input.as_bytes());
+        move |task_name: &str| {
+            asked.push(task_name.to_string());
+            prompt_step(&mut reader, &mut Vec::new(), task_name)
+        }
+    }
+
+    #[test]
+    fn test_prompt_step() {
+        let mut output = Vec::new();
+        let answer = prompt_step(&mut Cursor::new("boo\nY\n"), &mut output, "first").unwrap();
+        assert_eq!(answer, StepAnswer::Yes);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Perform task: first (N)o/(y)es/(c)ontinue: invalid answer: boo\n\
+            Perform task: first (N)o/(y)es/(c)ontinue: "
+        );
+        let answer = prompt_step(&mut Cursor::new("\n"), &mut Vec::new(), "first").unwrap();
+        assert_eq!(answer, StepAnswer::No);
+        let answer = prompt_step(&mut Cursor::new("continue\n"), &mut Vec::new(), "first");
+        assert_eq!(answer.unwrap(), StepAnswer::Continue);
+        let error = prompt_step(&mut Cursor::new(""), &mut Vec::new(), "first").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_exec_all_step() {
+        let mut asked = Vec::new();
+        let vars = Context::exec_all_step(
+            get_context(),
+            &mut scripted_prompt("n\ny\nyes\n", &mut asked),
+        )
+        .unwrap();
+        assert_eq!(asked, vec!["first", "second", "third"]);
+        assert!(!vars.contains_key("foo"));
+        assert_eq!(vars.get("bar").unwrap(), "yea");
+        assert_eq!(vars.get("baz").unwrap(), "none");
+    }
+
+    #[test]
+    fn test_exec_all_step_continue() {
+        let mut asked = Vec::new();
+        let vars =
+            Context::exec_all_step(get_context(), &mut scripted_prompt("n\nc\n", &mut asked))
+                .unwrap();
+        assert_eq!(asked, vec!["first", "second"]);
+        assert!(!vars.contains_key("foo"));
+        assert_eq!(vars.get("bar").unwrap(), "yea");
+        assert_eq!(vars.get("baz").unwrap(), "none");
+    }
+}
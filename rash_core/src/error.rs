@@ -31,6 +31,25 @@ struct Custom {
     error: Box<dyn error::Error + Send + Sync>,
 }
 
+/// Error annotated with where it happened, e.g.: task name or param key.
+#[derive(Debug)]
+struct ErrorContext {
+    context: String,
+    error: Error,
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.context, self.error)
+    }
+}
+
+impl error::Error for ErrorContext {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
 /// A list specifying general categories of `rash` error.
 ///
 /// This list is intended to grow over time and it is not recommended to
@@ -53,6 +72,8 @@ pub enum ErrorKind {
     SubprocessFail,
     /// Task stack is empty
     EmptyTaskStack,
+    /// Operation did not finish in the expected time.
+    Timeout,
     /// Any `rash` error not part of this list.
     Other,
 }
@@ -65,6 +86,7 @@ impl ErrorKind {
             ErrorKind::IOError => "I/O error",
             ErrorKind::SubprocessFail => "subprocess fail",
             ErrorKind::EmptyTaskStack => "task stack is empty",
+            ErrorKind::Timeout => "operation timed out",
             ErrorKind::Other => "other os error",
         }
     }
@@ -171,6 +193,33 @@ impl Error {
         }
     }
 
+    /// Wraps error with context about where it happened, keeping the same `ErrorKind`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rash_core::error::{Error, ErrorKind};
+    ///
+    /// let error = Error::new(ErrorKind::InvalidData, "oh no!")
+    ///     .context("param 'cmd'")
+    ///     .context("task 'foo'");
+    /// assert_eq!(error.kind(), ErrorKind::InvalidData);
+    /// assert_eq!("task 'foo': param 'cmd': oh no!", format!("{}", error));
+    /// ```
+    pub fn context<C>(self, context: C) -> Error
+    where
+        C: fmt::Display,
+    {
+        let kind = self.kind();
+        Self::new(
+            kind,
+            ErrorContext {
+                context: context.to_string(),
+                error: self,
+            },
+        )
+    }
+
     /// Returns the corresponding `ErrorKind` for this error.
     ///
     /// # Examples
@@ -409,6 +458,17 @@ mod test {
         assert_eq!(format!("{:?}", err), expected);
     }
 
+    #[test]
+    fn test_context() {
+        let err = Error::from(ErrorKind::NotFound).context("task 'foo'");
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert_eq!(format!("{}", err), "task 'foo': entity not found");
+        assert_eq!(
+            format!("{}", error::Error::source(&err).unwrap()),
+            "entity not found"
+        );
+    }
+
     #[test]
     fn test_downcasting() {
         #[derive(Debug)]
@@ -2,6 +2,7 @@ pub mod context;
 pub mod error;
 pub mod logger;
 pub mod modules;
+pub mod report;
 pub mod task;
 pub mod utils;
 pub mod vars;
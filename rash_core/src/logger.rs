@@ -12,6 +12,8 @@ fn log_format(out: FormatCallback, message: &fmt::Arguments, record: &log::Recor
         (log::Level::Info, "changed") => "changed: ".to_owned(),
         (log::Level::Info, "skipping") => "skipping".to_owned(),
         (log::Level::Info, "ignoring") => "[ignoring error] ".to_owned(),
+        (log::Level::Info, "retrying") => "retrying: ".to_owned(),
+        (log::Level::Info, "rescuing") => "[rescuing error] ".to_owned(),
         (log::Level::Warn, _) => "[WARNING] ".to_owned(),
         (log::Level::Error, "task") => "failed: ".to_owned(),
         (log::Level::Error, _) => "[ERROR] ".to_owned(),
@@ -32,6 +34,8 @@ fn log_format(out: FormatCallback, message: &fmt::Arguments, record: &log::Recor
                 (log::Level::Info, "ok") => Color::Green,
                 (log::Level::Info, "skipping") => Color::Blue,
                 (log::Level::Info, "ignoring") => Color::Blue,
+                (log::Level::Info, "retrying") => Color::Magenta,
+                (log::Level::Info, "rescuing") => Color::Magenta,
                 (log::Level::Info, _) => Color::White,
                 (log::Level::Warn, _) => Color::Magenta,
                 (log::Level::Error, _) => Color::Red,
@@ -59,8 +63,9 @@ fn log_format(out: FormatCallback, message: &fmt::Arguments, record: &log::Recor
     ))
 }
 
-/// Setup logging according to the specified verbosity.
-pub fn setup_logging(verbosity: u8) -> Result<()> {
+/// Setup logging according to the specified verbosity. With `to_stderr`, logs are written to
+/// stderr instead of stdout, keeping stdout for machine readable output.
+pub fn setup_logging(verbosity: u8, to_stderr: bool) -> Result<()> {
     let mut base_config = fern::Dispatch::new();
 
     base_config = match verbosity {
@@ -69,10 +74,15 @@ pub fn setup_logging(verbosity: u8) -> Result<()> {
         _ => base_config.level(log::LevelFilter::Trace),
     };
 
-    let stdout_config = fern::Dispatch::new().format(log_format).chain(io::stdout());
+    let output_config = fern::Dispatch::new().format(log_format);
+    let output_config = if to_stderr {
+        output_config.chain(io::stderr())
+    } else {
+        output_config.chain(io::stdout())
+    };
 
     base_config
-        .chain(stdout_config)
+        .chain(output_config)
         .apply()
         .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
 
@@ -1,12 +1,17 @@
-use rash_core::context::Context;
+use rash_core::context::{prompt_step, Context};
 use rash_core::error::{Error, ErrorKind};
 use rash_core::logger;
-use rash_core::task::read_file;
+use rash_core::report::{serialize, start_collecting, take_collected, Format};
+use rash_core::task::{filter_by_tags, read_file, start_at_task};
+use rash_core::utils::tera::{set_vault_decryptor, UndefinedBehavior};
+use rash_core::utils::vault::{encrypt, get_env_password, read_password_file, PasswordDecryptor};
 use rash_core::vars::builtin::Builtins;
-use rash_core::vars::env;
+use rash_core::vars::{env, merge_vars, Precedence, Vars};
 
+use std::io::{stdin, stdout, Read};
 use std::path::Path;
 use std::process::exit;
+use std::str::FromStr;
 
 use clap::{crate_description, crate_version, Clap};
 
@@ -36,13 +41,43 @@ where
 )]
 struct Opts {
     /// Script file to be executed
-    script_file: String,
+    #[clap(required_unless_present = "vault-encrypt")]
+    script_file: Option<String>,
     /// Verbose mode (-vv for more)
     #[clap(short, long, parse(from_occurrences))]
     verbose: u8,
     /// Set environment variables (Example: KEY=VALUE)
     #[clap(short, long, parse(try_from_str = parse_key_val), number_of_values = 1)]
     environment: Vec<(String, String)>,
+    /// Set extra vars, which override any other vars (Example: KEY=VALUE)
+    #[clap(long, parse(try_from_str = parse_key_val), number_of_values = 1)]
+    extra_vars: Vec<(String, String)>,
+    /// Only execute tasks tagged with these values
+    #[clap(short, long, number_of_values = 1)]
+    tags: Vec<String>,
+    /// Start the execution at the task with this name, skipping previous ones
+    #[clap(long)]
+    start_at_task: Option<String>,
+    /// Confirm each task before executing it
+    #[clap(long)]
+    step: bool,
+    /// Show changes made by modules which support it, like template
+    #[clap(long)]
+    diff: bool,
+    /// Behavior of undefined variables in templates: strict, lenient or chainable
+    #[clap(long, default_value = "strict")]
+    undefined: String,
+    /// Print task results at the end of the run in this format: json or yaml. Logs are
+    /// written to stderr instead
+    #[clap(long)]
+    output: Option<String>,
+    /// File with the password of `vault` lookup values, instead of `RASH_VAULT_PASSWORD` or
+    /// `RASH_VAULT_PASSWORD_FILE` environment variables
+    #[clap(long)]
+    vault_password_file: Option<String>,
+    /// Encrypt stdin as a `vault` lookup value and print it, without executing any script
+    #[clap(long)]
+    vault_encrypt: bool,
     /// Additional args to be accessible from builtin `{{ rash.args }}` as list of strings
     #[clap(multiple = true, takes_value = true, number_of_values = 1)]
     _args: Vec<String>,
@@ -58,6 +93,26 @@ fn crash_error(e: Error) {
     exit(e.raw_os_error().unwrap_or(1))
 }
 
+/// Return vault password from `--vault-password-file` or from environment.
+fn get_vault_password(password_file: &Option<String>) -> Result<String, Error> {
+    match password_file {
+        Some(path) => read_password_file(Path::new(path)),
+        None => get_env_password(),
+    }
+}
+
+/// Print stdin encrypted as a `vault` lookup value, without its trailing new line.
+fn vault_encrypt(password_file: &Option<String>) -> Result<(), Error> {
+    let password = get_vault_password(password_file)?;
+    let mut plaintext = String::new();
+    stdin().read_to_string(&mut plaintext)?;
+    println!(
+        "{}",
+        encrypt(plaintext.trim_end_matches(&['\r', '\n'][..]), &password)?
+    );
+    Ok(())
+}
+
 fn main() {
     let opts: Opts = Opts::parse();
 
@@ -74,28 +129,98 @@ fn main() {
         opts.verbose
     };
 
-    logger::setup_logging(verbose).expect("failed to initialize logging.");
+    let output = opts.output.as_deref().map(Format::from_str).transpose();
+    logger::setup_logging(verbose, matches!(output, Ok(Some(_))))
+        .expect("failed to initialize logging.");
     trace!("start logger");
 
-    let script_path = Path::new(&opts.script_file);
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => return crash_error(e),
+    };
+
+    if opts.vault_encrypt {
+        if let Err(e) = vault_encrypt(&opts.vault_password_file) {
+            crash_error(e)
+        };
+        return;
+    };
+
+    if opts.vault_password_file.is_some() {
+        let decryptor = get_vault_password(&opts.vault_password_file)
+            .and_then(|password| set_vault_decryptor(PasswordDecryptor::new(&password)));
+        if let Err(e) = decryptor {
+            return crash_error(e);
+        };
+    };
+
+    let undefined = match UndefinedBehavior::from_str(&opts.undefined) {
+        Ok(behavior) => behavior,
+        Err(e) => return crash_error(e),
+    };
+
+    // required by clap unless `--vault-encrypt` is passed
+    let script_file = opts.script_file.unwrap_or_default();
+    let script_path = Path::new(&script_file);
     match read_file(script_path.to_path_buf()) {
         Ok(tasks) => match env::load(opts.environment) {
             Ok(vars) => {
-                let mut new_vars = vars;
+                let mut defaults = vars;
                 match Builtins::new(
                     opts._args.iter().map(|s| &**s).collect::<Vec<&str>>(),
                     script_path,
                 ) {
-                    Ok(builtins) => new_vars.insert("rash", &builtins),
+                    Ok(builtins) => defaults.insert(
+                        "rash",
+                        &builtins
+                            .with_extra_vars(opts.extra_vars.clone())
+                            .with_undefined(undefined)
+                            .with_diff(opts.diff),
+                    ),
                     Err(e) => crash_error(e),
                 };
+                let mut extra_vars = Vars::new();
+                opts.extra_vars
+                    .iter()
+                    .for_each(|(key, value)| extra_vars.insert(key, value));
+                let new_vars = merge_vars(vec![
+                    (Precedence::Defaults, defaults),
+                    (Precedence::ExtraVars, extra_vars),
+                ]);
                 trace!("Vars: {}", &new_vars.clone().into_json().to_string());
-                match Context::exec(Context::new(tasks, new_vars)) {
-                    Ok(_) => (),
-                    Err(context_error) => match context_error.kind() {
-                        ErrorKind::EmptyTaskStack => (),
-                        _ => crash_error(context_error),
+                let tasks = filter_by_tags(tasks, &opts.tags);
+                let tasks = match &opts.start_at_task {
+                    Some(name) => match start_at_task(tasks, name) {
+                        Ok(tasks) => tasks,
+                        Err(e) => return crash_error(e),
                     },
+                    None => tasks,
+                };
+                let context = Context::new(tasks, new_vars);
+                if output.is_some() {
+                    start_collecting();
+                };
+                let executed = if opts.step {
+                    let mut prompt = |task_name: &str| {
+                        prompt_step(&mut stdin().lock(), &mut stdout(), task_name)
+                    };
+                    Context::exec_all_step(context, &mut prompt).map(|_| ())
+                } else {
+                    match Context::exec(context) {
+                        Err(context_error) if context_error.kind() != ErrorKind::EmptyTaskStack => {
+                            Err(context_error)
+                        }
+                        _ => Ok(()),
+                    }
+                };
+                if let Some(format) = output {
+                    match serialize(&take_collected(), format) {
+                        Ok(report) => println!("{}", report.trim_end()),
+                        Err(e) => crash_error(e),
+                    };
+                };
+                if let Err(e) = executed {
+                    crash_error(e)
                 };
             }
             Err(e) => crash_error(e),
@@ -0,0 +1,301 @@
+/// ANCHOR: module
+/// # apk
+///
+/// Manage packages with Alpine Linux `apk` package manager.
+///
+/// ## Parameters
+///
+/// ```yaml
+/// name:
+///   type: list
+///   description: |
+///     Packages to manage. It can be a list or a string with comma separated names.
+///     It is required unless `update_cache` or `upgrade` are set.
+/// state:
+///   type: string
+///   choices:
+///     - present
+///     - absent
+///     - latest
+///   description: |
+///     Whether packages must be installed, removed or installed and upgraded to their
+///     latest version. Defaults to present.
+/// update_cache:
+///   type: bool
+///   description: Update repository indexes before any other operation. Defaults to false.
+/// upgrade:
+///   type: bool
+///   description: Upgrade all installed packages. Defaults to false.
+/// ```
+///
+/// ## Example
+///
+/// ```yaml
+/// - apk:
+///     name:
+///       - curl
+///       - git
+///     update_cache: true
+///
+/// - apk:
+///     name: nginx
+///     state: absent
+///
+/// - apk:
+///     upgrade: true
+/// ```
+/// ANCHOR_END: module
+use crate::error::{Error, ErrorKind, Result};
+use crate::modules::package_manager::{parse_list, parse_state, run, run_checked, State};
+use crate::modules::{get_param_bool_or, validate_params, ModuleResult};
+use crate::vars::Vars;
+
+use std::process::Output;
+
+use yaml_rust::Yaml;
+
+#[derive(Debug, PartialEq)]
+struct Params {
+    name: Vec<String>,
+    state: State,
+    update_cache: bool,
+    upgrade: bool,
+}
+
+const OPTIONAL_PARAMS: &[&str] = &["name", "state", "update_cache", "upgrade"];
+
+fn parse_params(yaml: Yaml) -> Result<Params> {
+    trace!("parse params: {:?}", yaml);
+    validate_params("apk", &yaml, &[], OPTIONAL_PARAMS)?;
+    let params = Params {
+        name: parse_list(&yaml, "name")?,
+        state: parse_state(&yaml)?,
+        update_cache: get_param_bool_or(&yaml, "update_cache", false)?,
+        upgrade: get_param_bool_or(&yaml, "upgrade", false)?,
+    };
+    if params.name.is_empty() && !params.update_cache && !params.upgrade {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "one of name, update_cache or upgrade params is required",
+        ));
+    };
+    Ok(params)
+}
+
+fn is_installed<F>(runner: &mut F, name: &str) -> Result<bool>
+where
+    F: FnMut(&str, &[&str]) -> Result<Output>,
+{
+    Ok(runner("apk", &["info", "-e", name])?.status.success())
+}
+
+/// Return true if apk output reports any package installed, upgraded or removed.
+fn has_changes(stdout: &str) -> bool {
+    stdout.lines().any(|line| {
+        [
+            "Installing",
+            "Upgrading",
+            "Downgrading",
+            "Replacing",
+            "Purging",
+        ]
+        .iter()
+        .any(|action| line.contains(action))
+    })
+}
+
+/// Apply `params` calling apk through `runner`, so it can be replaced in tests.
+fn apply<F>(params: &Params, runner: &mut F) -> Result<ModuleResult>
+where
+    F: FnMut(&str, &[&str]) -> Result<Output>,
+{
+    if params.update_cache {
+        run_checked(runner, "apk", &["update"])?;
+    };
+    let mut changed = false;
+    if params.upgrade {
+        changed |= has_changes(&run_checked(runner, "apk", &["upgrade"])?);
+    };
+
+    let mut packages = Vec::new();
+    for name in params.name.iter() {
+        let installed = is_installed(runner, name)?;
+        match params.state {
+            State::Present | State::Latest if !installed => packages.push(name.as_str()),
+            State::Absent if installed => packages.push(name.as_str()),
+            _ => (),
+        }
+    }
+
+    match params.state {
+        State::Present | State::Absent if !packages.is_empty() => {
+            let command = if params.state == State::Present {
+                "add"
+            } else {
+                "del"
+            };
+            run_checked(runner, "apk", &[&[command], packages.as_slice()].concat())?;
+            changed = true;
+        }
+        State::Latest if !params.name.is_empty() => {
+            let names = params.name.iter().map(String::as_str).collect::<Vec<_>>();
+            let stdout = run_checked(
+                runner,
+                "apk",
+                &[&["add", "--upgrade"], names.as_slice()].concat(),
+            )?;
+            changed |= !packages.is_empty() || has_changes(&stdout);
+        }
+        _ => (),
+    };
+
+    Ok(ModuleResult::builder()
+        .changed(changed)
+        .extra("packages", json!(packages))
+        .build())
+}
+
+pub fn exec(optional_params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
+    let params = parse_params(optional_params)?;
+    Ok((apply(&params, &mut run)?, vars))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    use yaml_rust::YamlLoader;
+
+    /// Fake apk recording every call, where `installed` packages are reported by `info -e`.
+    fn fake_apk<'a>(
+        installed: &'a [&'a str],
+        stdout: &'a str,
+        calls: &'a mut Vec<String>,
+    ) -> impl FnMut(&str, &[&str]) -> Result<Output> + 'a {
+        move |program: &str, args: &[&str]| {
+            assert_eq!(program, "apk");
+            calls.push(args.join(" "));
+            let code = match args {
+                ["info", "-e", name] if !installed.contains(name) => 1,
+                _ => 0,
+            };
+            Ok(Output {
+                status: ExitStatus::from_raw(code << 8),
+                stdout: stdout.as_bytes().to_vec(),
+                stderr: Vec::new(),
+            })
+        }
+    }
+
+    fn get_params(yaml: &str) -> Params {
+        parse_params(YamlLoader::load_from_str(yaml).unwrap()[0].clone()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_params() {
+        assert_eq!(
+            get_params("name: curl, git\nupdate_cache: true"),
+            Params {
+                name: vec!["curl".to_string(), "git".to_string()],
+                state: State::Present,
+                update_cache: true,
+                upgrade: false,
+            }
+        );
+        assert_eq!(
+            get_params("name:\n  - curl\nstate: latest").state,
+            State::Latest
+        );
+    }
+
+    #[test]
+    fn test_parse_params_invalid() {
+        let parse = |s| parse_params(YamlLoader::load_from_str(s).unwrap()[0].clone());
+        let error = parse("state: absent").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        let error = parse("name: curl\nstate: removed").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        let error = parse("name: curl\nfoo: boo").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_apply_install_missing() {
+        let params = get_params("name:\n  - curl\n  - git");
+        let mut calls = Vec::new();
+        let result = apply(&params, &mut fake_apk(&["curl"], "", &mut calls)).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(result.get_extra(), Some(json!({"packages": ["git"]})));
+        assert_eq!(calls, vec!["info -e curl", "info -e git", "add git"]);
+    }
+
+    #[test]
+    fn test_apply_already_installed() {
+        let params = get_params("name:\n  - curl\n  - git");
+        let mut calls = Vec::new();
+        let result = apply(&params, &mut fake_apk(&["curl", "git"], "", &mut calls)).unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(result.get_extra(), Some(json!({"packages": []})));
+        assert_eq!(calls, vec!["info -e curl", "info -e git"]);
+    }
+
+    #[test]
+    fn test_apply_absent() {
+        let params = get_params("name: curl, git\nstate: absent");
+        let mut calls = Vec::new();
+        let result = apply(&params, &mut fake_apk(&["git"], "", &mut calls)).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(calls, vec!["info -e curl", "info -e git", "del git"]);
+
+        let mut calls = Vec::new();
+        let result = apply(&params, &mut fake_apk(&[], "", &mut calls)).unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(calls, vec!["info -e curl", "info -e git"]);
+    }
+
+    #[test]
+    fn test_apply_latest() {
+        let params = get_params("name: curl\nstate: latest\nupdate_cache: true");
+        let mut calls = Vec::new();
+        let stdout = "(1/1) Upgrading curl (8.4.0-r0 -> 8.5.0-r0)\nOK: 10 MiB in 20 packages\n";
+        let result = apply(&params, &mut fake_apk(&["curl"], stdout, &mut calls)).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(calls, vec!["update", "info -e curl", "add --upgrade curl"]);
+
+        let mut calls = Vec::new();
+        let stdout = "OK: 10 MiB in 20 packages\n";
+        let result = apply(&params, &mut fake_apk(&["curl"], stdout, &mut calls)).unwrap();
+        assert!(!result.get_changed());
+    }
+
+    #[test]
+    fn test_apply_upgrade() {
+        let params = get_params("upgrade: true");
+        let mut calls = Vec::new();
+        let stdout = "(1/2) Upgrading musl (1.2.4-r1 -> 1.2.4-r2)\n";
+        let result = apply(&params, &mut fake_apk(&[], stdout, &mut calls)).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(calls, vec!["upgrade"]);
+    }
+
+    #[test]
+    fn test_apply_command_fails() {
+        let params = get_params("name: curl");
+        let error = apply(&params, &mut |_: &str, _: &[&str]| {
+            Ok(Output {
+                status: ExitStatus::from_raw(1 << 8),
+                stdout: Vec::new(),
+                stderr: b"ERROR: unable to select packages".to_vec(),
+            })
+        })
+        .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::SubprocessFail);
+        assert_eq!(
+            format!("{}", error),
+            "apk add curl failed: ERROR: unable to select packages"
+        );
+    }
+}
@@ -0,0 +1,290 @@
+/// ANCHOR: module
+/// # assemble
+///
+/// Assemble a configuration file from fragments.
+///
+/// Files in `src` directory are concatenated sorted by file name. Content is only written
+/// if the assembled result differs from `dest`.
+///
+/// ## Parameters
+///
+/// ```yaml
+/// src:
+///   type: string
+///   required: true
+///   description: Directory with the fragments to assemble.
+/// dest:
+///   type: string
+///   required: true
+///   description: Path of the file where fragments are assembled.
+/// delimiter:
+///   type: string
+///   description: |
+///     String written between fragments. A newline is appended to it if it does not
+///     end in one.
+/// regexp:
+///   type: string
+///   description: Only assemble fragments whose file name matches this regex.
+/// remote_src:
+///   type: bool
+///   description: |
+///     Accepted for compatibility with Ansible. Fragments are always read byte by byte,
+///     so binary files are supported. Defaults to false.
+/// mode:
+///   type: string
+///   description: |
+///     Permissions of the destination file.
+///     Octal like `"0644"` or symbolic like `u+rwx,g=rx,o-rwx`.
+/// owner:
+///   type: string
+///   description: Name or uid of the user that should own the destination file.
+/// group:
+///   type: string
+///   description: Name or gid of the group that should own the destination file.
+/// creates:
+///   type: string
+///   description: A path which, if it already exists, makes the task to be skipped.
+/// removes:
+///   type: string
+///   description: A path which, if it does not exist, makes the task to be skipped.
+/// ```
+///
+/// ## Example
+///
+/// ```yaml
+/// - assemble:
+///     src: /etc/ssh/sshd_config.d
+///     dest: /etc/ssh/sshd_config
+///     regexp: '\.conf'
+///     delimiter: "### fragment"
+///     mode: "0600"
+/// ```
+/// ANCHOR_END: module
+use crate::error::{Error, ErrorKind, Result};
+use crate::modules::copy::{read_src, verify_file, Params as CopyParams};
+use crate::modules::{
+    check_creates_removes, get_optional_param, get_param, get_param_bool_or, get_param_mode,
+    validate_params, ModuleResult, CREATES_REMOVES_PARAMS,
+};
+use crate::vars::Vars;
+
+use std::fs::read_dir;
+use std::path::PathBuf;
+
+use regex::Regex;
+use yaml_rust::Yaml;
+
+#[derive(Debug)]
+struct Params {
+    src: String,
+    dest: String,
+    delimiter: Option<String>,
+    regexp: Option<Regex>,
+    mode: String,
+    owner: Option<String>,
+    group: Option<String>,
+}
+
+const REQUIRED_PARAMS: &[&str] = &["src", "dest"];
+const OPTIONAL_PARAMS: &[&str] = &[
+    "delimiter",
+    "regexp",
+    "remote_src",
+    "mode",
+    "owner",
+    "group",
+];
+
+fn parse_params(yaml: Yaml) -> Result<Params> {
+    trace!("parse params: {:?}", yaml);
+    validate_params(
+        "assemble",
+        &yaml,
+        REQUIRED_PARAMS,
+        &[OPTIONAL_PARAMS, CREATES_REMOVES_PARAMS].concat(),
+    )?;
+    get_param_bool_or(&yaml, "remote_src", false)?;
+    let regexp = get_optional_param(&yaml, "regexp")?
+        .map(|s| Regex::new(&s).map_err(|e| Error::new(ErrorKind::InvalidData, e)))
+        .transpose()?;
+    Ok(Params {
+        src: get_param(&yaml, "src")?,
+        dest: get_param(&yaml, "dest")?,
+        delimiter: get_optional_param(&yaml, "delimiter")?,
+        regexp,
+        mode: get_param_mode(&yaml, "0644")?,
+        owner: get_optional_param(&yaml, "owner")?,
+        group: get_optional_param(&yaml, "group")?,
+    })
+}
+
+/// Return sorted fragment files in `src`, filtered by `regexp` file name match.
+fn get_fragments(src: &str, regexp: Option<&Regex>) -> Result<Vec<PathBuf>> {
+    let mut fragments = read_dir(src)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<PathBuf>>>()?
+        .into_iter()
+        .filter(|path| path.is_file())
+        .filter(|path| match regexp {
+            // read_dir entries always have a file name
+            Some(re) => re.is_match(&path.file_name().unwrap().to_string_lossy()),
+            None => true,
+        })
+        .collect::<Vec<PathBuf>>();
+    fragments.sort();
+    Ok(fragments)
+}
+
+fn assemble(params: &Params) -> Result<Vec<u8>> {
+    let delimiter = params.delimiter.as_ref().map(|delimiter| {
+        if delimiter.ends_with('\n') {
+            delimiter.clone()
+        } else {
+            format!("{}\n", delimiter)
+        }
+    });
+    let mut content = Vec::new();
+    for (index, fragment) in get_fragments(&params.src, params.regexp.as_ref())?
+        .iter()
+        .enumerate()
+    {
+        if let (true, Some(delimiter)) = (index > 0, &delimiter) {
+            content.extend_from_slice(delimiter.as_bytes());
+        };
+        content.extend(read_src(&fragment.to_string_lossy())?);
+    }
+    Ok(content)
+}
+
+pub fn exec(optional_params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
+    let skipped = check_creates_removes(&optional_params)?;
+    let params = parse_params(optional_params)?;
+    if let Some(result) = skipped {
+        return Ok((result, vars));
+    };
+    if !PathBuf::from(&params.src).is_dir() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("src {} is not a directory", params.src),
+        ));
+    };
+    let content = assemble(&params)?;
+    let result = verify_file(CopyParams::new(
+        content,
+        params.dest,
+        params.mode,
+        params.owner,
+        params.group,
+    ))?;
+    Ok((result, vars))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::{read_to_string, write};
+
+    use tempfile::tempdir;
+    use yaml_rust::YamlLoader;
+
+    fn get_yaml(s: &str) -> Yaml {
+        YamlLoader::load_from_str(s).unwrap()[0].clone()
+    }
+
+    fn create_fragments(dir: &std::path::Path) {
+        write(dir.join("20-boo.conf"), "boo\n").unwrap();
+        write(dir.join("10-foo.conf"), "foo\n").unwrap();
+        write(dir.join("30-zoo.txt"), "zoo\n").unwrap();
+    }
+
+    #[test]
+    fn test_exec() {
+        let src_dir = tempdir().unwrap();
+        create_fragments(src_dir.path());
+        let dest_dir = tempdir().unwrap();
+        let dest = dest_dir.path().join("assembled");
+        let yaml = get_yaml(&format!(
+            "src: {}\ndest: {}",
+            src_dir.path().display(),
+            dest.display()
+        ));
+
+        let (result, _) = exec(yaml.clone(), Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(read_to_string(&dest).unwrap(), "foo\nboo\nzoo\n");
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(read_to_string(&dest).unwrap(), "foo\nboo\nzoo\n");
+    }
+
+    #[test]
+    fn test_exec_delimiter_and_regexp() {
+        let src_dir = tempdir().unwrap();
+        create_fragments(src_dir.path());
+        let dest_dir = tempdir().unwrap();
+        let dest = dest_dir.path().join("assembled");
+        let yaml = get_yaml(&format!(
+            "src: {}\ndest: {}\ndelimiter: '# ---'\nregexp: '\\.conf$'",
+            src_dir.path().display(),
+            dest.display()
+        ));
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(read_to_string(&dest).unwrap(), "foo\n# ---\nboo\n");
+    }
+
+    #[test]
+    fn test_exec_fragment_changed() {
+        let src_dir = tempdir().unwrap();
+        create_fragments(src_dir.path());
+        let dest_dir = tempdir().unwrap();
+        let dest = dest_dir.path().join("assembled");
+        let yaml = get_yaml(&format!(
+            "src: {}\ndest: {}",
+            src_dir.path().display(),
+            dest.display()
+        ));
+        let _ = exec(yaml.clone(), Vars::new()).unwrap();
+
+        write(src_dir.path().join("20-boo.conf"), "boo boo\n").unwrap();
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(read_to_string(&dest).unwrap(), "foo\nboo boo\nzoo\n");
+    }
+
+    #[test]
+    fn test_exec_removes() {
+        let src_dir = tempdir().unwrap();
+        create_fragments(src_dir.path());
+        let dest_dir = tempdir().unwrap();
+        let dest = dest_dir.path().join("assembled");
+        let yaml = get_yaml(&format!(
+            "src: {}\ndest: {}\nremoves: {}",
+            src_dir.path().display(),
+            dest.display(),
+            dest_dir.path().join("missing").display()
+        ));
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(result.get_extra(), Some(json!({"skipped": true})));
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn test_exec_src_not_dir() {
+        let yaml = get_yaml("src: /tmp/this/does/not/exist\ndest: /tmp/foo");
+        let error = exec(yaml, Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_parse_params_invalid_regexp() {
+        let yaml = get_yaml("src: /tmp\ndest: /tmp/foo\nregexp: '('");
+        let error = parse_params(yaml).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -25,7 +25,7 @@
 /// ```
 /// ANCHOR_END: module
 use crate::error::{Error, ErrorKind, Result};
-use crate::modules::{get_param_list, ModuleResult};
+use crate::modules::{get_param_list, validate_params, ModuleResult};
 use crate::utils::tera::is_render_string;
 use crate::vars::Vars;
 
@@ -38,6 +38,7 @@ pub struct Params {
 
 fn parse_params(yaml: Yaml) -> Result<Params> {
     trace!("parse params: {:?}", yaml);
+    validate_params("assert", &yaml, &["that"], &[])?;
 
     Ok(Params {
         that: get_param_list(&yaml, "that")?,
@@ -59,11 +60,7 @@ fn verify_conditions(params: Params, vars: Vars) -> Result<ModuleResult> {
             }
         })
         .collect::<Result<Vec<bool>>>()?;
-    Ok(ModuleResult {
-        changed: false,
-        output: None,
-        extra: None,
-    })
+    Ok(ModuleResult::builder().build())
 }
 
 pub fn exec(optional_params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
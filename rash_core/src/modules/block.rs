@@ -0,0 +1,127 @@
+/// ANCHOR: module
+/// # block
+///
+/// Group tasks. Combined with `rescue` and `always` task fields, it allows error handling:
+/// `rescue` tasks are executed if any task in the block fails and, if they succeed, the
+/// failure is cleared. `always` tasks are executed in any case.
+///
+/// Block result is changed if any of its tasks changed.
+///
+/// ## Parameters
+///
+/// ```yaml
+/// tasks:
+///   type: list
+///   required: true
+///   description: |
+///     List of tasks passed directly as block value.
+/// ```
+///
+/// ## Example
+///
+/// ```yaml
+/// - block:
+///     - command: /bin/false
+///   rescue:
+///     - command: echo "block failed"
+///   always:
+///     - command: echo "this is always executed"
+/// ```
+/// ANCHOR_END: module
+use crate::context::Context;
+use crate::error::Result;
+use crate::modules::ModuleResult;
+use crate::report::{start_collecting, take_collected};
+use crate::task::parse_tasks;
+use crate::vars::Vars;
+
+use yaml_rust::Yaml;
+
+pub fn exec(params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
+    let tasks = parse_tasks(&params)?;
+    start_collecting();
+    let executed = Context::exec_all(Context::new(tasks, vars));
+    // always taken, so failures do not leave the collection open
+    let results = take_collected();
+    let new_vars = executed?;
+    let changed = results.iter().any(ModuleResult::get_changed);
+    Ok((ModuleResult::builder().changed(changed).build(), new_vars))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::error::ErrorKind;
+
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn test_exec() {
+        let yaml = YamlLoader::load_from_str(
+            r#"
+            - set_vars:
+                foo: boo
+            - assert:
+                that:
+                  - foo == 'boo'
+            "#,
+        )
+        .unwrap()[0]
+            .clone();
+        let (result, vars) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(vars.get("foo").unwrap(), "boo");
+    }
+
+    #[test]
+    fn test_exec_changed() {
+        let yaml = YamlLoader::load_from_str(
+            r#"
+            - set_vars:
+                foo: boo
+            - command: echo foo
+            "#,
+        )
+        .unwrap()[0]
+            .clone();
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(result.get_changed());
+    }
+
+    #[test]
+    fn test_exec_nested_changed() {
+        let yaml = YamlLoader::load_from_str(
+            r#"
+            - block:
+                - command: echo boo
+            "#,
+        )
+        .unwrap()[0]
+            .clone();
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(result.get_changed());
+    }
+
+    #[test]
+    fn test_exec_fail() {
+        let yaml = YamlLoader::load_from_str(
+            r#"
+            - assert:
+                that:
+                  - "1 == 2"
+            "#,
+        )
+        .unwrap()[0]
+            .clone();
+        let error = exec(yaml, Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Other);
+    }
+
+    #[test]
+    fn test_exec_not_list() {
+        let yaml = YamlLoader::load_from_str("foo: boo").unwrap()[0].clone();
+        let error = exec(yaml, Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
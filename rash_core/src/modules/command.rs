@@ -19,11 +19,35 @@
 ///   description: |
 ///     Execute command as PID 1.
 ///     Note: from this point on, your rash script execution is transfered to the command.
+/// stream:
+///   type: bool
+///   description: |
+///     Log stdout and stderr lines while the command is running instead of only
+///     returning them when it finishes. Defaults to false.
+/// timeout:
+///   type: integer
+///   description: |
+///     Seconds to wait for the command. If it is exceeded, the command and every
+///     process started by it are killed and the task fails.
+/// creates:
+///   type: string
+///   description: A path which, if it already exists, makes the task to be skipped.
+/// removes:
+///   type: string
+///   description: A path which, if it does not exist, makes the task to be skipped.
 /// ```
 /// ## Example
 ///
 /// ```yaml
 /// - command:
+///     cmd: tar -xf /tmp/rash.tar.gz -C /opt
+///     creates: /opt/rash
+///
+/// - command:
+///     cmd: apt-get dist-upgrade -y
+///     stream: true
+///
+/// - command:
 ///     argv:
 ///       - echo
 ///       - "Hellow World"'
@@ -31,10 +55,18 @@
 /// ```
 /// ANCHOR_END: module
 use crate::error::{Error, ErrorKind, Result};
-use crate::modules::{get_param_bool, get_param_list, ModuleResult};
+use crate::modules::{
+    check_creates_removes, get_param_bool_or, get_param_list, get_param_u64, validate_params,
+    ModuleResult, CREATES_REMOVES_PARAMS,
+};
 use crate::vars::Vars;
 
-use std::process::Command;
+use std::io::{BufRead, BufReader, Read};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use exec as exec_command;
 use yaml_rust::Yaml;
@@ -44,20 +76,56 @@ struct Params {
     cmd: Option<String>,
     argv: Option<Vec<String>>,
     transfer_pid_1: bool,
+    timeout: Option<u64>,
+    stream: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+impl OutputStream {
+    fn as_str(&self) -> &'static str {
+        match self {
+            OutputStream::Stdout => "stdout",
+            OutputStream::Stderr => "stderr",
+        }
+    }
 }
 
+/// Function called with every output line, without trailing newline, as soon as it is read.
+type LineCallback = Arc<dyn Fn(OutputStream, &str) + Send + Sync>;
+
+/// Interval between checks of child process status while waiting with timeout.
+const WAIT_INTERVAL: Duration = Duration::from_millis(10);
+
 fn parse_params(yaml: Yaml) -> Result<Params> {
     trace!("parse params: {:?}", yaml);
+    validate_params(
+        "command",
+        &yaml,
+        &[],
+        &[
+            &["cmd", "argv", "transfer_pid_1", "timeout", "stream"],
+            CREATES_REMOVES_PARAMS,
+        ]
+        .concat(),
+    )?;
     let cmd = yaml
         .as_str()
         .or_else(|| yaml["cmd"].as_str())
         .map(String::from);
 
     let argv = get_param_list(&yaml, "argv").ok();
-    let transfer_pid_1 = get_param_bool(&yaml, "transfer_pid_1").or_else(|e| match e.kind() {
-        ErrorKind::NotFound => Ok(false),
-        _ => Err(e),
-    })?;
+    let transfer_pid_1 = get_param_bool_or(&yaml, "transfer_pid_1", false)?;
+    let stream = get_param_bool_or(&yaml, "stream", false)?;
+    let timeout = match get_param_u64(&yaml, "timeout") {
+        Ok(timeout) => Some(timeout),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
 
     if cmd.is_none() & argv.is_none() {
         return Err(Error::new(
@@ -70,12 +138,123 @@ fn parse_params(yaml: Yaml) -> Result<Params> {
         cmd,
         argv,
         transfer_pid_1,
+        timeout,
+        stream,
+    })
+}
+
+/// Read `pipe` until it is closed, passing each line to `on_line` if it is set.
+fn read_pipe<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    stream: OutputStream,
+    on_line: Option<LineCallback>,
+) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        match (pipe, on_line) {
+            (Some(mut pipe), None) => {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            (Some(pipe), Some(on_line)) => {
+                let mut reader = BufReader::new(pipe);
+                loop {
+                    let start = buf.len();
+                    match reader.read_until(b'\n', &mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(_) => {
+                            let line = String::from_utf8_lossy(&buf[start..]);
+                            on_line(stream, line.trim_end_matches('\n'));
+                        }
+                    }
+                }
+            }
+            (None, _) => (),
+        };
+        buf
+    })
+}
+
+/// Kill `child` process group, so processes started by it are killed too, and wait for it.
+fn kill_process_group(child: &mut Child) -> Result<()> {
+    // safe: it only sends a signal to the group created with `child` as leader
+    if unsafe { libc::killpg(child.id() as libc::pid_t, libc::SIGKILL) } != 0 {
+        return Err(Error::from(std::io::Error::last_os_error()));
+    };
+    child.wait()?;
+    Ok(())
+}
+
+/// Wait for `child` output, killing its process group if `timeout` is exceeded.
+fn wait_child(
+    mut child: Child,
+    timeout: Option<Duration>,
+    on_line: Option<LineCallback>,
+) -> Result<Output> {
+    // read pipes in background to avoid blocking the child when they are full
+    let stdout = read_pipe(child.stdout.take(), OutputStream::Stdout, on_line.clone());
+    let stderr = read_pipe(child.stderr.take(), OutputStream::Stderr, on_line);
+    let start = Instant::now();
+    let status = match timeout {
+        None => child.wait()?,
+        Some(timeout) => loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            };
+            if start.elapsed() >= timeout {
+                kill_process_group(&mut child)?;
+                return Err(Error::new(
+                    ErrorKind::Timeout,
+                    format!("command timed out after {} seconds", timeout.as_secs()),
+                ));
+            };
+            thread::sleep(WAIT_INTERVAL);
+        },
+    };
+    let join_error = |_| Error::new(ErrorKind::SubprocessFail, "cannot read command output");
+    Ok(Output {
+        status,
+        stdout: stdout.join().map_err(join_error)?,
+        stderr: stderr.join().map_err(join_error)?,
     })
 }
 
+fn run(
+    mut command: Command,
+    timeout: Option<u64>,
+    on_line: Option<LineCallback>,
+) -> Result<Output> {
+    if timeout.is_none() && on_line.is_none() {
+        return command
+            .output()
+            .map_err(|e| Error::new(ErrorKind::SubprocessFail, e));
+    };
+    if timeout.is_some() {
+        // own process group, created with setpgid, to kill processes started by the command
+        command.process_group(0);
+    };
+    wait_child(
+        command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| Error::new(ErrorKind::SubprocessFail, e))?,
+        timeout.map(Duration::from_secs),
+        on_line,
+    )
+}
+
+/// Log each line with its stream as target.
+fn log_line(stream: OutputStream, line: &str) {
+    info!(target: stream.as_str(), "{}", line);
+}
+
 pub fn exec(optional_params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
+    let skipped = check_creates_removes(&optional_params)?;
     let params = parse_params(optional_params)?;
     trace!("exec - params: {:?}", params);
+    if let Some(result) = skipped {
+        return Ok((result, vars));
+    };
 
     if params.transfer_pid_1 {
         let args_vec = match params.cmd {
@@ -97,12 +276,11 @@ pub fn exec(optional_params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
         return Err(Error::new(ErrorKind::SubprocessFail, error));
     }
 
-    let output = if params.cmd.is_some() {
-        Command::new("/bin/sh")
-            // safe unwrap: verify in parse_params
-            .args(vec!["-c", &params.cmd.unwrap()])
-            .output()
-            .map_err(|e| Error::new(ErrorKind::SubprocessFail, e))?
+    let command = if params.cmd.is_some() {
+        let mut command = Command::new("/bin/sh");
+        // safe unwrap: verify in parse_params
+        command.args(vec!["-c", &params.cmd.unwrap()]);
+        command
     } else {
         // safe unwrap: verify in parse_params
         let argv = params.argv.unwrap();
@@ -111,47 +289,165 @@ pub fn exec(optional_params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
             .next()
             .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("{:?} invalid cmd", args)))?;
 
-        Command::new(program)
-            .args(args)
-            .output()
-            .map_err(|e| Error::new(ErrorKind::SubprocessFail, e))?
+        let mut command = Command::new(program);
+        command.args(args);
+        command
+    };
+    let on_line: Option<LineCallback> = if params.stream {
+        Some(Arc::new(log_line))
+    } else {
+        None
     };
+    let output = run(command, params.timeout, on_line)?;
 
     trace!("exec - output: {:?}", output);
     let stderr =
         String::from_utf8(output.stderr).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
 
-    if !output.status.success() {
-        return Err(Error::new(ErrorKind::InvalidData, stderr));
-    }
     let output_string =
         String::from_utf8(output.stdout).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
 
-    let module_output = if output_string.is_empty() {
-        None
+    // non-zero rc is returned as a failed result to be able to register it
+    let builder = ModuleResult::builder()
+        .changed(true)
+        .failed(!output.status.success())
+        .rc(output.status.code())
+        .stdout(&output_string)
+        .stderr(stderr);
+    let module_result = if output_string.is_empty() {
+        builder.build()
     } else {
-        Some(output_string)
+        builder.output(output_string).build()
     };
 
-    Ok((
-        ModuleResult {
-            changed: true,
-            output: module_output,
-            extra: Some(json!({
-                "rc": output.status.code(),
-                "stderr": stderr,
-            })),
-        },
-        vars,
-    ))
+    Ok((module_result, vars))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use std::sync::Mutex;
+
+    use tempfile::tempdir;
     use yaml_rust::YamlLoader;
 
+    #[test]
+    fn test_exec_non_zero_rc() {
+        let yaml =
+            YamlLoader::load_from_str("sh -c 'echo foo; echo boo >&2; exit 2'").unwrap()[0].clone();
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(result.get_failed());
+        assert_eq!(result.get_failed_msg(), "boo\n");
+        assert_eq!(
+            result.get_extra(),
+            Some(json!({"rc": 2, "stdout": "foo\n", "stderr": "boo\n"}))
+        );
+    }
+
+    #[test]
+    fn test_exec_success() {
+        let yaml = YamlLoader::load_from_str("echo foo").unwrap()[0].clone();
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_failed());
+        assert_eq!(result.get_output(), Some("foo\n".to_string()));
+        assert_eq!(
+            result.get_extra(),
+            Some(json!({"rc": 0, "stdout": "foo\n", "stderr": ""}))
+        );
+    }
+
+    #[test]
+    fn test_exec_creates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("created");
+        let yaml =
+            YamlLoader::load_from_str(&format!("cmd: touch {0}\ncreates: {0}", path.display()))
+                .unwrap()[0]
+                .clone();
+
+        let (result, _) = exec(yaml.clone(), Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert!(path.exists());
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(result.get_extra(), Some(json!({"skipped": true})));
+    }
+
+    #[test]
+    fn test_exec_removes_missing() {
+        let yaml = YamlLoader::load_from_str("cmd: exit 1\nremoves: /tmp/this/does/not/exist")
+            .unwrap()[0]
+            .clone();
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_failed());
+        assert_eq!(result.get_extra(), Some(json!({"skipped": true})));
+    }
+
+    #[test]
+    fn test_exec_timeout() {
+        let yaml = YamlLoader::load_from_str("cmd: sleep 5\ntimeout: 1").unwrap()[0].clone();
+        let start = Instant::now();
+        let error = exec(yaml, Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Timeout);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    /// Return true if process with `pid` exists and it is not a zombie.
+    fn is_running(pid: &str) -> bool {
+        std::fs::read_to_string(format!("/proc/{}/stat", pid))
+            .ok()
+            .and_then(|stat| {
+                // state is the first field after command name
+                let state = stat.rsplit_once(") ")?.1.split(' ').next()?.to_string();
+                Some(state != "Z")
+            })
+            .unwrap_or(false)
+    }
+
+    #[test]
+    fn test_exec_timeout_kills_process_group() {
+        let dir = tempdir().unwrap();
+        let pid_path = dir.path().join("pid");
+        let yaml = YamlLoader::load_from_str(&format!(
+            "cmd: \"sleep 5 & echo $! > {}; wait; true\"\ntimeout: 1",
+            pid_path.display()
+        ))
+        .unwrap()[0]
+            .clone();
+        let start = Instant::now();
+        let error = exec(yaml, Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Timeout);
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let sleep_pid = std::fs::read_to_string(pid_path)
+            .unwrap()
+            .trim()
+            .to_string();
+        let killed = (0..10).any(|_| {
+            thread::sleep(Duration::from_millis(100));
+            !is_running(&sleep_pid)
+        });
+        assert!(killed, "sleep {} is still running", sleep_pid);
+    }
+
+    #[test]
+    fn test_exec_timeout_not_exceeded() {
+        let yaml =
+            YamlLoader::load_from_str("argv:\n  - echo\n  - foo\ntimeout: 5").unwrap()[0].clone();
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_failed());
+        assert_eq!(result.get_output(), Some("foo\n".to_string()));
+    }
+
+    #[test]
+    fn test_parse_params_timeout_invalid() {
+        let yaml = YamlLoader::load_from_str("cmd: ls\ntimeout: foo").unwrap()[0].clone();
+        let error = parse_params(yaml).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
     #[test]
     fn test_parse_params() {
         let yaml = YamlLoader::load_from_str("ls")
@@ -166,7 +462,53 @@ mod tests {
                 cmd: Some("ls".to_string()),
                 argv: None,
                 transfer_pid_1: false,
+                timeout: None,
+                stream: false,
             }
         );
     }
+
+    #[test]
+    fn test_run_stream() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let received = lines.clone();
+        let on_line: LineCallback = Arc::new(move |stream, line: &str| {
+            received
+                .lock()
+                .unwrap()
+                .push((stream, line.to_string(), Instant::now()));
+        });
+        let mut command = Command::new("/bin/sh");
+        command.args(vec![
+            "-c",
+            "echo foo; sleep 0.5; echo boo >&2; sleep 0.5; printf last",
+        ]);
+
+        let output = run(command, None, Some(on_line)).unwrap();
+        let finished = Instant::now();
+        assert_eq!(output.stdout, b"foo\nlast");
+        assert_eq!(output.stderr, b"boo\n");
+
+        let lines = lines.lock().unwrap();
+        assert_eq!(
+            lines
+                .iter()
+                .map(|(stream, line, _)| (*stream, line.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (OutputStream::Stdout, "foo"),
+                (OutputStream::Stderr, "boo"),
+                (OutputStream::Stdout, "last"),
+            ]
+        );
+        assert!(finished.duration_since(lines[0].2) >= Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_exec_stream() {
+        let yaml =
+            YamlLoader::load_from_str("cmd: echo foo; echo boo\nstream: true").unwrap()[0].clone();
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert_eq!(result.get_output(), Some("foo\nboo\n".to_string()));
+    }
 }
@@ -3,20 +3,92 @@
 ///
 /// Copy files to path.
 ///
+/// The result extra holds dest content sha256 as `checksum`. When `src` is a directory,
+/// `checksum` maps each copied file path to its sha256.
+///
 /// ## Parameters
 ///
 /// ```yaml
 /// content:
 ///   type: string
-///   required: true
-///   description: Sets the contents of a file directly to the specified value.
+///   description: |
+///     Sets the contents of a file directly to the specified value.
+///     Required if `src` is not set.
+/// src:
+///   type: string
+///   description: |
+///     Path of the file to copy. Its content is copied byte by byte, so binary files
+///     are supported.
+///     If it is a directory, whole tree is copied recursively. With a trailing `/`
+///     only its content is copied, otherwise the directory itself is copied.
+///     Symlinks in the tree are copied as symlinks, without following them.
+///     Required if `content` is not set.
+/// remote_src:
+///   type: bool
+///   description: |
+///     If true, `src` is a file already on the target and it is copied directly,
+///     comparing checksums instead of loading its content, which suits big files.
+///     Requires `src`. Files of a `src` directory are always copied this way.
+///     Defaults to false.
 /// dest:
 ///   type: string
 ///   required: true
-///   description: The absolute path where the file should be copied to.
+///   description: |
+///     The absolute path where the file should be copied to.
+///     If it ends in `/` or it is an existing directory, file is copied inside it
+///     using `src` basename.
+///     Missing parent directories are created just before writing it.
 /// mode:
 ///   type: string
-///   description: Permissions of the destination file or directory.
+///   description: |
+///     Permissions of the destination file or directory.
+///     Octal like `"0644"` or symbolic like `u+rwx,g=rx,o-rwx`.
+///     `preserve` sets the same permissions as `src` files.
+/// directory_mode:
+///   type: string
+///   description: |
+///     Permissions of directories created during a recursive copy.
+///     Result extra lists as `files` the paths whose content, mode or ownership changed.
+/// owner:
+///   type: string
+///   description: |
+///     Name or uid of the user that should own the destination file.
+///     In a recursive copy it is applied to every file and directory of the tree.
+/// group:
+///   type: string
+///   description: |
+///     Name or gid of the group that should own the destination file.
+///     In a recursive copy it is applied to every file and directory of the tree.
+/// force:
+///   type: bool
+///   description: |
+///     If false, file is only copied if destination does not exist. Defaults to true.
+/// checksum:
+///   type: string
+///   description: |
+///     Expected sha256 of the file content. If it does not match, dest is not written
+///     and the task fails. Not supported when `src` is a directory.
+/// backup:
+///   type: bool
+///   description: |
+///     Create a timestamped copy of dest before changing its content, returned as
+///     `backup_file` in the result. Defaults to false.
+/// keep_backups:
+///   type: integer
+///   description: |
+///     Number of backups of dest to keep when `backup` is true. Older ones are removed
+///     after creating a new backup. By default, all backups are kept.
+/// validate:
+///   type: string
+///   description: |
+///     Command to validate the new content before writing dest, with `%s` replaced by
+///     the path of a temporary file holding it. If it fails, dest is not modified.
+///     Not supported when `src` is a directory.
+/// unsafe_writes:
+///   type: bool
+///   description: |
+///     If dest cannot be atomically replaced, like bind mounted files, truncate and
+///     write it in place. Defaults to false.
 /// ```
 ///
 /// ## Examples
@@ -26,124 +98,613 @@
 ///     content: "supersecret"
 ///     dest: /tmp/MY_PASSWORD_FILE.txt
 ///     mode: "0400"
+///
+/// - copy:
+///     content: "rash ALL=(ALL) NOPASSWD: ALL"
+///     dest: /etc/sudoers.d/rash
+///     validate: visudo -cf %s
+///
+/// - copy:
+///     src: /usr/bin/rash
+///     dest: /tmp/
+///     remote_src: true
+///
+/// - copy:
+///     src: /etc/rash/
+///     dest: /tmp/rash
+///     mode: "0600"
+///     directory_mode: "0700"
 /// ```
 /// ANCHOR_END: module
-use crate::error::{ErrorKind, Result};
-use crate::modules::{get_param, ModuleResult};
-use crate::utils::parse_octal;
+use crate::error::{Error, ErrorKind, Result};
+use crate::modules::{
+    get_optional_param, get_param, get_param_bool_or, get_param_mode, get_param_u64, parse_mode,
+    resolve_preserve_mode, validate_params, ModuleResult,
+};
+use crate::utils::file::{backup, get_checksum, get_tmp_path, remove_old_backups, replace_dest};
+use crate::utils::user::{get_gid, get_uid};
 use crate::vars::Vars;
 
-use std::fs::{set_permissions, OpenOptions};
-use std::io::prelude::*;
-use std::io::SeekFrom;
-use std::io::{BufReader, Write};
-use std::os::unix::fs::PermissionsExt;
+use std::fs::{
+    copy, create_dir_all, read, read_dir, read_link, remove_file, rename, set_permissions, write,
+};
+use std::os::unix::fs::{chown, symlink, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
 use yaml_rust::Yaml;
 
+/// Content to write to dest.
+#[derive(Debug, PartialEq)]
+pub enum Content {
+    /// Content in memory, like `content` param or rendered templates.
+    Bytes(Vec<u8>),
+    /// File already on the target, copied directly to dest.
+    File(String),
+}
+
+impl Content {
+    fn get_checksum(&self) -> Result<String> {
+        match self {
+            Content::Bytes(content) => Ok(format!("{:x}", Sha256::digest(content))),
+            Content::File(src) => get_checksum(Path::new(src)),
+        }
+    }
+
+    /// Return true if `dest` content is different.
+    fn differs(&self, dest: &Path) -> Result<bool> {
+        match self {
+            Content::Bytes(content) => Ok(&read(dest)? != content),
+            Content::File(_) => Ok(self.get_checksum()? != get_checksum(dest)?),
+        }
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        match self {
+            Content::Bytes(content) => write(path, content)?,
+            Content::File(src) => {
+                copy(src, path)?;
+            }
+        };
+        Ok(())
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Params {
-    content: String,
+    content: Content,
     dest: String,
-    mode: u32,
+    mode: String,
+    owner: Option<String>,
+    group: Option<String>,
+    force: bool,
+    checksum: Option<String>,
+    backup: bool,
+    keep_backups: Option<usize>,
+    unsafe_writes: bool,
+    validate: Option<String>,
 }
 
 impl Params {
-    pub fn new(content: String, dest: String, mode: u32) -> Self {
+    pub fn new(
+        content: Vec<u8>,
+        dest: String,
+        mode: String,
+        owner: Option<String>,
+        group: Option<String>,
+    ) -> Self {
         Params {
-            content,
+            content: Content::Bytes(content),
             dest,
             mode,
+            owner,
+            group,
+            force: true,
+            checksum: None,
+            backup: false,
+            keep_backups: None,
+            unsafe_writes: false,
+            validate: None,
         }
     }
 
+    /// Set command validating content before it is written, see [`run_validate`].
+    ///
+    /// [`run_validate`]: fn.run_validate.html
+    pub fn with_validate(mut self, validate: Option<String>) -> Self {
+        self.validate = validate;
+        self
+    }
+
     #[cfg(test)]
     pub fn get_content(&self) -> String {
-        self.content.clone()
+        match &self.content {
+            Content::Bytes(content) => String::from_utf8(content.clone()).unwrap(),
+            Content::File(src) => std::fs::read_to_string(src).unwrap(),
+        }
+    }
+}
+
+const REQUIRED_PARAMS: &[&str] = &["dest"];
+const OPTIONAL_PARAMS: &[&str] = &[
+    "content",
+    "src",
+    "remote_src",
+    "mode",
+    "directory_mode",
+    "owner",
+    "group",
+    "force",
+    "checksum",
+    "backup",
+    "keep_backups",
+    "validate",
+    "unsafe_writes",
+];
+
+#[derive(Debug, PartialEq)]
+struct DirParams {
+    src: String,
+    dest: String,
+    mode: String,
+    directory_mode: Option<String>,
+    owner: Option<String>,
+    group: Option<String>,
+    force: bool,
+    backup: bool,
+    keep_backups: Option<usize>,
+    unsafe_writes: bool,
+}
+
+/// Parse `keep_backups` param, which must be greater than 0 to keep the backup just created.
+pub fn parse_keep_backups_param(yaml: &Yaml) -> Result<Option<usize>> {
+    match get_param_u64(yaml, "keep_backups") {
+        Ok(0) => Err(Error::new(
+            ErrorKind::InvalidData,
+            "param 'keep_backups' must be greater than 0",
+        )),
+        Ok(keep_backups) => Ok(Some(keep_backups as usize)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// [`backup`] `dest` and, if `keep_backups` is set, remove its older backups except the newest
+/// `keep_backups` ones.
+///
+/// [`backup`]: ../../utils/file/fn.backup.html
+pub fn backup_dest(dest: &Path, keep_backups: Option<usize>) -> Result<String> {
+    let backup_file = backup(dest)?;
+    if let Some(keep_backups) = keep_backups {
+        remove_old_backups(dest, keep_backups)?;
+    };
+    Ok(backup_file)
+}
+
+/// Parse `validate` param, which must contain `%s` to reference the file to validate.
+pub fn parse_validate_param(yaml: &Yaml) -> Result<Option<String>> {
+    let validate = get_optional_param(yaml, "validate")?;
+    match &validate {
+        Some(validate) if !validate.contains("%s") => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("param 'validate' must contain %s, found: {}", validate),
+        )),
+        _ => Ok(validate),
     }
 }
 
+/// Run `validate` command replacing `%s` with `path`, failing if it does not succeed.
+pub fn run_validate(path: &Path, validate: &str) -> Result<()> {
+    let cmd = validate.replace("%s", &path.to_string_lossy());
+    trace!("validate: {}", cmd);
+    let output = Command::new("/bin/sh")
+        .args(["-c", &cmd])
+        .output()
+        .map_err(|e| Error::new(ErrorKind::SubprocessFail, e))?;
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "failed to validate with `{}`: {}",
+                cmd,
+                String::from_utf8_lossy(&output.stderr).trim_end()
+            ),
+        ));
+    };
+    Ok(())
+}
+
+pub fn read_src(src: &str) -> Result<Vec<u8>> {
+    Ok(read(src)?)
+}
+
+fn parse_dir_params(yaml: Yaml) -> Result<DirParams> {
+    trace!("parse dir params: {:?}", yaml);
+    let directory_mode = get_optional_param(&yaml, "directory_mode")?;
+    if let Some(directory_mode) = &directory_mode {
+        parse_mode(directory_mode, 0o755, true)?;
+    };
+    Ok(DirParams {
+        src: get_param(&yaml, "src")?,
+        dest: get_param(&yaml, "dest")?,
+        mode: get_param_mode(&yaml, "0644")?,
+        directory_mode,
+        owner: get_optional_param(&yaml, "owner")?,
+        group: get_optional_param(&yaml, "group")?,
+        force: get_param_bool_or(&yaml, "force", true)?,
+        backup: get_param_bool_or(&yaml, "backup", false)?,
+        keep_backups: parse_keep_backups_param(&yaml)?,
+        unsafe_writes: get_param_bool_or(&yaml, "unsafe_writes", false)?,
+    })
+}
+
 fn parse_params(yaml: Yaml) -> Result<Params> {
     trace!("parse params: {:?}", yaml);
-    let mode_string = get_param(&yaml, "mode").or_else(|e| match e.kind() {
-        ErrorKind::NotFound => Ok("0644".to_string()),
-        _ => Err(e),
-    })?;
+    let remote_src = get_param_bool_or(&yaml, "remote_src", false)?;
+    let src = get_optional_param(&yaml, "src")?;
+    let mode_string = resolve_preserve_mode(
+        &get_param_mode(&yaml, "0644")?,
+        src.as_deref().map(Path::new),
+    )?;
+    let content = get_optional_param(&yaml, "content")?;
+    let content = match (content, &src) {
+        (Some(_), None) if remote_src => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "src param is required with remote_src",
+            ))
+        }
+        (Some(content), None) => Content::Bytes(content.into_bytes()),
+        (None, Some(src)) if remote_src => Content::File(src.clone()),
+        (None, Some(src)) => Content::Bytes(read_src(src)?),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "one of content or src params is required, but not both",
+            ))
+        }
+    };
     Ok(Params {
-        content: get_param(&yaml, "content")?,
-        dest: get_param(&yaml, "dest")?,
-        mode: parse_octal(&mode_string)?,
+        content,
+        dest: resolve_dest(&get_param(&yaml, "dest")?, src.as_deref())?,
+        mode: mode_string,
+        owner: get_optional_param(&yaml, "owner")?,
+        group: get_optional_param(&yaml, "group")?,
+        force: get_param_bool_or(&yaml, "force", true)?,
+        checksum: get_optional_param(&yaml, "checksum")?,
+        backup: get_param_bool_or(&yaml, "backup", false)?,
+        keep_backups: parse_keep_backups_param(&yaml)?,
+        unsafe_writes: get_param_bool_or(&yaml, "unsafe_writes", false)?,
+        validate: parse_validate_param(&yaml)?,
     })
 }
 
-pub fn verify_file(params: Params) -> Result<ModuleResult> {
-    trace!("params: {:?}", params);
-    let open_read_file = OpenOptions::new().read(true).clone();
-    let read_file = open_read_file.open(&params.dest).or_else(|_| {
-        trace!("file does not exists, create new one: {:?}", &params.dest);
-        open_read_file
-            .clone()
-            .write(true)
-            .create(true)
-            .open(&params.dest)
+/// Return final destination path. When `dest` ends in `/` or is an existing directory,
+/// file is placed inside it using `src` basename. Nothing is created, see [`create_parent_dir`].
+///
+/// [`create_parent_dir`]: fn.create_parent_dir.html
+pub fn resolve_dest(dest: &str, src: Option<&str>) -> Result<String> {
+    let dest_path = Path::new(dest);
+    if !dest.ends_with('/') && !dest_path.is_dir() {
+        return Ok(dest.to_string());
+    }
+
+    let basename = src.and_then(|s| Path::new(s).file_name()).ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("dest {} is a directory and there is no src basename", dest),
+        )
     })?;
-    let mut buf_reader = BufReader::new(&read_file);
-    let mut content = String::new();
-    buf_reader.read_to_string(&mut content)?;
-    let metadata = read_file.metadata()?;
+    Ok(dest_path.join(basename).to_string_lossy().to_string())
+}
+
+/// Create parent directories of `dest` if they don't exist, just before writing it.
+pub fn create_parent_dir(dest: &Path) -> Result<()> {
+    match dest.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() && !parent.exists() => {
+            trace!("creating dest directory: {:?}", parent);
+            Ok(create_dir_all(parent)?)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Set `mode` and ownership of `dest`, returning true if something changed.
+pub fn verify_permissions(
+    dest: &str,
+    mode: &str,
+    uid: Option<u32>,
+    gid: Option<u32>,
+) -> Result<bool> {
+    let metadata = Path::new(dest).metadata()?;
     let mut permissions = metadata.permissions();
     let mut changed = false;
 
-    if content != params.content {
+    // & 0o7777 to remove lead 100: 100644 -> 644
+    let current_mode = permissions.mode() & 0o7777;
+    let mode = parse_mode(mode, current_mode, false)?;
+    if current_mode != mode {
+        trace!("changing mode: {:o}", &mode);
+        permissions.set_mode(mode);
+        set_permissions(dest, permissions)?;
+        changed = true;
+    };
+
+    changed |= verify_ownership(dest, uid, gid)?;
+    Ok(changed)
+}
+
+/// Set ownership of `dest`, returning true if it changed.
+pub fn verify_ownership(dest: &str, uid: Option<u32>, gid: Option<u32>) -> Result<bool> {
+    let metadata = Path::new(dest).metadata()?;
+    let uid_change = uid.filter(|&uid| uid != metadata.uid());
+    let gid_change = gid.filter(|&gid| gid != metadata.gid());
+    if uid_change.is_none() && gid_change.is_none() {
+        return Ok(false);
+    };
+    trace!("changing ownership: {:?}:{:?}", uid_change, gid_change);
+    chown(dest, uid_change, gid_change).map_err(|e| {
+        Error::new(
+            ErrorKind::IOError,
+            format!("cannot change ownership of {}: {}", dest, e),
+        )
+    })?;
+    Ok(true)
+}
+
+/// Return an error if `content` sha256 does not match `checksum`.
+fn verify_checksum(content: &Content, checksum: &str) -> Result<()> {
+    let content_checksum = content.get_checksum()?;
+    if content_checksum != checksum.to_lowercase() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "checksum mismatch: expected {} but content sha256 is {}",
+                checksum, content_checksum
+            ),
+        ));
+    };
+    Ok(())
+}
+
+pub fn verify_file(params: Params) -> Result<ModuleResult> {
+    trace!("params: {:?}", params);
+    if let Some(checksum) = &params.checksum {
+        verify_checksum(&params.content, checksum)?;
+    };
+    if Path::new(&params.dest).is_dir() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("dest {} is a directory", params.dest),
+        ));
+    }
+    if !params.force && Path::new(&params.dest).exists() {
+        trace!("dest already exists and force is false: {:?}", &params.dest);
+        return Ok(ModuleResult::builder()
+            .changed(false)
+            .extra("checksum", json!(get_checksum(Path::new(&params.dest))?))
+            .output(params.dest)
+            .build());
+    }
+    let uid = params.owner.as_deref().map(get_uid).transpose()?;
+    let gid = params.group.as_deref().map(get_gid).transpose()?;
+    let dest_path = Path::new(&params.dest);
+    let dest_existed = dest_path.exists();
+    let mut changed = false;
+    let mut backup_file = None;
+
+    if !dest_existed || params.content.differs(dest_path)? {
         trace!("changing content: {:?}", &params.content);
-        if permissions.readonly() {
-            let mut p = permissions.clone();
-            // enable write
-            p.set_mode(permissions.mode() | 0o200);
-            set_permissions(&params.dest, p)?;
-        }
+        create_parent_dir(dest_path)?;
+        let tmp_path = get_tmp_path(dest_path)?;
+        params.content.write(&tmp_path)?;
+        let validated = match &params.validate {
+            Some(validate) => run_validate(&tmp_path, validate),
+            None => Ok(()),
+        };
+        let backed_up = validated.and_then(|_| {
+            if params.backup && dest_existed {
+                backup_dest(dest_path, params.keep_backups).map(Some)
+            } else {
+                Ok(None)
+            }
+        });
+        backup_file = match backed_up {
+            Ok(backup_file) => backup_file,
+            Err(e) => {
+                let _ = remove_file(&tmp_path);
+                return Err(e);
+            }
+        };
+        if let Ok(metadata) = dest_path.metadata() {
+            set_permissions(&tmp_path, metadata.permissions())?;
+            if let Err(e) = chown(&tmp_path, Some(metadata.uid()), Some(metadata.gid())) {
+                trace!("cannot preserve ownership of {}: {}", params.dest, e);
+            };
+        };
+        replace_dest(&tmp_path, dest_path, params.unsafe_writes, |from, to| {
+            rename(from, to)
+        })?;
+        changed = true;
+    };
+
+    changed |= verify_permissions(&params.dest, &params.mode, uid, gid)?;
+
+    let mut builder = ModuleResult::builder()
+        .changed(changed)
+        .extra("checksum", json!(get_checksum(dest_path)?))
+        .output(params.dest.clone());
+    if let Some(backup_file) = backup_file {
+        builder = builder.extra("backup_file", json!(backup_file));
+    };
+    Ok(builder.build())
+}
 
-        let mut file = OpenOptions::new().write(true).open(&params.dest)?;
-        file.seek(SeekFrom::Start(0))?;
-        file.write_all(params.content.as_bytes())?;
-        file.set_len(params.content.len() as u64)?;
+/// Copy `src` directory tree to `dest`. With a trailing `/` in `src` only its content is copied,
+/// otherwise the directory itself is created inside `dest`.
+fn copy_dir(params: DirParams) -> Result<ModuleResult> {
+    trace!("params: {:?}", params);
+    let src = Path::new(&params.src);
+    let dest = if params.src.ends_with('/') {
+        PathBuf::from(&params.dest)
+    } else {
+        Path::new(&params.dest).join(src.file_name().ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("src {} has no basename", params.src),
+            )
+        })?)
+    };
 
-        if permissions.readonly() {
-            set_permissions(&params.dest, permissions.clone())?;
+    let uid = params.owner.as_deref().map(get_uid).transpose()?;
+    let gid = params.group.as_deref().map(get_gid).transpose()?;
+    let mut copied = Vec::new();
+    let mut checksums = Map::new();
+    let changed = copy_dir_recursive(src, &dest, &params, (uid, gid), &mut copied, &mut checksums)?;
+    Ok(ModuleResult::builder()
+        .changed(changed)
+        .extra("files", json!(copied))
+        .extra("checksum", Value::Object(checksums))
+        .output(dest.to_string_lossy().to_string())
+        .build())
+}
+
+/// Create `dest` as a symlink to the same target as `src` symlink, returning true if it
+/// changed. If `force` is false, an existing `dest` is kept.
+pub fn copy_symlink(src: &Path, dest: &Path, force: bool) -> Result<bool> {
+    let target = read_link(src)?;
+    match dest.symlink_metadata() {
+        Ok(_) if !force => return Ok(false),
+        Ok(metadata) if metadata.file_type().is_symlink() && read_link(dest)? == target => {
+            return Ok(false)
         }
-        changed = true;
+        Ok(metadata) if metadata.is_dir() => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("dest {} is a directory", dest.display()),
+            ))
+        }
+        Ok(_) => remove_file(dest)?,
+        Err(_) => (),
     };
+    trace!("creating symlink: {:?} -> {:?}", dest, target);
+    symlink(target, dest)?;
+    Ok(true)
+}
 
-    // & 0o7777 to remove lead 100: 100644 -> 644
-    if permissions.mode() & 0o7777 != params.mode {
-        trace!("changing mode: {:o}", &params.mode);
-        permissions.set_mode(params.mode);
-        set_permissions(&params.dest, permissions)?;
+/// Copy `src` content into `dest`, applying ownership to every file and directory in the tree.
+/// Symlinks are copied as symlinks, so they are never followed out of `src`.
+/// Changed paths are pushed to `copied`, except directories created by the copy, and sha256 of
+/// every file in `dest` tree is added to `checksums`.
+fn copy_dir_recursive(
+    src: &Path,
+    dest: &Path,
+    params: &DirParams,
+    ownership: (Option<u32>, Option<u32>),
+    copied: &mut Vec<String>,
+    checksums: &mut Map<String, Value>,
+) -> Result<bool> {
+    let mut changed = false;
+    let dest_string = dest.to_string_lossy().to_string();
+    if dest.exists() {
+        if verify_ownership(&dest_string, ownership.0, ownership.1)? {
+            copied.push(dest_string);
+            changed = true;
+        };
+    } else {
+        trace!("creating directory: {:?}", dest);
+        create_dir_all(dest)?;
+        if let Some(directory_mode) = &params.directory_mode {
+            let mut permissions = dest.metadata()?.permissions();
+            permissions.set_mode(parse_mode(
+                directory_mode,
+                permissions.mode() & 0o7777,
+                true,
+            )?);
+            set_permissions(dest, permissions)?;
+        };
+        verify_ownership(&dest_string, ownership.0, ownership.1)?;
         changed = true;
     };
 
-    Ok(ModuleResult {
-        changed,
-        output: Some(params.dest),
-        extra: None,
-    })
+    let mut entries = read_dir(src)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<PathBuf>>>()?;
+    entries.sort();
+
+    for entry in entries {
+        // read_dir entries always have a file name
+        let entry_dest = dest.join(entry.file_name().unwrap());
+        let file_type = entry.symlink_metadata()?.file_type();
+        if file_type.is_symlink() {
+            if copy_symlink(&entry, &entry_dest, params.force)? {
+                copied.push(entry_dest.to_string_lossy().to_string());
+                changed = true;
+            };
+        } else if file_type.is_dir() {
+            changed |=
+                copy_dir_recursive(&entry, &entry_dest, params, ownership, copied, checksums)?;
+        } else {
+            let entry_dest_string = entry_dest.to_string_lossy().to_string();
+            let result = verify_file(Params {
+                content: Content::File(entry.to_string_lossy().to_string()),
+                dest: entry_dest_string.clone(),
+                mode: resolve_preserve_mode(&params.mode, Some(&entry))?,
+                owner: params.owner.clone(),
+                group: params.group.clone(),
+                force: params.force,
+                checksum: None,
+                backup: params.backup,
+                keep_backups: params.keep_backups,
+                unsafe_writes: params.unsafe_writes,
+                validate: None,
+            })?;
+            checksums.insert(entry_dest_string.clone(), json!(get_checksum(&entry_dest)?));
+            if result.changed {
+                copied.push(entry_dest_string);
+                changed = true;
+            };
+        }
+    }
+    Ok(changed)
 }
 
 pub fn exec(optional_params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
-    Ok((verify_file(parse_params(optional_params)?)?, vars))
+    validate_params("copy", &optional_params, REQUIRED_PARAMS, OPTIONAL_PARAMS)?;
+    match get_param(&optional_params, "src") {
+        Ok(src) if Path::new(&src).is_dir() => {
+            if let Some(param) = ["checksum", "validate"]
+                .iter()
+                .find(|param| !optional_params[**param].is_badvalue())
+            {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{} is not supported when src is a directory", param),
+                ));
+            };
+            Ok((copy_dir(parse_dir_params(optional_params)?)?, vars))
+        }
+        _ => Ok((verify_file(parse_params(optional_params)?)?, vars)),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use std::fs::File;
-    use std::io::Read;
+    use std::fs::{read_to_string, File};
+    use std::io::{Read, Write};
     use std::os::unix::fs::PermissionsExt;
 
     use tempfile::tempdir;
     use yaml_rust::YamlLoader;
 
+    fn get_sha256(path: &Path) -> String {
+        format!("{:x}", Sha256::digest(std::fs::read(path).unwrap()))
+    }
+
     #[test]
     fn test_parse_params() {
         let yaml = YamlLoader::load_from_str(
@@ -161,9 +722,17 @@ mod tests {
         assert_eq!(
             params,
             Params {
-                content: "boo".to_string(),
+                content: Content::Bytes("boo".as_bytes().to_vec()),
                 dest: "/tmp/buu.txt".to_string(),
-                mode: 0o600,
+                mode: "0600".to_string(),
+                owner: None,
+                group: None,
+                force: true,
+                checksum: None,
+                backup: false,
+                keep_backups: None,
+                unsafe_writes: false,
+                validate: None,
             }
         );
     }
@@ -201,13 +770,888 @@ mod tests {
         assert_eq!(
             params,
             Params {
-                content: "boo".to_string(),
+                content: Content::Bytes("boo".as_bytes().to_vec()),
                 dest: "/tmp/buu.txt".to_string(),
-                mode: 0o644,
+                mode: "0644".to_string(),
+                owner: None,
+                group: None,
+                force: true,
+                checksum: None,
+                backup: false,
+                keep_backups: None,
+                unsafe_writes: false,
+                validate: None,
             }
         );
     }
 
+    #[test]
+    fn test_parse_params_src() {
+        let dir = tempdir().unwrap();
+        let src_path = dir.path().join("src.txt");
+        let mut file = File::create(src_path.clone()).unwrap();
+        writeln!(file, "boo").unwrap();
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: /tmp/buu.txt",
+            src_path.to_str().unwrap()
+        ))
+        .unwrap()
+        .first()
+        .unwrap()
+        .clone();
+        let params = parse_params(yaml).unwrap();
+        assert_eq!(params.get_content(), "boo\n");
+    }
+
+    #[test]
+    fn test_parse_params_content_and_src() {
+        let yaml = YamlLoader::load_from_str(
+            r#"
+        content: "boo"
+        src: "/tmp/foo.txt"
+        dest: "/tmp/buu.txt"
+        "#,
+        )
+        .unwrap()
+        .first()
+        .unwrap()
+        .clone();
+        let error = parse_params(yaml).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_parse_params_no_content_nor_src() {
+        let yaml = YamlLoader::load_from_str(
+            r#"
+        dest: "/tmp/buu.txt"
+        "#,
+        )
+        .unwrap()
+        .first()
+        .unwrap()
+        .clone();
+        let error = parse_params(yaml).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_exec_content() {
+        let dir = tempdir().unwrap();
+        let dest_path = dir.path().join("dest.txt");
+        let yaml = YamlLoader::load_from_str(&format!(
+            "content: boo\ndest: {}",
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(result.changed);
+        assert_eq!(read_to_string(&dest_path).unwrap(), "boo");
+    }
+
+    #[test]
+    fn test_exec_checksum() {
+        let dir = tempdir().unwrap();
+        let src_path = dir.path().join("src.txt");
+        let dest_path = dir.path().join("dest.txt");
+        create_file(&src_path, "foo");
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: {}\nchecksum: 2C26B46B68FFC68FF99B453C1D30413413422D706483BFA0F98A5E886266E7AE",
+            src_path.to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(result.changed);
+        assert_eq!(read_to_string(&dest_path).unwrap(), "foo");
+        assert_eq!(
+            result.get_extra().unwrap()["checksum"],
+            json!(get_sha256(&dest_path))
+        );
+    }
+
+    #[test]
+    fn test_exec_backup() {
+        let dir = tempdir().unwrap();
+        let dest_path = dir.path().join("dest.txt");
+        let yaml = |content: &str| {
+            YamlLoader::load_from_str(&format!(
+                "content: {}\ndest: {}\nbackup: true",
+                content,
+                dest_path.to_str().unwrap()
+            ))
+            .unwrap()[0]
+                .clone()
+        };
+
+        let (result, _) = exec(yaml("foo"), Vars::new()).unwrap();
+        assert!(result.changed);
+        let checksum = json!({ "checksum": get_sha256(&dest_path) });
+        assert_eq!(result.get_extra(), Some(checksum.clone()));
+
+        let (result, _) = exec(yaml("foo"), Vars::new()).unwrap();
+        assert!(!result.changed);
+        assert_eq!(result.get_extra(), Some(checksum));
+
+        let (result, _) = exec(yaml("boo"), Vars::new()).unwrap();
+        assert!(result.changed);
+        let backup_file = result.get_extra().unwrap()["backup_file"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(read_to_string(&backup_file).unwrap(), "foo");
+        assert_eq!(read_to_string(&dest_path).unwrap(), "boo");
+    }
+
+    #[test]
+    fn test_exec_keep_backups() {
+        let dir = tempdir().unwrap();
+        let dest_path = dir.path().join("dest.txt");
+        let backup_files = ["foo", "boo", "zoo", "yoo"]
+            .iter()
+            .filter_map(|content| {
+                let yaml = YamlLoader::load_from_str(&format!(
+                    "content: {}\ndest: {}\nbackup: true\nkeep_backups: 2",
+                    content,
+                    dest_path.to_str().unwrap()
+                ))
+                .unwrap()[0]
+                    .clone();
+                let (result, _) = exec(yaml, Vars::new()).unwrap();
+                result.get_extra().unwrap()["backup_file"]
+                    .as_str()
+                    .map(String::from)
+            })
+            .collect::<Vec<String>>();
+
+        assert_eq!(backup_files.len(), 3);
+        assert!(!Path::new(&backup_files[0]).exists());
+        assert_eq!(read_to_string(&backup_files[1]).unwrap(), "boo");
+        assert_eq!(read_to_string(&backup_files[2]).unwrap(), "zoo");
+        assert_eq!(read_dir(dir.path()).unwrap().count(), 3);
+    }
+
+    #[test]
+    fn test_exec_mode_preserve() {
+        let dir = tempdir().unwrap();
+        let src_path = dir.path().join("script.sh");
+        create_file(&src_path, "echo rash");
+        set_permissions(&src_path, std::fs::Permissions::from_mode(0o750)).unwrap();
+        let dest_path = dir.path().join("dest.sh");
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: {}\nmode: preserve",
+            src_path.display(),
+            dest_path.display()
+        ))
+        .unwrap()[0]
+            .clone();
+        let (result, _) = exec(yaml.clone(), Vars::new()).unwrap();
+        assert!(result.get_changed());
+        let mode = dest_path.metadata().unwrap().permissions().mode() & 0o7777;
+        assert_eq!(format!("{:o}", mode), "750");
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_changed());
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "content: foo\ndest: {}\nmode: preserve",
+            dest_path.display()
+        ))
+        .unwrap()[0]
+            .clone();
+        let error = exec(yaml, Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_exec_dir_mode_preserve() {
+        let dir = tempdir().unwrap();
+        let src_path = dir.path().join("src");
+        create_dir_all(src_path.join("sub")).unwrap();
+        create_file(&src_path.join("run.sh"), "a");
+        create_file(&src_path.join("sub").join("b.txt"), "b");
+        set_permissions(
+            src_path.join("run.sh"),
+            std::fs::Permissions::from_mode(0o750),
+        )
+        .unwrap();
+        set_permissions(
+            src_path.join("sub").join("b.txt"),
+            std::fs::Permissions::from_mode(0o600),
+        )
+        .unwrap();
+        let dest_path = dir.path().join("dest");
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}/\ndest: {}\nmode: preserve",
+            src_path.display(),
+            dest_path.display()
+        ))
+        .unwrap()[0]
+            .clone();
+        let _ = exec(yaml, Vars::new()).unwrap();
+        let get_mode = |path: PathBuf| path.metadata().unwrap().permissions().mode() & 0o7777;
+        assert_eq!(format!("{:o}", get_mode(dest_path.join("run.sh"))), "750");
+        assert_eq!(
+            format!("{:o}", get_mode(dest_path.join("sub").join("b.txt"))),
+            "600"
+        );
+    }
+
+    #[test]
+    fn test_exec_validate() {
+        let dir = tempdir().unwrap();
+        let dest = dir.path().join("sudoers");
+        let yaml = |content: &str| {
+            YamlLoader::load_from_str(&format!(
+                "content: {}\ndest: {}\nvalidate: \"grep -q valid %s\"",
+                content,
+                dest.display()
+            ))
+            .unwrap()[0]
+                .clone()
+        };
+
+        let error = exec(yaml("foo"), Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(!dest.exists());
+        assert_eq!(dir.path().read_dir().unwrap().count(), 0);
+
+        let (result, _) = exec(yaml("valid"), Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(read_to_string(&dest).unwrap(), "valid");
+
+        let _ = exec(yaml("foo"), Vars::new()).unwrap_err();
+        assert_eq!(read_to_string(&dest).unwrap(), "valid");
+    }
+
+    #[test]
+    fn test_exec_replace_dest() {
+        let dir = tempdir().unwrap();
+        let dest = dir.path().join("app.conf");
+        create_file(&dest, "port=80");
+        let inode = dest.metadata().unwrap().ino();
+        let yaml = YamlLoader::load_from_str(&format!(
+            "content: port=8080\ndest: {}\nunsafe_writes: true",
+            dest.display()
+        ))
+        .unwrap()[0]
+            .clone();
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(read_to_string(&dest).unwrap(), "port=8080");
+        assert_ne!(dest.metadata().unwrap().ino(), inode);
+        assert_eq!(dir.path().read_dir().unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_parse_validate_param() {
+        let yaml = YamlLoader::load_from_str("validate: visudo -cf %s").unwrap()[0].clone();
+        assert_eq!(
+            parse_validate_param(&yaml).unwrap(),
+            Some("visudo -cf %s".to_string())
+        );
+        let yaml = YamlLoader::load_from_str("validate: visudo -c").unwrap()[0].clone();
+        let _ = parse_validate_param(&yaml).unwrap_err();
+    }
+
+    #[test]
+    fn test_parse_keep_backups_param() {
+        let yaml = YamlLoader::load_from_str("keep_backups: 3").unwrap()[0].clone();
+        assert_eq!(parse_keep_backups_param(&yaml).unwrap(), Some(3));
+        let yaml = YamlLoader::load_from_str("backup: true").unwrap()[0].clone();
+        assert_eq!(parse_keep_backups_param(&yaml).unwrap(), None);
+        let yaml = YamlLoader::load_from_str("keep_backups: 0").unwrap()[0].clone();
+        let _ = parse_keep_backups_param(&yaml).unwrap_err();
+    }
+
+    #[test]
+    fn test_exec_checksum_mismatch() {
+        let dir = tempdir().unwrap();
+        let src_path = dir.path().join("src.txt");
+        let dest_path = dir.path().join("dest.txt");
+        create_file(&src_path, "foo corrupted");
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: {}\nchecksum: 2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae",
+            src_path.to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
+
+        let error = exec(yaml, Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(!dest_path.exists());
+    }
+
+    #[test]
+    fn test_exec_checksum_dir() {
+        let dir = tempdir().unwrap();
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: /tmp/foo\nchecksum: 2c26b46b",
+            dir.path().to_str().unwrap(),
+        ))
+        .unwrap()[0]
+            .clone();
+
+        let error = exec(yaml, Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_exec_content_and_src() {
+        let dir = tempdir().unwrap();
+        let src_path = dir.path().join("src.txt");
+        let dest_path = dir.path().join("dest.txt");
+        create_file(&src_path, "foo");
+        let yaml = YamlLoader::load_from_str(&format!(
+            "content: boo\nsrc: {}\ndest: {}",
+            src_path.to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
+
+        let error = exec(yaml, Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(!dest_path.exists());
+    }
+
+    #[test]
+    fn test_exec_missing_dest() {
+        let yaml = YamlLoader::load_from_str("content: boo").unwrap()[0].clone();
+        let error = exec(yaml, Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(format!("{}", error).contains("module `copy` missing required params: dest"));
+    }
+
+    #[test]
+    fn test_exec_remote_src() {
+        let dir = tempdir().unwrap();
+        let src_path = dir.path().join("src.bin");
+        let dest_path = dir.path().join("dest.bin");
+        let src_content = vec![0u8, 159, 146, 150, 10, 255];
+        File::create(src_path.clone())
+            .unwrap()
+            .write_all(&src_content)
+            .unwrap();
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: {}\nremote_src: true",
+            src_path.to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()
+        .first()
+        .unwrap()
+        .clone();
+
+        let (result, _) = exec(yaml.clone(), Vars::new()).unwrap();
+        assert!(result.changed);
+        assert_eq!(read(&dest_path).unwrap(), src_content);
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.changed);
+        assert_eq!(read(&dest_path).unwrap(), src_content);
+    }
+
+    #[test]
+    fn test_exec_remote_src_checksum() {
+        let dir = tempdir().unwrap();
+        let src_path = dir.path().join("src.txt");
+        let dest_path = dir.path().join("dest.txt");
+        create_file(&src_path, "boo");
+        let yaml = |checksum: &str| {
+            YamlLoader::load_from_str(&format!(
+                "src: {}\ndest: {}\nremote_src: true\nchecksum: {}",
+                src_path.to_str().unwrap(),
+                dest_path.to_str().unwrap(),
+                checksum
+            ))
+            .unwrap()[0]
+                .clone()
+        };
+
+        let error = exec(yaml("0000"), Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(!dest_path.exists());
+
+        let (result, _) = exec(yaml(&get_sha256(&src_path)), Vars::new()).unwrap();
+        assert!(result.changed);
+        assert_eq!(read_to_string(&dest_path).unwrap(), "boo");
+    }
+
+    #[test]
+    fn test_exec_remote_src_without_src() {
+        let dir = tempdir().unwrap();
+        let dest_path = dir.path().join("dest.txt");
+        let yaml = YamlLoader::load_from_str(&format!(
+            "content: boo\ndest: {}\nremote_src: true",
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
+
+        let error = exec(yaml, Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(!dest_path.exists());
+    }
+
+    #[test]
+    fn test_exec_binary_src() {
+        let dir = tempdir().unwrap();
+        let src_path = dir.path().join("src.bin");
+        let dest_path = dir.path().join("dest.bin");
+        let src_content = vec![0xffu8, 0xfe, 0x00, 0xc3, 0x28];
+        assert!(String::from_utf8(src_content.clone()).is_err());
+        File::create(src_path.clone())
+            .unwrap()
+            .write_all(&src_content)
+            .unwrap();
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: {}",
+            src_path.to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
+
+        let (result, _) = exec(yaml.clone(), Vars::new()).unwrap();
+        assert!(result.changed);
+        assert_eq!(read(&dest_path).unwrap(), src_content);
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.changed);
+    }
+
+    fn create_file(path: &Path, content: &str) {
+        let mut file = File::create(path).unwrap();
+        write!(file, "{}", content).unwrap();
+    }
+
+    #[test]
+    fn test_exec_dir() {
+        let dir = tempdir().unwrap();
+        let src_path = dir.path().join("src");
+        create_dir_all(src_path.join("sub").join("deeper")).unwrap();
+        create_file(&src_path.join("a.txt"), "a");
+        create_file(&src_path.join("sub").join("b.txt"), "b");
+        create_file(&src_path.join("sub").join("deeper").join("c.txt"), "c");
+        let dest_path = dir.path().join("dest");
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}/\ndest: {}\nmode: \"0600\"\ndirectory_mode: \"0750\"",
+            src_path.to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()
+        .first()
+        .unwrap()
+        .clone();
+
+        let (result, _) = exec(yaml.clone(), Vars::new()).unwrap();
+        assert!(result.get_changed());
+        let files = [
+            dest_path.join("a.txt"),
+            dest_path.join("sub").join("b.txt"),
+            dest_path.join("sub").join("deeper").join("c.txt"),
+        ];
+        let checksums: Map<String, Value> = files
+            .iter()
+            .map(|file| (file.to_str().unwrap().to_string(), json!(get_sha256(file))))
+            .collect();
+        assert_eq!(
+            result.get_extra(),
+            Some(json!({
+                "files": files.iter().map(|file| file.to_str().unwrap()).collect::<Vec<_>>(),
+                "checksum": checksums,
+            }))
+        );
+        assert_eq!(read_to_string(dest_path.join("a.txt")).unwrap(), "a");
+        assert_eq!(
+            read_to_string(dest_path.join("sub").join("deeper").join("c.txt")).unwrap(),
+            "c"
+        );
+
+        let file_mode = dest_path
+            .join("sub")
+            .join("b.txt")
+            .metadata()
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(format!("{:o}", file_mode & 0o7777), format!("{:o}", 0o600));
+        let dir_mode = dest_path
+            .join("sub")
+            .metadata()
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(format!("{:o}", dir_mode & 0o7777), format!("{:o}", 0o750));
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(
+            result.get_extra(),
+            Some(json!({"files": [], "checksum": checksums}))
+        );
+    }
+
+    #[test]
+    fn test_exec_dir_symlinks() {
+        let dir = tempdir().unwrap();
+        let src_path = dir.path().join("src");
+        create_dir_all(src_path.join("sub")).unwrap();
+        create_file(&src_path.join("a.txt"), "a");
+        symlink("a.txt", src_path.join("link.txt")).unwrap();
+        // loop which would be followed forever
+        symlink("..", src_path.join("sub").join("parent")).unwrap();
+        let dest_path = dir.path().join("dest");
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}/\ndest: {}",
+            src_path.to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
+
+        let (result, _) = exec(yaml.clone(), Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(
+            read_link(dest_path.join("link.txt")).unwrap(),
+            PathBuf::from("a.txt")
+        );
+        assert_eq!(
+            read_link(dest_path.join("sub").join("parent")).unwrap(),
+            PathBuf::from("..")
+        );
+        assert_eq!(read_to_string(dest_path.join("link.txt")).unwrap(), "a");
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(result.get_extra().unwrap()["files"], json!([]));
+    }
+
+    #[test]
+    fn test_copy_symlink_replaces_file() {
+        let dir = tempdir().unwrap();
+        let src_path = dir.path().join("link");
+        let dest_path = dir.path().join("dest");
+        symlink("target", &src_path).unwrap();
+        create_file(&dest_path, "boo");
+
+        assert!(!copy_symlink(&src_path, &dest_path, false).unwrap());
+        assert_eq!(read_to_string(&dest_path).unwrap(), "boo");
+
+        assert!(copy_symlink(&src_path, &dest_path, true).unwrap());
+        assert_eq!(read_link(&dest_path).unwrap(), PathBuf::from("target"));
+        assert!(!copy_symlink(&src_path, &dest_path, true).unwrap());
+    }
+
+    #[test]
+    fn test_exec_dir_owner_group() {
+        let dir = tempdir().unwrap();
+        let src_path = dir.path().join("src");
+        create_dir_all(src_path.join("sub").join("deeper")).unwrap();
+        create_file(&src_path.join("a.txt"), "a");
+        create_file(&src_path.join("sub").join("deeper").join("c.txt"), "c");
+        let dest_path = dir.path().join("dest");
+        let metadata = dir.path().metadata().unwrap();
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}/\ndest: {}\nmode: \"0640\"\ndirectory_mode: \"0710\"\nowner: \"{}\"\ngroup: \"{}\"",
+            src_path.to_str().unwrap(),
+            dest_path.to_str().unwrap(),
+            metadata.uid(),
+            metadata.gid(),
+        ))
+        .unwrap()
+        .first()
+        .unwrap()
+        .clone();
+
+        let (result, _) = exec(yaml.clone(), Vars::new()).unwrap();
+        assert!(result.get_changed());
+
+        let get_mode = |path: PathBuf| path.metadata().unwrap().permissions().mode() & 0o7777;
+        for path in [
+            dest_path.clone(),
+            dest_path.join("sub"),
+            dest_path.join("sub").join("deeper"),
+        ] {
+            assert_eq!(format!("{:o}", get_mode(path.clone())), "710");
+            assert_eq!(path.metadata().unwrap().uid(), metadata.uid());
+            assert_eq!(path.metadata().unwrap().gid(), metadata.gid());
+        }
+        for path in [
+            dest_path.join("a.txt"),
+            dest_path.join("sub").join("deeper").join("c.txt"),
+        ] {
+            assert_eq!(format!("{:o}", get_mode(path.clone())), "640");
+            assert_eq!(path.metadata().unwrap().uid(), metadata.uid());
+            assert_eq!(path.metadata().unwrap().gid(), metadata.gid());
+        }
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(result.get_extra().unwrap()["files"], json!([]));
+    }
+
+    #[test]
+    fn test_verify_ownership() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("owner.txt");
+        File::create(&file_path).unwrap();
+        let metadata = file_path.metadata().unwrap();
+        let dest = file_path.to_str().unwrap();
+
+        assert!(!verify_ownership(dest, None, None).unwrap());
+        assert!(!verify_ownership(dest, Some(metadata.uid()), Some(metadata.gid())).unwrap());
+    }
+
+    #[test]
+    fn test_exec_dir_without_trailing_slash() {
+        let dir = tempdir().unwrap();
+        let src_path = dir.path().join("src");
+        create_dir_all(&src_path).unwrap();
+        create_file(&src_path.join("a.txt"), "a");
+        let dest_path = dir.path().join("dest");
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: {}",
+            src_path.to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()
+        .first()
+        .unwrap()
+        .clone();
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert_eq!(
+            result.get_output(),
+            Some(dest_path.join("src").to_str().unwrap().to_string())
+        );
+        assert_eq!(
+            read_to_string(dest_path.join("src").join("a.txt")).unwrap(),
+            "a"
+        );
+    }
+
+    #[test]
+    fn test_resolve_dest_file() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("foo.txt");
+        let dest = resolve_dest(file_path.to_str().unwrap(), Some("/tmp/boo.txt")).unwrap();
+        assert_eq!(dest, file_path.to_str().unwrap());
+    }
+
+    #[test]
+    fn test_resolve_dest_existing_dir() {
+        let dir = tempdir().unwrap();
+        let dest = resolve_dest(dir.path().to_str().unwrap(), Some("/tmp/boo.txt")).unwrap();
+        assert_eq!(dest, dir.path().join("boo.txt").to_str().unwrap());
+    }
+
+    #[test]
+    fn test_resolve_dest_trailing_slash() {
+        let dir = tempdir().unwrap();
+        let dest_dir = dir.path().join("new_dir");
+        let dest = resolve_dest(
+            &format!("{}/", dest_dir.to_str().unwrap()),
+            Some("/tmp/boo.txt"),
+        )
+        .unwrap();
+        assert_eq!(dest, dest_dir.join("boo.txt").to_str().unwrap());
+        assert!(!dest_dir.exists());
+    }
+
+    #[test]
+    fn test_exec_dest_trailing_slash() {
+        let dir = tempdir().unwrap();
+        let src_path = dir.path().join("boo.txt");
+        create_file(&src_path, "boo");
+        let dest_dir = dir.path().join("new_dir");
+        let yaml = |checksum: &str| {
+            YamlLoader::load_from_str(&format!(
+                "src: {}\ndest: {}/\nchecksum: {}",
+                src_path.to_str().unwrap(),
+                dest_dir.to_str().unwrap(),
+                checksum
+            ))
+            .unwrap()[0]
+                .clone()
+        };
+
+        let error = exec(yaml("0000"), Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(!dest_dir.exists());
+
+        let (result, _) = exec(yaml(&get_sha256(&src_path)), Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(read_to_string(dest_dir.join("boo.txt")).unwrap(), "boo");
+    }
+
+    #[test]
+    fn test_resolve_dest_dir_without_src() {
+        let dir = tempdir().unwrap();
+        let error = resolve_dest(dir.path().to_str().unwrap(), None).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_verify_file_dest_dir() {
+        let dir = tempdir().unwrap();
+        let error = verify_file(Params {
+            content: Content::Bytes("test\n".as_bytes().to_vec()),
+            dest: dir.path().to_str().unwrap().to_string(),
+            mode: "0644".to_string(),
+            owner: None,
+            group: None,
+            force: true,
+            checksum: None,
+            backup: false,
+            keep_backups: None,
+            unsafe_writes: false,
+            validate: None,
+        })
+        .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_parse_params_owner_group() {
+        let yaml = YamlLoader::load_from_str(
+            r#"
+        content: "boo"
+        dest: "/tmp/buu.txt"
+        owner: foo
+        group: "1000"
+        "#,
+        )
+        .unwrap()
+        .first()
+        .unwrap()
+        .clone();
+        let params = parse_params(yaml).unwrap();
+        assert_eq!(params.owner, Some("foo".to_string()));
+        assert_eq!(params.group, Some("1000".to_string()));
+    }
+
+    #[test]
+    fn test_verify_file_owner_group() {
+        let dir = tempdir().unwrap();
+
+        let file_path = dir.path().join("owner.txt");
+        let mut file = File::create(file_path.clone()).unwrap();
+        writeln!(file, "test").unwrap();
+
+        let mut permissions = file.metadata().unwrap().permissions();
+        permissions.set_mode(0o644);
+        set_permissions(&file_path, permissions).unwrap();
+        let metadata = file.metadata().unwrap();
+
+        let output = verify_file(Params {
+            content: Content::Bytes("test\n".as_bytes().to_vec()),
+            dest: file_path.to_str().unwrap().to_string(),
+            mode: "0644".to_string(),
+            owner: Some(metadata.uid().to_string()),
+            group: Some(metadata.gid().to_string()),
+            force: true,
+            checksum: None,
+            backup: false,
+            keep_backups: None,
+            unsafe_writes: false,
+            validate: None,
+        })
+        .unwrap();
+        assert!(!output.changed);
+    }
+
+    #[test]
+    fn test_verify_file_force() {
+        let dir = tempdir().unwrap();
+
+        let file_path = dir.path().join("force.txt");
+        create_file(&file_path, "old");
+        let params = |force| Params {
+            content: Content::Bytes("new".as_bytes().to_vec()),
+            dest: file_path.to_str().unwrap().to_string(),
+            mode: "0644".to_string(),
+            owner: None,
+            group: None,
+            force,
+            checksum: None,
+            backup: false,
+            keep_backups: None,
+            unsafe_writes: false,
+            validate: None,
+        };
+
+        let output = verify_file(params(false)).unwrap();
+        assert!(!output.changed);
+        assert_eq!(read_to_string(&file_path).unwrap(), "old");
+
+        let output = verify_file(params(true)).unwrap();
+        assert!(output.changed);
+        assert_eq!(read_to_string(&file_path).unwrap(), "new");
+    }
+
+    #[test]
+    fn test_verify_file_no_force_create() {
+        let dir = tempdir().unwrap();
+
+        let file_path = dir.path().join("force.txt");
+        let output = verify_file(Params {
+            content: Content::Bytes("new".as_bytes().to_vec()),
+            dest: file_path.to_str().unwrap().to_string(),
+            mode: "0644".to_string(),
+            owner: None,
+            group: None,
+            force: false,
+            checksum: None,
+            backup: false,
+            keep_backups: None,
+            unsafe_writes: false,
+            validate: None,
+        })
+        .unwrap();
+        assert!(output.changed);
+        assert_eq!(read_to_string(&file_path).unwrap(), "new");
+    }
+
+    #[test]
+    fn test_verify_file_owner_not_found() {
+        let dir = tempdir().unwrap();
+
+        let file_path = dir.path().join("owner.txt");
+        let error = verify_file(Params {
+            content: Content::Bytes("test\n".as_bytes().to_vec()),
+            dest: file_path.to_str().unwrap().to_string(),
+            mode: "0644".to_string(),
+            owner: Some("this_user_does_not_exist".to_string()),
+            group: None,
+            force: true,
+            checksum: None,
+            backup: false,
+            keep_backups: None,
+            unsafe_writes: false,
+            validate: None,
+        })
+        .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(!file_path.exists());
+    }
+
     #[test]
     fn test_verify_file_no_change() {
         let dir = tempdir().unwrap();
@@ -221,9 +1665,17 @@ mod tests {
         set_permissions(&file_path, permissions).unwrap();
 
         let output = verify_file(Params {
-            content: "test\n".to_string(),
+            content: Content::Bytes("test\n".as_bytes().to_vec()),
             dest: file_path.to_str().unwrap().to_string(),
-            mode: 0o644,
+            mode: "0644".to_string(),
+            owner: None,
+            group: None,
+            force: true,
+            checksum: None,
+            backup: false,
+            keep_backups: None,
+            unsafe_writes: false,
+            validate: None,
         })
         .unwrap();
 
@@ -244,7 +1696,11 @@ mod tests {
             ModuleResult {
                 changed: false,
                 output: Some(file_path.to_str().unwrap().to_string()),
-                extra: None,
+                extra: Some(json!({ "checksum": get_sha256(&file_path) })),
+                delegate_to: None,
+                label: None,
+                failed: false,
+                diff: None,
             }
         );
     }
@@ -257,9 +1713,17 @@ mod tests {
         let mut file = File::create(file_path.clone()).unwrap();
         writeln!(file, "test").unwrap();
         let output = verify_file(Params {
-            content: "fu".to_string(),
+            content: Content::Bytes("fu".as_bytes().to_vec()),
             dest: file_path.to_str().unwrap().to_string(),
-            mode: 0o400,
+            mode: "0400".to_string(),
+            owner: None,
+            group: None,
+            force: true,
+            checksum: None,
+            backup: false,
+            keep_backups: None,
+            unsafe_writes: false,
+            validate: None,
         })
         .unwrap();
 
@@ -280,20 +1744,71 @@ mod tests {
             ModuleResult {
                 changed: true,
                 output: Some(file_path.to_str().unwrap().to_string()),
-                extra: None,
+                extra: Some(json!({ "checksum": get_sha256(&file_path) })),
+                delegate_to: None,
+                label: None,
+                failed: false,
+                diff: None,
             }
         );
     }
 
+    #[test]
+    fn test_verify_file_symbolic_mode() {
+        let dir = tempdir().unwrap();
+
+        let file_path = dir.path().join("symbolic.txt");
+        let mut file = File::create(file_path.clone()).unwrap();
+        writeln!(file, "test").unwrap();
+
+        let mut permissions = file.metadata().unwrap().permissions();
+        permissions.set_mode(0o644);
+        set_permissions(&file_path, permissions).unwrap();
+
+        let output = verify_file(Params {
+            content: Content::Bytes("test\n".as_bytes().to_vec()),
+            dest: file_path.to_str().unwrap().to_string(),
+            mode: "u+x,go-r".to_string(),
+            owner: None,
+            group: None,
+            force: true,
+            checksum: None,
+            backup: false,
+            keep_backups: None,
+            unsafe_writes: false,
+            validate: None,
+        })
+        .unwrap();
+        assert!(output.changed);
+
+        let permissions = File::open(&file_path)
+            .unwrap()
+            .metadata()
+            .unwrap()
+            .permissions();
+        assert_eq!(
+            format!("{:o}", permissions.mode() & 0o7777),
+            format!("{:o}", 0o700)
+        );
+    }
+
     #[test]
     fn test_verify_file_create() {
         let dir = tempdir().unwrap();
 
         let file_path = dir.path().join("create.txt");
         let output = verify_file(Params {
-            content: "zoo".to_string(),
+            content: Content::Bytes("zoo".as_bytes().to_vec()),
             dest: file_path.to_str().unwrap().to_string(),
-            mode: 0o400,
+            mode: "0400".to_string(),
+            owner: None,
+            group: None,
+            force: true,
+            checksum: None,
+            backup: false,
+            keep_backups: None,
+            unsafe_writes: false,
+            validate: None,
         })
         .unwrap();
 
@@ -314,7 +1829,11 @@ mod tests {
             ModuleResult {
                 changed: true,
                 output: Some(file_path.to_str().unwrap().to_string()),
-                extra: None,
+                extra: Some(json!({ "checksum": get_sha256(&file_path) })),
+                delegate_to: None,
+                label: None,
+                failed: false,
+                diff: None,
             }
         );
     }
@@ -330,9 +1849,17 @@ mod tests {
         set_permissions(&file_path, permissions).unwrap();
 
         let output = verify_file(Params {
-            content: "zoo".to_string(),
+            content: Content::Bytes("zoo".as_bytes().to_vec()),
             dest: file_path.to_str().unwrap().to_string(),
-            mode: 0o600,
+            mode: "0600".to_string(),
+            owner: None,
+            group: None,
+            force: true,
+            checksum: None,
+            backup: false,
+            keep_backups: None,
+            unsafe_writes: false,
+            validate: None,
         })
         .unwrap();
 
@@ -353,7 +1880,11 @@ mod tests {
             ModuleResult {
                 changed: true,
                 output: Some(file_path.to_str().unwrap().to_string()),
-                extra: None,
+                extra: Some(json!({ "checksum": get_sha256(&file_path) })),
+                delegate_to: None,
+                label: None,
+                failed: false,
+                diff: None,
             }
         );
     }
@@ -369,9 +1900,17 @@ mod tests {
         set_permissions(&file_path, permissions).unwrap();
 
         let output = verify_file(Params {
-            content: "zoo".to_string(),
+            content: Content::Bytes("zoo".as_bytes().to_vec()),
             dest: file_path.to_str().unwrap().to_string(),
-            mode: 0o400,
+            mode: "0400".to_string(),
+            owner: None,
+            group: None,
+            force: true,
+            checksum: None,
+            backup: false,
+            keep_backups: None,
+            unsafe_writes: false,
+            validate: None,
         })
         .unwrap();
 
@@ -392,7 +1931,11 @@ mod tests {
             ModuleResult {
                 changed: true,
                 output: Some(file_path.to_str().unwrap().to_string()),
-                extra: None,
+                extra: Some(json!({ "checksum": get_sha256(&file_path) })),
+                delegate_to: None,
+                label: None,
+                failed: false,
+                diff: None,
             }
         );
     }
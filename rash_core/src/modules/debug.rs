@@ -0,0 +1,343 @@
+/// ANCHOR: module
+/// # debug
+///
+/// Print messages or variables during execution.
+///
+/// Variables whose names match a sensitive pattern are masked as
+/// `VALUE_SPECIFIED_IN_NO_LOG_PARAMETER`, even when they are nested inside a dict or accessed
+/// with subscripts like `db["password"]`. Their values are masked in `msg` too.
+/// Patterns are globs separated by commas, defined in `RASH_NO_LOG_PATTERNS` environment
+/// variable. Defaults to `password,*_password,*_secret,*_token`.
+///
+/// ## Parameters
+///
+/// ```yaml
+/// msg:
+///   type: string
+///   description: The message to print.
+/// var:
+///   type: string
+///   description: |
+///     A variable name to print. Only one of `msg` or `var` can be set.
+/// ```
+///
+/// ## Example
+///
+/// ```yaml
+/// - debug:
+///     msg: "Hello world"
+///
+/// - debug:
+///     var: rash.user
+/// ```
+/// ANCHOR_END: module
+use crate::error::{Error, ErrorKind, Result};
+use crate::modules::{get_param, validate_params, ModuleResult};
+use crate::utils::tera::render_string;
+use crate::vars::Vars;
+
+use std::env;
+
+use glob::Pattern;
+use serde_json::Value;
+use yaml_rust::Yaml;
+
+pub const NO_LOG_VALUE: &str = "VALUE_SPECIFIED_IN_NO_LOG_PARAMETER";
+const DEFAULT_NO_LOG_PATTERNS: &str = "password,*_password,*_secret,*_token";
+
+#[derive(Debug, PartialEq)]
+enum Params {
+    Msg(String),
+    Var(String),
+}
+
+fn parse_params(yaml: &Yaml) -> Result<Params> {
+    trace!("parse params: {:?}", yaml);
+    validate_params("debug", yaml, &[], &["msg", "var"])?;
+    match (get_param(yaml, "msg"), get_param(yaml, "var")) {
+        (Ok(msg), Err(_)) => Ok(Params::Msg(msg)),
+        (Err(_), Ok(var)) => Ok(Params::Var(var)),
+        _ => Err(Error::new(
+            ErrorKind::InvalidData,
+            "one of msg or var params is required, but not both",
+        )),
+    }
+}
+
+/// Parse comma separated glob patterns of sensitive var names.
+fn get_no_log_patterns(patterns: &str) -> Result<Vec<Pattern>> {
+    patterns
+        .split(',')
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .map(|pattern| Pattern::new(pattern).map_err(|e| Error::new(ErrorKind::InvalidData, e)))
+        .collect()
+}
+
+fn is_sensitive(name: &str, patterns: &[Pattern]) -> bool {
+    patterns.iter().any(|pattern| pattern.matches(name))
+}
+
+/// Mask `value` if `name` is sensitive or, recursively, its sensitive keys.
+fn mask(name: &str, value: Value, patterns: &[Pattern]) -> Value {
+    if is_sensitive(name, patterns) {
+        return Value::String(NO_LOG_VALUE.to_string());
+    };
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| {
+                    let masked = mask(&k, v, patterns);
+                    (k, masked)
+                })
+                .collect(),
+        ),
+        Value::Array(v) => Value::Array(v.into_iter().map(|x| mask("", x, patterns)).collect()),
+        _ => value,
+    }
+}
+
+/// Return the last path element of `var`, which is the var name, e.g.: `password` for
+/// `db.password` or `db["password"]`. List indexes are skipped, so `tokens[0]` is `tokens`.
+fn get_var_name(var: &str) -> &str {
+    let var = var.trim();
+    match var.strip_suffix(']').and_then(|s| s.rsplit_once('[')) {
+        Some((prefix, key)) => {
+            let key = key.trim();
+            match ['"', '\'']
+                .iter()
+                .find_map(|quote| key.strip_prefix(*quote)?.strip_suffix(*quote))
+            {
+                Some(name) => name,
+                None => get_var_name(prefix),
+            }
+        }
+        None => var.rsplit('.').next().unwrap_or(var).trim(),
+    }
+}
+
+fn get_var(var: &str, vars: Vars, patterns: &[Pattern]) -> Result<Value> {
+    let json = render_string(&format!("{{{{ {} | json_encode() | safe }}}}", var), vars)?;
+    let value = serde_json::from_str(&json).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    Ok(mask(get_var_name(var), value, patterns))
+}
+
+/// Add to `values` the string values of sensitive vars in `value`, recursively.
+fn get_sensitive_values(name: &str, value: &Value, patterns: &[Pattern], values: &mut Vec<String>) {
+    match value {
+        // every value inside a sensitive var is sensitive
+        Value::Object(map) if is_sensitive(name, patterns) => map
+            .values()
+            .for_each(|v| get_sensitive_values(name, v, patterns, values)),
+        Value::Object(map) => map
+            .iter()
+            .for_each(|(k, v)| get_sensitive_values(k, v, patterns, values)),
+        Value::Array(v) => v
+            .iter()
+            .for_each(|x| get_sensitive_values(name, x, patterns, values)),
+        Value::String(s) if !s.is_empty() && is_sensitive(name, patterns) => values.push(s.clone()),
+        Value::Number(_) if is_sensitive(name, patterns) => values.push(value.to_string()),
+        _ => (),
+    }
+}
+
+/// Replace values of sensitive vars in rendered `msg`.
+fn mask_msg(msg: &str, vars: &Vars, patterns: &[Pattern]) -> String {
+    let mut values = Vec::new();
+    get_sensitive_values("", &vars.clone().into_json(), patterns, &mut values);
+    // longest first, so values containing others are fully masked
+    values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+    values.iter().fold(msg.to_string(), |msg, value| {
+        msg.replace(value, NO_LOG_VALUE)
+    })
+}
+
+pub fn exec(optional_params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
+    let patterns = get_no_log_patterns(
+        &env::var("RASH_NO_LOG_PATTERNS").unwrap_or_else(|_| DEFAULT_NO_LOG_PATTERNS.to_string()),
+    )?;
+    let result = match parse_params(&optional_params)? {
+        Params::Msg(msg) => ModuleResult::builder()
+            .output(mask_msg(&msg, &vars, &patterns))
+            .build(),
+        Params::Var(var) => {
+            let value = get_var(&var, vars.clone(), &patterns)?;
+            ModuleResult::builder()
+                .output(format!("{}: {}", var, value))
+                .extra(&var, value)
+                .build()
+        }
+    };
+    Ok((result, vars))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use yaml_rust::YamlLoader;
+
+    fn default_patterns() -> Vec<Pattern> {
+        get_no_log_patterns(DEFAULT_NO_LOG_PATTERNS).unwrap()
+    }
+
+    #[test]
+    fn test_parse_params() {
+        let yaml = YamlLoader::load_from_str("msg: hello").unwrap()[0].clone();
+        assert_eq!(
+            parse_params(&yaml).unwrap(),
+            Params::Msg("hello".to_string())
+        );
+        let yaml = YamlLoader::load_from_str("var: foo").unwrap()[0].clone();
+        assert_eq!(parse_params(&yaml).unwrap(), Params::Var("foo".to_string()));
+    }
+
+    #[test]
+    fn test_parse_params_invalid() {
+        let yaml = YamlLoader::load_from_str("msg: hello\nvar: foo").unwrap()[0].clone();
+        let error = parse_params(&yaml).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_get_var_masked() {
+        let mut vars = Vars::new();
+        vars.insert("db_password", "supersecret");
+        vars.insert(
+            "db",
+            &json!({
+                "user": "foo",
+                "password": "supersecret",
+                "replicas": [{"host": "a", "api_token": "supersecret"}],
+            }),
+        );
+
+        assert_eq!(
+            get_var("db_password", vars.clone(), &default_patterns()).unwrap(),
+            json!(NO_LOG_VALUE)
+        );
+        assert_eq!(
+            get_var("db", vars.clone(), &default_patterns()).unwrap(),
+            json!({
+                "user": "foo",
+                "password": NO_LOG_VALUE,
+                "replicas": [{"host": "a", "api_token": NO_LOG_VALUE}],
+            })
+        );
+        assert_eq!(
+            get_var("db.password", vars.clone(), &default_patterns()).unwrap(),
+            json!(NO_LOG_VALUE)
+        );
+        assert_eq!(
+            get_var("db.user", vars, &default_patterns()).unwrap(),
+            json!("foo")
+        );
+    }
+
+    #[test]
+    fn test_get_var_masked_subscript() {
+        let mut vars = Vars::new();
+        vars.insert(
+            "db",
+            &json!({
+                "user": "foo",
+                "password": "supersecret",
+                "api_tokens": ["supersecret"],
+                "replicas": [{"host": "a", "api_token": "supersecret"}],
+            }),
+        );
+
+        [
+            r#"db["password"]"#,
+            "db['password']",
+            r#"db["replicas"][0]['api_token']"#,
+            "db.replicas[0].api_token",
+        ]
+        .iter()
+        .for_each(|var| {
+            assert_eq!(
+                get_var(var, vars.clone(), &default_patterns()).unwrap(),
+                json!(NO_LOG_VALUE),
+                "{}",
+                var
+            )
+        });
+        assert_eq!(
+            get_var(r#"db["user"]"#, vars.clone(), &default_patterns()).unwrap(),
+            json!("foo")
+        );
+        assert_eq!(
+            get_var(r#"db["replicas"][0]"#, vars, &default_patterns()).unwrap(),
+            json!({"host": "a", "api_token": NO_LOG_VALUE})
+        );
+    }
+
+    #[test]
+    fn test_get_var_name() {
+        assert_eq!(get_var_name("db_password"), "db_password");
+        assert_eq!(get_var_name(" db.password "), "password");
+        assert_eq!(get_var_name(r#"db["password"]"#), "password");
+        assert_eq!(get_var_name("db['api_token']"), "api_token");
+        assert_eq!(get_var_name("tokens[0]"), "tokens");
+        assert_eq!(get_var_name("db.tokens[0][1]"), "tokens");
+    }
+
+    #[test]
+    fn test_exec_msg_masked() {
+        let mut vars = Vars::new();
+        vars.insert("user", "foo");
+        vars.insert("db_password", "supersecret");
+        vars.insert("db", &json!({"password": "boo", "port": 5432}));
+
+        let yaml =
+            YamlLoader::load_from_str("msg: \"foo:supersecret@db:5432 boo\"").unwrap()[0].clone();
+        let (result, _) = exec(yaml, vars).unwrap();
+        assert_eq!(
+            result.get_output(),
+            Some(format!("foo:{}@db:5432 {}", NO_LOG_VALUE, NO_LOG_VALUE))
+        );
+    }
+
+    #[test]
+    fn test_get_var_custom_patterns() {
+        let mut vars = Vars::new();
+        vars.insert("ssh_key", "supersecret");
+        vars.insert("db_password", "foo");
+        let patterns = get_no_log_patterns("*_key, ").unwrap();
+        assert_eq!(
+            get_var("ssh_key", vars.clone(), &patterns).unwrap(),
+            json!(NO_LOG_VALUE)
+        );
+        assert_eq!(
+            get_var("db_password", vars, &patterns).unwrap(),
+            json!("foo")
+        );
+    }
+
+    #[test]
+    fn test_exec() {
+        let mut vars = Vars::new();
+        vars.insert("user", "foo");
+        vars.insert("user_password", "supersecret");
+
+        let yaml = YamlLoader::load_from_str("var: user").unwrap()[0].clone();
+        let (result, _) = exec(yaml, vars.clone()).unwrap();
+        assert_eq!(result.get_output(), Some("user: \"foo\"".to_string()));
+        assert_eq!(result.get_extra(), Some(json!({"user": "foo"})));
+
+        let yaml = YamlLoader::load_from_str("var: user_password").unwrap()[0].clone();
+        let (result, _) = exec(yaml, vars.clone()).unwrap();
+        assert!(!result.get_output().unwrap().contains("supersecret"));
+
+        let yaml = YamlLoader::load_from_str("msg: hello").unwrap()[0].clone();
+        let (result, _) = exec(yaml, vars).unwrap();
+        assert_eq!(result.get_output(), Some("hello".to_string()));
+    }
+
+    #[test]
+    fn test_exec_undefined() {
+        let yaml = YamlLoader::load_from_str("var: foo").unwrap()[0].clone();
+        let error = exec(yaml, Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,383 @@
+/// ANCHOR: module
+/// # dnf
+///
+/// Manage packages with `dnf` package manager, falling back to `yum` when `dnf` is not
+/// available.
+///
+/// ## Parameters
+///
+/// ```yaml
+/// name:
+///   type: list
+///   description: |
+///     Packages to manage. It can be a list or a string with comma separated names.
+///     It is required unless `update_cache` is set.
+/// state:
+///   type: string
+///   choices:
+///     - present
+///     - absent
+///     - latest
+///   description: |
+///     Whether packages must be installed, removed or installed and upgraded to their
+///     latest version. Defaults to present.
+/// enablerepo:
+///   type: list
+///   description: Repositories to enable for the install or upgrade operation.
+/// disablerepo:
+///   type: list
+///   description: Repositories to disable for the install or upgrade operation.
+/// update_cache:
+///   type: bool
+///   description: Update repository metadata before any other operation. Defaults to false.
+/// ```
+///
+/// ## Example
+///
+/// ```yaml
+/// - dnf:
+///     name:
+///       - curl
+///       - git
+///     update_cache: true
+///
+/// - dnf:
+///     name: nginx
+///     state: latest
+///     enablerepo: epel
+///
+/// - dnf:
+///     name: httpd
+///     state: absent
+/// ```
+/// ANCHOR_END: module
+use crate::error::{Error, ErrorKind, Result};
+use crate::modules::package_manager::{
+    is_in_path, parse_list, parse_state, run, run_checked, State,
+};
+use crate::modules::{get_param_bool_or, validate_params, ModuleResult};
+use crate::vars::Vars;
+
+use std::process::Output;
+
+use yaml_rust::Yaml;
+
+#[derive(Debug, PartialEq)]
+struct Params {
+    name: Vec<String>,
+    state: State,
+    enablerepo: Vec<String>,
+    disablerepo: Vec<String>,
+    update_cache: bool,
+}
+
+const OPTIONAL_PARAMS: &[&str] = &["name", "state", "enablerepo", "disablerepo", "update_cache"];
+
+/// `dnf check-update` exit code when there are updates available.
+const UPDATES_AVAILABLE_RC: i32 = 100;
+
+fn parse_params(yaml: Yaml) -> Result<Params> {
+    trace!("parse params: {:?}", yaml);
+    validate_params("dnf", &yaml, &[], OPTIONAL_PARAMS)?;
+    let params = Params {
+        name: parse_list(&yaml, "name")?,
+        state: parse_state(&yaml)?,
+        enablerepo: parse_list(&yaml, "enablerepo")?,
+        disablerepo: parse_list(&yaml, "disablerepo")?,
+        update_cache: get_param_bool_or(&yaml, "update_cache", false)?,
+    };
+    if params.name.is_empty() && !params.update_cache {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "one of name or update_cache params is required",
+        ));
+    };
+    Ok(params)
+}
+
+fn get_program() -> &'static str {
+    if is_in_path("dnf") {
+        "dnf"
+    } else {
+        "yum"
+    }
+}
+
+fn get_repo_args(params: &Params) -> Vec<String> {
+    params
+        .enablerepo
+        .iter()
+        .map(|repo| format!("--enablerepo={}", repo))
+        .chain(
+            params
+                .disablerepo
+                .iter()
+                .map(|repo| format!("--disablerepo={}", repo)),
+        )
+        .collect()
+}
+
+fn is_installed<F>(runner: &mut F, name: &str) -> Result<bool>
+where
+    F: FnMut(&str, &[&str]) -> Result<Output>,
+{
+    Ok(runner("rpm", &["-q", name])?.status.success())
+}
+
+/// Return names of packages listed by `check-update` output, like `curl.x86_64 8.5.0-1 updates`.
+fn parse_updates(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .filter_map(
+            |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [package, _, _] => package.rsplit_once('.').map(|(name, _)| name.to_string()),
+                _ => None,
+            },
+        )
+        .collect()
+}
+
+/// Return packages of `names` with updates available.
+fn get_updates<F>(
+    runner: &mut F,
+    program: &str,
+    repo_args: &[&str],
+    names: &[&str],
+) -> Result<Vec<String>>
+where
+    F: FnMut(&str, &[&str]) -> Result<Output>,
+{
+    let args = [&["check-update", "-q"], repo_args, names].concat();
+    let output = runner(program, &args)?;
+    match output.status.code() {
+        Some(0) => Ok(Vec::new()),
+        Some(UPDATES_AVAILABLE_RC) => Ok(parse_updates(&String::from_utf8_lossy(&output.stdout))),
+        _ => Err(Error::new(
+            ErrorKind::SubprocessFail,
+            format!(
+                "{} {} failed: {}",
+                program,
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim_end()
+            ),
+        )),
+    }
+}
+
+/// Apply `params` calling `program` and `rpm` through `runner`, so it can be replaced in tests.
+fn apply<F>(params: &Params, program: &str, runner: &mut F) -> Result<ModuleResult>
+where
+    F: FnMut(&str, &[&str]) -> Result<Output>,
+{
+    let repo_args = get_repo_args(params);
+    let repo_args = repo_args.iter().map(String::as_str).collect::<Vec<_>>();
+    if params.update_cache {
+        run_checked(
+            runner,
+            program,
+            &[&["makecache"], repo_args.as_slice()].concat(),
+        )?;
+    };
+
+    let mut missing = Vec::new();
+    let mut installed = Vec::new();
+    for name in params.name.iter() {
+        if is_installed(runner, name)? {
+            installed.push(name.as_str());
+        } else {
+            missing.push(name.as_str());
+        }
+    }
+
+    let mut packages = Vec::new();
+    match params.state {
+        State::Present | State::Latest if !missing.is_empty() => {
+            run_checked(
+                runner,
+                program,
+                &[&["install", "-y"], repo_args.as_slice(), missing.as_slice()].concat(),
+            )?;
+            packages.extend(missing.iter().map(|name| name.to_string()));
+        }
+        State::Absent if !installed.is_empty() => {
+            run_checked(
+                runner,
+                program,
+                &[&["remove", "-y"], installed.as_slice()].concat(),
+            )?;
+            packages.extend(installed.iter().map(|name| name.to_string()));
+        }
+        _ => (),
+    };
+    if params.state == State::Latest && !installed.is_empty() {
+        let updates = get_updates(runner, program, &repo_args, &installed)?;
+        if !updates.is_empty() {
+            let updates_args = updates.iter().map(String::as_str).collect::<Vec<_>>();
+            run_checked(
+                runner,
+                program,
+                &[
+                    &["upgrade", "-y"],
+                    repo_args.as_slice(),
+                    updates_args.as_slice(),
+                ]
+                .concat(),
+            )?;
+            packages.extend(updates);
+        };
+    };
+
+    Ok(ModuleResult::builder()
+        .changed(!packages.is_empty())
+        .extra("packages", json!(packages))
+        .build())
+}
+
+pub fn exec(optional_params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
+    let params = parse_params(optional_params)?;
+    Ok((apply(&params, get_program(), &mut run)?, vars))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    use yaml_rust::YamlLoader;
+
+    /// Fake dnf and rpm recording every call, where `installed` packages are reported by
+    /// `rpm -q` and `check-update` returns `updates` output.
+    fn fake_dnf<'a>(
+        installed: &'a [&'a str],
+        updates: &'a str,
+        calls: &'a mut Vec<String>,
+    ) -> impl FnMut(&str, &[&str]) -> Result<Output> + 'a {
+        move |program: &str, args: &[&str]| {
+            calls.push(format!("{} {}", program, args.join(" ")));
+            let (code, stdout) = match args {
+                ["-q", name] if !installed.contains(name) => (1, ""),
+                ["check-update", ..] if !updates.is_empty() => (UPDATES_AVAILABLE_RC, updates),
+                _ => (0, ""),
+            };
+            Ok(Output {
+                status: ExitStatus::from_raw(code << 8),
+                stdout: stdout.as_bytes().to_vec(),
+                stderr: Vec::new(),
+            })
+        }
+    }
+
+    fn get_params(yaml: &str) -> Params {
+        parse_params(YamlLoader::load_from_str(yaml).unwrap()[0].clone()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_params() {
+        assert_eq!(
+            get_params("name: curl\nenablerepo: epel, crb\nupdate_cache: true"),
+            Params {
+                name: vec!["curl".to_string()],
+                state: State::Present,
+                enablerepo: vec!["epel".to_string(), "crb".to_string()],
+                disablerepo: Vec::new(),
+                update_cache: true,
+            }
+        );
+        let error = parse_params(YamlLoader::load_from_str("state: latest").unwrap()[0].clone())
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_apply_install_missing() {
+        let params = get_params("name:\n  - curl\n  - git\nenablerepo: epel");
+        let mut calls = Vec::new();
+        let result = apply(&params, "dnf", &mut fake_dnf(&["curl"], "", &mut calls)).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(result.get_extra(), Some(json!({"packages": ["git"]})));
+        assert_eq!(
+            calls,
+            vec![
+                "rpm -q curl",
+                "rpm -q git",
+                "dnf install -y --enablerepo=epel git"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_already_installed() {
+        let params = get_params("name:\n  - curl\n  - git");
+        let mut calls = Vec::new();
+        let result = apply(
+            &params,
+            "yum",
+            &mut fake_dnf(&["curl", "git"], "", &mut calls),
+        )
+        .unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(result.get_extra(), Some(json!({"packages": []})));
+        assert_eq!(calls, vec!["rpm -q curl", "rpm -q git"]);
+    }
+
+    #[test]
+    fn test_apply_absent() {
+        let params = get_params("name: curl, git\nstate: absent");
+        let mut calls = Vec::new();
+        let result = apply(&params, "dnf", &mut fake_dnf(&["git"], "", &mut calls)).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(
+            calls,
+            vec!["rpm -q curl", "rpm -q git", "dnf remove -y git"]
+        );
+    }
+
+    #[test]
+    fn test_apply_latest() {
+        let params = get_params("name: curl, git, vim\nstate: latest\nupdate_cache: true");
+        let updates = "\ncurl.x86_64    8.5.0-1.fc39    updates\n";
+        let mut calls = Vec::new();
+        let result = apply(
+            &params,
+            "dnf",
+            &mut fake_dnf(&["curl", "git"], updates, &mut calls),
+        )
+        .unwrap();
+        assert!(result.get_changed());
+        assert_eq!(
+            result.get_extra(),
+            Some(json!({"packages": ["vim", "curl"]}))
+        );
+        assert_eq!(
+            calls,
+            vec![
+                "dnf makecache",
+                "rpm -q curl",
+                "rpm -q git",
+                "rpm -q vim",
+                "dnf install -y vim",
+                "dnf check-update -q curl git",
+                "dnf upgrade -y curl",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_latest_up_to_date() {
+        let params = get_params("name: curl\nstate: latest");
+        let mut calls = Vec::new();
+        let result = apply(&params, "dnf", &mut fake_dnf(&["curl"], "", &mut calls)).unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(calls, vec!["rpm -q curl", "dnf check-update -q curl"]);
+    }
+
+    #[test]
+    fn test_parse_updates() {
+        let stdout = "Last metadata expiration check: 0:01:02 ago.\n\
+            curl.x86_64          8.5.0-1.fc39       updates\n\
+            python3-libs.x86_64  3.12.1-2.fc39      updates\n";
+        assert_eq!(parse_updates(stdout), vec!["curl", "python3-libs"]);
+    }
+}
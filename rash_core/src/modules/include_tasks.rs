@@ -0,0 +1,223 @@
+/// ANCHOR: module
+/// # include_tasks
+///
+/// Include and execute tasks from another file at runtime.
+///
+/// ## Parameters
+///
+/// ```yaml
+/// file:
+///   type: string
+///   required: true
+///   description: |
+///     Path of the tasks file. Relative paths are relative to script directory.
+///     It can be passed directly as `include_tasks` value.
+/// vars:
+///   type: dict
+///   description: |
+///     Vars only accessible from included tasks. Other vars defined by included tasks
+///     remain accessible after the include.
+/// ```
+///
+/// ## Example
+///
+/// ```yaml
+/// - include_tasks: "tasks/main.rh"
+///
+/// - include_tasks:
+///     file: "tasks/install.rh"
+///     vars:
+///       package: rash
+/// ```
+/// ANCHOR_END: module
+use crate::context::Context;
+use crate::error::{Error, ErrorKind, Result};
+use crate::modules::{get_param, set_vars, validate_params, ModuleResult};
+use crate::task::read_file;
+use crate::utils::tera::render_string;
+use crate::vars::Vars;
+
+use std::path::{Path, PathBuf};
+
+use yaml_rust::Yaml;
+
+#[derive(Debug, PartialEq)]
+struct Params {
+    file: String,
+    vars: Option<Yaml>,
+}
+
+fn parse_params(yaml: &Yaml) -> Result<Params> {
+    trace!("parse params: {:?}", yaml);
+    if let Some(file) = yaml.as_str() {
+        return Ok(Params {
+            file: file.to_string(),
+            vars: None,
+        });
+    };
+    validate_params("include_tasks", yaml, &["file"], &["vars"])?;
+    let vars = match &yaml["vars"] {
+        Yaml::BadValue => None,
+        Yaml::Hash(_) => Some(yaml["vars"].clone()),
+        vars => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("param 'vars' {:?} must be a dict", vars),
+            ))
+        }
+    };
+    Ok(Params {
+        file: get_param(yaml, "file")?,
+        vars,
+    })
+}
+
+/// Resolve relative paths from script directory, accessible from `rash.dir` var.
+fn get_path(file: &str, vars: &Vars) -> PathBuf {
+    let path = Path::new(file);
+    match vars
+        .get("rash")
+        .and_then(|rash| rash.get("dir"))
+        .and_then(|dir| dir.as_str())
+    {
+        Some(dir) if path.is_relative() => Path::new(dir).join(path),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn render_vars(yaml: &Yaml, vars: &Vars) -> Result<Yaml> {
+    // safe unwrap: verified in parse_params
+    yaml.as_hash()
+        .unwrap()
+        .iter()
+        .map(|(key, value)| match value.as_str() {
+            Some(s) => Ok((key.clone(), Yaml::String(render_string(s, vars.clone())?))),
+            None => Ok((key.clone(), value.clone())),
+        })
+        .collect::<Result<_>>()
+        .map(Yaml::Hash)
+}
+
+pub fn exec(optional_params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
+    let params = parse_params(&optional_params)?;
+    let path = get_path(&params.file, &vars);
+    let tasks = read_file(path.clone())
+        .map_err(|e| e.context(format!("include_tasks '{}'", path.display())))?;
+
+    let (scoped_vars, scoped_keys) = match &params.vars {
+        Some(yaml) => {
+            let rendered = render_vars(yaml, &vars)?;
+            let keys = rendered
+                .as_hash()
+                .unwrap()
+                .keys()
+                .filter_map(|key| key.as_str().map(String::from))
+                .collect::<Vec<String>>();
+            (set_vars::exec(rendered, vars.clone())?.1, keys)
+        }
+        None => (vars.clone(), Vec::new()),
+    };
+
+    let mut new_vars = Context::exec_all(Context::new(tasks, scoped_vars))?;
+    scoped_keys.iter().for_each(|key| match vars.get(key) {
+        Some(value) => new_vars.insert(key, value),
+        None => {
+            new_vars.remove(key);
+        }
+    });
+
+    Ok((
+        ModuleResult::builder()
+            .output(path.to_string_lossy())
+            .build(),
+        new_vars,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::File;
+    use std::io::Write;
+
+    use tempfile::tempdir;
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn test_parse_params() {
+        let yaml = YamlLoader::load_from_str("tasks.rh").unwrap()[0].clone();
+        assert_eq!(
+            parse_params(&yaml).unwrap(),
+            Params {
+                file: "tasks.rh".to_string(),
+                vars: None,
+            }
+        );
+
+        let yaml =
+            YamlLoader::load_from_str("file: tasks.rh\nvars:\n  foo: boo").unwrap()[0].clone();
+        let params = parse_params(&yaml).unwrap();
+        assert_eq!(params.file, "tasks.rh");
+        assert_eq!(params.vars.unwrap()["foo"].as_str(), Some("boo"));
+    }
+
+    #[test]
+    fn test_parse_params_invalid_vars() {
+        let yaml = YamlLoader::load_from_str("file: tasks.rh\nvars: foo").unwrap()[0].clone();
+        let error = parse_params(&yaml).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_get_path() {
+        let mut vars = Vars::new();
+        assert_eq!(get_path("tasks.rh", &vars), PathBuf::from("tasks.rh"));
+        vars.insert("rash", &json!({"dir": "/scripts"}));
+        assert_eq!(
+            get_path("tasks.rh", &vars),
+            PathBuf::from("/scripts/tasks.rh")
+        );
+        assert_eq!(get_path("/tasks.rh", &vars), PathBuf::from("/tasks.rh"));
+    }
+
+    #[test]
+    fn test_exec() {
+        let dir = tempdir().unwrap();
+        let mut file = File::create(dir.path().join("included.rh")).unwrap();
+        writeln!(
+            file,
+            r#"
+- assert:
+    that:
+      - package == "rash"
+
+- set_vars:
+    installed: "{{{{ package }}}}"
+"#
+        )
+        .unwrap();
+
+        let mut vars = Vars::new();
+        vars.insert("rash", &json!({"dir": dir.path()}));
+        vars.insert("name", "rash");
+        let yaml = YamlLoader::load_from_str("file: included.rh\nvars:\n  package: \"{{ name }}\"")
+            .unwrap()[0]
+            .clone();
+        let (result, new_vars) = exec(yaml, vars).unwrap();
+        assert_eq!(
+            result.get_output().unwrap(),
+            dir.path().join("included.rh").to_string_lossy()
+        );
+        assert_eq!(new_vars.get("installed").unwrap(), "rash");
+        assert!(!new_vars.contains_key("package"));
+    }
+
+    #[test]
+    fn test_exec_not_found() {
+        let yaml =
+            YamlLoader::load_from_str("/tmp/this/file/does/not/exist.rh").unwrap()[0].clone();
+        let error = exec(yaml, Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,606 @@
+/// ANCHOR: module
+/// # lineinfile
+///
+/// Ensure a particular line is in a file, or replace an existing line using a regex.
+///
+/// ## Parameters
+///
+/// ```yaml
+/// path:
+///   type: string
+///   required: true
+///   description: The file to modify.
+/// line:
+///   type: string
+///   description: |
+///     The line to insert or replace. Required if `state` is `present`.
+/// regexp:
+///   type: string
+///   description: |
+///     Regex to look for in every line of the file. With `state: present` the last
+///     matching line is replaced with `line`. With `state: absent` all matching lines
+///     are removed.
+/// state:
+///   type: string
+///   choices:
+///     - present
+///     - absent
+///   description: Whether the line should be there or not. Defaults to present.
+/// backrefs:
+///   type: bool
+///   description: |
+///     Expand `regexp` capture groups referenced in `line`, like `\1`. If `regexp`
+///     does not match any line, the file is left unchanged. Defaults to false.
+/// insertafter:
+///   type: string
+///   description: |
+///     Used when `line` is added because `regexp` did not match. Either `EOF` to append
+///     it, `BOF` to prepend it, or a regex to insert it after the last matching line.
+///     If the regex does not match, `line` is appended. Defaults to `EOF`.
+/// insertbefore:
+///   type: string
+///   description: |
+///     Same as `insertafter` but inserting `line` before the last matching line.
+///     Only one of `insertafter` or `insertbefore` can be set.
+/// create:
+///   type: bool
+///   description: |
+///     Create the file if it does not exist. Otherwise the task fails. Defaults to false.
+/// mode:
+///   type: string
+///   description: |
+///     Permissions of the file. Defaults to current ones, or `"0644"` if it is created.
+///     Octal like `"0644"` or symbolic like `u+rwx,g=rx,o-rwx`.
+/// validate:
+///   type: string
+///   description: |
+///     Command to validate the edited file before writing it, with `%s` replaced by
+///     the path of a temporary file holding it. If it fails, the file is not modified.
+/// owner:
+///   type: string
+///   description: Name or uid of the user that should own the file.
+/// group:
+///   type: string
+///   description: Name or gid of the group that should own the file.
+/// ```
+///
+/// ## Example
+///
+/// ```yaml
+/// - lineinfile:
+///     path: /etc/ssh/sshd_config
+///     regexp: 'PermitRootLogin '
+///     line: PermitRootLogin no
+///
+/// - lineinfile:
+///     path: /etc/ssh/sshd_config
+///     regexp: '(ListenAddress) .*'
+///     line: '\1 0.0.0.0'
+///     backrefs: true
+///
+/// - lineinfile:
+///     path: /etc/ssh/sshd_config
+///     line: '# managed by rash'
+///     insertbefore: BOF
+///
+/// - lineinfile:
+///     path: /etc/ssh/sshd_config
+///     line: Port 2222
+///     insertafter: '#Port '
+///
+/// - lineinfile:
+///     path: /etc/hosts
+///     regexp: 'old.example.com'
+///     state: absent
+/// ```
+/// ANCHOR_END: module
+use crate::error::{Error, ErrorKind, Result};
+use crate::modules::copy::{parse_validate_param, verify_file, Params as CopyParams};
+use crate::modules::{
+    get_optional_param, get_param, get_param_bool_or, get_param_mode, validate_params, ModuleResult,
+};
+use crate::utils::to_regex_replacement;
+use crate::vars::Vars;
+
+use std::fs::read_to_string;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use regex::Regex;
+use yaml_rust::Yaml;
+
+#[derive(Debug, PartialEq)]
+enum State {
+    Present,
+    Absent,
+}
+
+/// Where a new line is inserted.
+#[derive(Debug)]
+enum Insert {
+    Bof,
+    Eof,
+    After(Regex),
+    Before(Regex),
+}
+
+#[derive(Debug)]
+struct Params {
+    path: String,
+    line: Option<String>,
+    regexp: Option<Regex>,
+    state: State,
+    backrefs: bool,
+    insert: Insert,
+    create: bool,
+    mode: Option<String>,
+    owner: Option<String>,
+    group: Option<String>,
+    validate: Option<String>,
+}
+
+const REQUIRED_PARAMS: &[&str] = &["path"];
+const OPTIONAL_PARAMS: &[&str] = &[
+    "line",
+    "regexp",
+    "state",
+    "backrefs",
+    "insertafter",
+    "insertbefore",
+    "create",
+    "mode",
+    "owner",
+    "group",
+    "validate",
+];
+
+fn parse_state(yaml: &Yaml) -> Result<State> {
+    match get_optional_param(yaml, "state")?.as_deref() {
+        None | Some("present") => Ok(State::Present),
+        Some("absent") => Ok(State::Absent),
+        Some(state) => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "param 'state' must be one of present or absent, found: {}",
+                state
+            ),
+        )),
+    }
+}
+
+fn parse_regex(s: &str) -> Result<Regex> {
+    Regex::new(s).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Parse `insertafter` and `insertbefore` params. `EOF` and `BOF` are symbolic positions,
+/// any other value is a regex.
+fn parse_insert(yaml: &Yaml) -> Result<Insert> {
+    match (
+        get_optional_param(yaml, "insertafter")?.as_deref(),
+        get_optional_param(yaml, "insertbefore")?.as_deref(),
+    ) {
+        (Some(_), Some(_)) => Err(Error::new(
+            ErrorKind::InvalidData,
+            "insertafter and insertbefore params are mutually exclusive",
+        )),
+        (None, None) | (Some("EOF"), None) | (None, Some("EOF")) => Ok(Insert::Eof),
+        (Some("BOF"), None) | (None, Some("BOF")) => Ok(Insert::Bof),
+        (Some(s), None) => Ok(Insert::After(parse_regex(s)?)),
+        (None, Some(s)) => Ok(Insert::Before(parse_regex(s)?)),
+    }
+}
+
+fn parse_params(yaml: Yaml) -> Result<Params> {
+    trace!("parse params: {:?}", yaml);
+    validate_params("lineinfile", &yaml, REQUIRED_PARAMS, OPTIONAL_PARAMS)?;
+    let regexp = get_optional_param(&yaml, "regexp")?
+        .map(|s| parse_regex(&s))
+        .transpose()?;
+    let mode = match yaml["mode"] {
+        Yaml::BadValue => None,
+        _ => Some(get_param_mode(&yaml, "0644")?),
+    };
+    let params = Params {
+        path: get_param(&yaml, "path")?,
+        line: get_optional_param(&yaml, "line")?,
+        regexp,
+        state: parse_state(&yaml)?,
+        backrefs: get_param_bool_or(&yaml, "backrefs", false)?,
+        insert: parse_insert(&yaml)?,
+        create: get_param_bool_or(&yaml, "create", false)?,
+        mode,
+        owner: get_optional_param(&yaml, "owner")?,
+        group: get_optional_param(&yaml, "group")?,
+        validate: parse_validate_param(&yaml)?,
+    };
+    match params.state {
+        State::Present if params.line.is_none() => Err(Error::new(
+            ErrorKind::InvalidData,
+            "param 'line' is required when state is present",
+        )),
+        State::Absent if params.line.is_none() && params.regexp.is_none() => Err(Error::new(
+            ErrorKind::InvalidData,
+            "one of line or regexp params is required when state is absent",
+        )),
+        _ if params.backrefs && params.regexp.is_none() => Err(Error::new(
+            ErrorKind::InvalidData,
+            "param 'backrefs' requires regexp",
+        )),
+        _ => Ok(params),
+    }
+}
+
+/// Insert `line` at `insert` position if it is not already in `lines`, returning true if it
+/// was added.
+fn insert(lines: &mut Vec<String>, line: &str, insert: &Insert) -> bool {
+    if lines.iter().any(|l| l == line) {
+        return false;
+    };
+    let index = match insert {
+        Insert::Bof => 0,
+        Insert::Eof => lines.len(),
+        Insert::After(regex) => lines
+            .iter()
+            .rposition(|l| regex.is_match(l))
+            .map_or(lines.len(), |i| i + 1),
+        Insert::Before(regex) => lines
+            .iter()
+            .rposition(|l| regex.is_match(l))
+            .unwrap_or(lines.len()),
+    };
+    lines.insert(index, line.to_string());
+    true
+}
+
+/// Ensure `line` is in `lines`, returning true if they were modified.
+fn present(lines: &mut Vec<String>, params: &Params) -> bool {
+    // parse_params ensures line is defined when state is present
+    let line = params.line.as_deref().unwrap_or_default();
+    let regexp = match &params.regexp {
+        Some(regexp) => regexp,
+        None => return insert(lines, line, &params.insert),
+    };
+    match lines.iter().rposition(|l| regexp.is_match(l)) {
+        Some(index) => {
+            let new_line = if params.backrefs {
+                let mut expanded = String::new();
+                // index comes from a matching line
+                regexp
+                    .captures(&lines[index])
+                    .unwrap()
+                    .expand(&to_regex_replacement(line), &mut expanded);
+                expanded
+            } else {
+                line.to_string()
+            };
+            if lines[index] == new_line {
+                return false;
+            };
+            lines[index] = new_line;
+            true
+        }
+        None if params.backrefs => false,
+        None => insert(lines, line, &params.insert),
+    }
+}
+
+/// Remove lines matching `regexp` or equal to `line`, returning true if any was removed.
+fn absent(lines: &mut Vec<String>, params: &Params) -> bool {
+    let original_len = lines.len();
+    lines.retain(|l| match &params.regexp {
+        Some(regexp) => !regexp.is_match(l),
+        None => Some(l.as_str()) != params.line.as_deref(),
+    });
+    lines.len() != original_len
+}
+
+pub fn exec(optional_params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
+    let params = parse_params(optional_params)?;
+    let path = Path::new(&params.path);
+    let original = if path.exists() {
+        read_to_string(path)?
+    } else {
+        match params.state {
+            State::Absent => {
+                return Ok((
+                    ModuleResult::new(false, None, Some(params.path.clone())),
+                    vars,
+                ))
+            }
+            State::Present if !params.create => {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("path {} does not exist", params.path),
+                ))
+            }
+            State::Present => String::new(),
+        }
+    };
+
+    let mut lines = original.lines().map(String::from).collect::<Vec<String>>();
+    let modified = match params.state {
+        State::Present => present(&mut lines, &params),
+        State::Absent => absent(&mut lines, &params),
+    };
+    let content = match (modified, lines.is_empty()) {
+        (false, _) => original,
+        (true, true) => String::new(),
+        (true, false) => format!("{}\n", lines.join("\n")),
+    };
+
+    let mode = match (&params.mode, path.exists()) {
+        (Some(mode), _) => mode.clone(),
+        (None, true) => format!("{:04o}", path.metadata()?.permissions().mode() & 0o777),
+        (None, false) => "0644".to_string(),
+    };
+    let result = verify_file(
+        CopyParams::new(
+            content.into_bytes(),
+            params.path,
+            mode,
+            params.owner,
+            params.group,
+        )
+        .with_validate(params.validate),
+    )?;
+    Ok((result, vars))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::{set_permissions, write};
+
+    use tempfile::tempdir;
+    use yaml_rust::YamlLoader;
+
+    fn get_yaml(s: &str) -> Yaml {
+        YamlLoader::load_from_str(s).unwrap()[0].clone()
+    }
+
+    const CONFIG: &str = "# sshd\nPermitRootLogin yes\nListenAddress 127.0.0.1\n";
+
+    #[test]
+    fn test_exec_regexp() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("sshd_config");
+        write(&path, CONFIG).unwrap();
+        let yaml = get_yaml(&format!(
+            "path: {}\nregexp: '^PermitRootLogin '\nline: PermitRootLogin no",
+            path.display()
+        ));
+
+        let (result, _) = exec(yaml.clone(), Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(
+            read_to_string(&path).unwrap(),
+            "# sshd\nPermitRootLogin no\nListenAddress 127.0.0.1\n"
+        );
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_changed());
+    }
+
+    #[test]
+    fn test_exec_line() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("sshd_config");
+        write(&path, CONFIG).unwrap();
+        let yaml = get_yaml(&format!("path: {}\nline: Port 2222", path.display()));
+
+        let (result, _) = exec(yaml.clone(), Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(
+            read_to_string(&path).unwrap(),
+            format!("{}Port 2222\n", CONFIG)
+        );
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_changed());
+    }
+
+    #[test]
+    fn test_exec_backrefs() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("sshd_config");
+        write(&path, CONFIG).unwrap();
+        let yaml = get_yaml(&format!(
+            "path: {}\nregexp: '^(ListenAddress) [0-9.]+$'\nline: '\\1 0.0.0.0'\nbackrefs: true",
+            path.display()
+        ));
+
+        let (result, _) = exec(yaml.clone(), Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(
+            read_to_string(&path).unwrap(),
+            "# sshd\nPermitRootLogin yes\nListenAddress 0.0.0.0\n"
+        );
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_changed());
+    }
+
+    #[test]
+    fn test_exec_backrefs_no_match() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("sshd_config");
+        write(&path, CONFIG).unwrap();
+        let yaml = get_yaml(&format!(
+            "path: {}\nregexp: '^(Port) .*'\nline: '\\1 2222'\nbackrefs: true",
+            path.display()
+        ));
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(read_to_string(&path).unwrap(), CONFIG);
+    }
+
+    #[test]
+    fn test_exec_insert_bof() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("sshd_config");
+        write(&path, CONFIG).unwrap();
+        let yaml = get_yaml(&format!(
+            "path: {}\nline: '# managed'\ninsertbefore: BOF",
+            path.display()
+        ));
+
+        let (result, _) = exec(yaml.clone(), Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(
+            read_to_string(&path).unwrap(),
+            format!("# managed\n{}", CONFIG)
+        );
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_changed());
+    }
+
+    #[test]
+    fn test_exec_insert_eof() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("sshd_config");
+        write(&path, CONFIG).unwrap();
+        let yaml = get_yaml(&format!(
+            "path: {}\nregexp: '^Port '\nline: Port 2222\ninsertafter: EOF",
+            path.display()
+        ));
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(
+            read_to_string(&path).unwrap(),
+            format!("{}Port 2222\n", CONFIG)
+        );
+    }
+
+    #[test]
+    fn test_exec_insert_regex() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("sshd_config");
+        write(&path, CONFIG).unwrap();
+        let yaml = get_yaml(&format!(
+            "path: {}\nline: Port 2222\ninsertafter: '^PermitRootLogin'",
+            path.display()
+        ));
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(
+            read_to_string(&path).unwrap(),
+            "# sshd\nPermitRootLogin yes\nPort 2222\nListenAddress 127.0.0.1\n"
+        );
+
+        let yaml = get_yaml(&format!(
+            "path: {}\nline: Protocol 2\ninsertbefore: '^Port'",
+            path.display()
+        ));
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(
+            read_to_string(&path).unwrap(),
+            "# sshd\nPermitRootLogin yes\nProtocol 2\nPort 2222\nListenAddress 127.0.0.1\n"
+        );
+    }
+
+    #[test]
+    fn test_insert_regex_no_match() {
+        let mut lines = vec!["foo".to_string()];
+        let after = Insert::After(Regex::new("boo").unwrap());
+        assert!(insert(&mut lines, "zoo", &after));
+        assert_eq!(lines, vec!["foo", "zoo"]);
+    }
+
+    #[test]
+    fn test_exec_absent() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("sshd_config");
+        write(&path, CONFIG).unwrap();
+        let yaml = get_yaml(&format!(
+            "path: {}\nregexp: '^[A-Z]'\nstate: absent",
+            path.display()
+        ));
+
+        let (result, _) = exec(yaml.clone(), Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(read_to_string(&path).unwrap(), "# sshd\n");
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_changed());
+    }
+
+    #[test]
+    fn test_exec_create() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("new.conf");
+        let yaml = get_yaml(&format!("path: {}\nline: foo", path.display()));
+        let error = exec(yaml, Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+
+        let yaml = get_yaml(&format!(
+            "path: {}\nline: foo\ncreate: true",
+            path.display()
+        ));
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(read_to_string(&path).unwrap(), "foo\n");
+        let mode = path.metadata().unwrap().permissions().mode() & 0o7777;
+        assert_eq!(format!("{:o}", mode), "644");
+    }
+
+    #[test]
+    fn test_exec_preserve_mode() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("sshd_config");
+        write(&path, CONFIG).unwrap();
+        set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        let yaml = get_yaml(&format!("path: {}\nline: Port 2222", path.display()));
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(result.get_changed());
+        let mode = path.metadata().unwrap().permissions().mode() & 0o7777;
+        assert_eq!(format!("{:o}", mode), "600");
+    }
+
+    #[test]
+    fn test_exec_validate() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("sshd_config");
+        write(&path, CONFIG).unwrap();
+        let yaml = get_yaml(&format!(
+            "path: {}\nline: Port 2222\nvalidate: \"! grep -q '^Port' %s\"",
+            path.display()
+        ));
+
+        let error = exec(yaml, Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(read_to_string(&path).unwrap(), CONFIG);
+        assert_eq!(dir.path().read_dir().unwrap().count(), 1);
+
+        let yaml = get_yaml(&format!(
+            "path: {}\nline: Port 2222\nvalidate: \"grep -q '^Port' %s\"",
+            path.display()
+        ));
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(
+            read_to_string(&path).unwrap(),
+            format!("{}Port 2222\n", CONFIG)
+        );
+    }
+
+    #[test]
+    fn test_parse_params_invalid() {
+        let _ = parse_params(get_yaml("path: /tmp/foo")).unwrap_err();
+        let _ = parse_params(get_yaml("path: /tmp/foo\nstate: absent")).unwrap_err();
+        let _ = parse_params(get_yaml("path: /tmp/foo\nline: foo\nstate: boo")).unwrap_err();
+        let _ = parse_params(get_yaml("path: /tmp/foo\nline: foo\nbackrefs: true")).unwrap_err();
+        let _ = parse_params(get_yaml("path: /tmp/foo\nline: foo\nregexp: '('")).unwrap_err();
+        let _ = parse_params(get_yaml(
+            "path: /tmp/foo\nline: foo\ninsertafter: EOF\ninsertbefore: BOF",
+        ))
+        .unwrap_err();
+        let _ = parse_params(get_yaml("path: /tmp/foo\nline: foo\nvalidate: 'true'")).unwrap_err();
+    }
+}
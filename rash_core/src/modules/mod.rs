@@ -1,16 +1,28 @@
+mod apk;
+mod assemble;
 mod assert;
+mod block;
 mod command;
 mod copy;
+mod debug;
+mod dnf;
+mod include_tasks;
+mod lineinfile;
+mod package;
+mod package_manager;
 mod set_vars;
+mod setup;
 mod template;
 
 use crate::error::{Error, ErrorKind, Result};
 use crate::vars::Vars;
 
 use std::collections::HashMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
 
 use serde::Serialize;
-use serde_json::Value;
+use serde_json::{Map, Value};
 use yaml_rust::Yaml;
 
 /// Return values of a [`Module`] execution.
@@ -25,15 +37,42 @@ pub struct ModuleResult {
     output: Option<String>,
     /// Modules store the data they return in the Extra field.
     extra: Option<Value>,
+    /// Host where the module was executed, when task defines `delegate_to`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delegate_to: Option<String>,
+    /// Short description of the loop item, rendered from `loop_control.label`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    /// True when the module ran but its execution failed, e.g.: non-zero `rc`.
+    #[serde(skip_serializing_if = "is_false")]
+    failed: bool,
+    /// Content before and after the change, when the module modified a file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<Diff>,
 }
 // ANCHOR_END: module_result
 
+/// Text content of a changed file before and after module execution.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Diff {
+    pub before: String,
+    pub after: String,
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
 impl ModuleResult {
     pub fn new(changed: bool, extra: Option<Value>, output: Option<String>) -> Self {
         Self {
             changed,
             extra,
             output,
+            delegate_to: None,
+            label: None,
+            failed: false,
+            diff: None,
         }
     }
 
@@ -51,6 +90,135 @@ impl ModuleResult {
     pub fn get_output(&self) -> Option<String> {
         self.output.clone()
     }
+
+    /// Return delegation target.
+    pub fn get_delegate_to(&self) -> Option<String> {
+        self.delegate_to.clone()
+    }
+
+    /// Return loop item label.
+    pub fn get_label(&self) -> Option<String> {
+        self.label.clone()
+    }
+
+    /// Return failed.
+    pub fn get_failed(&self) -> bool {
+        self.failed
+    }
+
+    /// Return diff.
+    pub fn get_diff(&self) -> Option<Diff> {
+        self.diff.clone()
+    }
+
+    /// Return a message describing the failure: `stderr` if not empty, or `output`.
+    pub fn get_failed_msg(&self) -> String {
+        self.extra
+            .as_ref()
+            .and_then(|extra| extra.get("stderr"))
+            .and_then(Value::as_str)
+            .filter(|stderr| !stderr.is_empty())
+            .map(String::from)
+            .or_else(|| self.output.clone())
+            .unwrap_or_else(|| "module failed".to_string())
+    }
+
+    /// Set delegation target.
+    pub fn set_delegate_to(&mut self, delegate_to: Option<String>) {
+        self.delegate_to = delegate_to;
+    }
+
+    /// Set loop item label.
+    pub fn set_label(&mut self, label: Option<String>) {
+        self.label = label;
+    }
+
+    /// Return a [`ModuleResultBuilder`] to create results with consistent fields.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let result = ModuleResult::builder()
+    ///     .changed(true)
+    ///     .output("/tmp/foo")
+    ///     .rc(0)
+    ///     .build();
+    /// ```
+    ///
+    /// [`ModuleResultBuilder`]: struct.ModuleResultBuilder.html
+    pub fn builder() -> ModuleResultBuilder {
+        ModuleResultBuilder::default()
+    }
+}
+
+/// Fluent builder of [`ModuleResult`]. `rc`, `stdout`, `stderr` and custom keys are stored
+/// in `extra`, which is `None` if no one is set.
+///
+/// [`ModuleResult`]: struct.ModuleResult.html
+#[derive(Debug, Default)]
+pub struct ModuleResultBuilder {
+    changed: bool,
+    failed: bool,
+    output: Option<String>,
+    extra: Map<String, Value>,
+    diff: Option<Diff>,
+}
+
+impl ModuleResultBuilder {
+    pub fn changed(mut self, changed: bool) -> Self {
+        self.changed = changed;
+        self
+    }
+
+    pub fn failed(mut self, failed: bool) -> Self {
+        self.failed = failed;
+        self
+    }
+
+    pub fn output<S: Into<String>>(mut self, output: S) -> Self {
+        self.output = Some(output.into());
+        self
+    }
+
+    pub fn rc(self, rc: Option<i32>) -> Self {
+        self.extra("rc", json!(rc))
+    }
+
+    pub fn stdout<S: Into<String>>(self, stdout: S) -> Self {
+        self.extra("stdout", Value::String(stdout.into()))
+    }
+
+    pub fn stderr<S: Into<String>>(self, stderr: S) -> Self {
+        self.extra("stderr", Value::String(stderr.into()))
+    }
+
+    pub fn extra(mut self, key: &str, value: Value) -> Self {
+        self.extra.insert(key.to_string(), value);
+        self
+    }
+
+    pub fn diff<S: Into<String>>(mut self, before: S, after: S) -> Self {
+        self.diff = Some(Diff {
+            before: before.into(),
+            after: after.into(),
+        });
+        self
+    }
+
+    pub fn build(self) -> ModuleResult {
+        ModuleResult {
+            changed: self.changed,
+            output: self.output,
+            extra: if self.extra.is_empty() {
+                None
+            } else {
+                Some(Value::Object(self.extra))
+            },
+            delegate_to: None,
+            label: None,
+            failed: self.failed,
+            diff: self.diff,
+        }
+    }
 }
 
 /// Basic execution structure. Build with module name and module exec function.
@@ -75,16 +243,7 @@ impl Module {
     pub fn test_example() -> Self {
         Module {
             name: "test",
-            exec_fn: |_, _| {
-                Ok((
-                    ModuleResult {
-                        changed: true,
-                        extra: None,
-                        output: None,
-                    },
-                    Vars::new(),
-                ))
-            },
+            exec_fn: |_, _| Ok((ModuleResult::builder().changed(true).build(), Vars::new())),
         }
     }
 }
@@ -92,6 +251,20 @@ impl Module {
 lazy_static! {
     pub static ref MODULES: HashMap<&'static str, Module> = {
         vec![
+            (
+                "apk",
+                Module {
+                    name: "apk",
+                    exec_fn: apk::exec,
+                },
+            ),
+            (
+                "assemble",
+                Module {
+                    name: "assemble",
+                    exec_fn: assemble::exec,
+                },
+            ),
             (
                 "assert",
                 Module {
@@ -99,6 +272,13 @@ lazy_static! {
                     exec_fn: assert::exec,
                 },
             ),
+            (
+                "block",
+                Module {
+                    name: "block",
+                    exec_fn: block::exec,
+                },
+            ),
             (
                 "command",
                 Module {
@@ -113,6 +293,41 @@ lazy_static! {
                     exec_fn: copy::exec,
                 },
             ),
+            (
+                "debug",
+                Module {
+                    name: "debug",
+                    exec_fn: debug::exec,
+                },
+            ),
+            (
+                "dnf",
+                Module {
+                    name: "dnf",
+                    exec_fn: dnf::exec,
+                },
+            ),
+            (
+                "include_tasks",
+                Module {
+                    name: "include_tasks",
+                    exec_fn: include_tasks::exec,
+                },
+            ),
+            (
+                "lineinfile",
+                Module {
+                    name: "lineinfile",
+                    exec_fn: lineinfile::exec,
+                },
+            ),
+            (
+                "package",
+                Module {
+                    name: "package",
+                    exec_fn: package::exec,
+                },
+            ),
             (
                 "set_vars",
                 Module {
@@ -120,6 +335,13 @@ lazy_static! {
                     exec_fn: set_vars::exec,
                 },
             ),
+            (
+                "setup",
+                Module {
+                    name: "setup",
+                    exec_fn: setup::exec,
+                },
+            ),
             (
                 "template",
                 Module {
@@ -138,6 +360,191 @@ pub fn is_module(module: &str) -> bool {
     MODULES.get(module).is_some()
 }
 
+fn parse_octal(s: &str) -> Result<u32> {
+    match s.len() {
+        3 => u32::from_str_radix(&s, 8).map_err(|e| Error::new(ErrorKind::InvalidData, e)),
+        4 => u32::from_str_radix(s.get(1..).unwrap(), 8)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e)),
+        _ => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("{} cannot be parsed to octal", s),
+        )),
+    }
+}
+
+/// Parse octal (`0644`) or symbolic (`u+rwx,g=rx,o-rwx`) modes. Symbolic modes are applied
+/// over `current` mode; `X` only grants execute if `is_dir` or any execute bit is already set.
+pub fn parse_mode(s: &str, current: u32, is_dir: bool) -> Result<u32> {
+    if s.chars().all(|c| c.is_ascii_digit()) {
+        return parse_octal(s);
+    };
+    s.split(',').try_fold(current & 0o7777, |mode, clause| {
+        apply_symbolic_mode(clause, mode, is_dir)
+    })
+}
+
+fn apply_symbolic_mode(clause: &str, mode: u32, is_dir: bool) -> Result<u32> {
+    let invalid = || {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("{} cannot be parsed to symbolic mode", clause),
+        )
+    };
+
+    let op_index = clause.find(|c| "+-=".contains(c)).ok_or_else(invalid)?;
+    let who_mask = match &clause[..op_index] {
+        "" => 0o7777,
+        who => who.chars().try_fold(0, |mask, c| match c {
+            'u' => Ok(mask | 0o4700),
+            'g' => Ok(mask | 0o2070),
+            'o' => Ok(mask | 0o1007),
+            'a' => Ok(mask | 0o7777),
+            _ => Err(invalid()),
+        })?,
+    };
+
+    let mut result = mode;
+    let mut actions = clause[op_index..].chars().peekable();
+    while let Some(op) = actions.next() {
+        let mut perms = 0;
+        while let Some(c) = actions.peek().filter(|c| !"+-=".contains(**c)) {
+            perms |= match c {
+                'r' => 0o444,
+                'w' => 0o222,
+                'x' => 0o111,
+                'X' if is_dir || mode & 0o111 != 0 => 0o111,
+                'X' => 0,
+                's' => 0o6000,
+                't' => 0o1000,
+                _ => return Err(invalid()),
+            };
+            actions.next();
+        }
+        let bits = perms & who_mask;
+        result = match op {
+            '+' => result | bits,
+            '-' => result & !bits,
+            '=' => (result & !who_mask) | bits,
+            _ => return Err(invalid()),
+        };
+    }
+    Ok(result)
+}
+
+/// Mode value to set source file permissions in destination.
+pub const PRESERVE_MODE: &str = "preserve";
+
+/// Return `mode`, or `src` permissions in octal if it is [`PRESERVE_MODE`].
+///
+/// [`PRESERVE_MODE`]: constant.PRESERVE_MODE.html
+pub fn resolve_preserve_mode(mode: &str, src: Option<&Path>) -> Result<String> {
+    if mode != PRESERVE_MODE {
+        return Ok(mode.to_string());
+    };
+    let src = src.ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("mode {} requires a src file", PRESERVE_MODE),
+        )
+    })?;
+    // & 0o777 because only permission bits are supported by parse_mode
+    Ok(format!(
+        "{:04o}",
+        src.metadata()?.permissions().mode() & 0o777
+    ))
+}
+
+/// Get `mode` param as string, validated with [`parse_mode`]. Unquoted octal numbers
+/// are rejected because YAML parses `0644` as an integer. [`PRESERVE_MODE`] is returned as is,
+/// to be resolved with [`resolve_preserve_mode`].
+///
+/// [`parse_mode`]: fn.parse_mode.html
+/// [`PRESERVE_MODE`]: constant.PRESERVE_MODE.html
+/// [`resolve_preserve_mode`]: fn.resolve_preserve_mode.html
+pub fn get_param_mode(yaml: &Yaml, default: &str) -> Result<String> {
+    let mode = match &yaml["mode"] {
+        Yaml::BadValue => default.to_string(),
+        Yaml::Integer(i) => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("param 'mode' {} must be a quoted string like \"0644\"", i),
+            ))
+        }
+        _ => get_param(yaml, "mode")?,
+    };
+    if mode == PRESERVE_MODE {
+        return Ok(mode);
+    };
+    // validate mode before running
+    parse_mode(&mode, 0o644, false)?;
+    Ok(mode)
+}
+
+/// Check that all `required` params are defined and that there are no params other than
+/// `required` and `optional` ones, failing with an error which names the offending params and
+/// lists all valid params of the `module`.
+///
+/// # Example
+/// ```ignore
+/// validate_params("template", &yaml, &["src", "dest"], &["mode"]).unwrap();
+/// ```
+pub fn validate_params(
+    module: &str,
+    yaml: &Yaml,
+    required: &[&str],
+    optional: &[&str],
+) -> Result<()> {
+    let mut valid_params = required
+        .iter()
+        .chain(optional)
+        .cloned()
+        .collect::<Vec<&str>>();
+    valid_params.sort_unstable();
+
+    let missing = required
+        .iter()
+        .filter(|key| yaml[**key].is_badvalue())
+        .cloned()
+        .collect::<Vec<&str>>();
+    if !missing.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "module `{}` missing required params: {}. Valid params are: {}",
+                module,
+                missing.join(", "),
+                valid_params.join(", "),
+            ),
+        ));
+    };
+
+    let unknown = yaml
+        .as_hash()
+        .map(|hash| {
+            hash.keys()
+                .map(|key| match key.as_str() {
+                    Some(s) => s.to_string(),
+                    None => format!("{:?}", key),
+                })
+                .filter(|key| !valid_params.contains(&key.as_str()))
+                .collect::<Vec<String>>()
+        })
+        .unwrap_or_default();
+    if !unknown.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "module `{}` unsupported params: {}. Valid params are: {}",
+                module,
+                unknown.join(", "),
+                valid_params.join(", "),
+            ),
+        ));
+    };
+
+    Ok(())
+}
+
 #[inline]
 fn get_key(yaml: &Yaml, key: &str) -> Result<Yaml> {
     if yaml[key].is_badvalue() {
@@ -170,6 +577,23 @@ pub fn get_param(yaml: &Yaml, key: &str) -> Result<String> {
     }
 }
 
+/// Get string param from [`Yaml`] like [`get_param`], returning `None` if it is missing.
+///
+/// # Example
+/// ```ignore
+/// let param = get_optional_param(&yaml, "foo").unwrap();
+/// assert_eq!(param, Some("boo".to_string()));
+/// ```
+/// [`Yaml`]: ../../yaml_rust/struct.Yaml.
+/// [`get_param`]: fn.get_param.html
+#[inline]
+pub fn get_optional_param(yaml: &Yaml, key: &str) -> Result<Option<String>> {
+    get_param(yaml, key).map(Some).or_else(|e| match e.kind() {
+        ErrorKind::NotFound => Ok(None),
+        _ => Err(e),
+    })
+}
+
 /// Get param from [`Yaml`] with `rash` [`Error`] wrappers.
 ///
 /// # Example
@@ -190,6 +614,23 @@ pub fn get_param_bool(yaml: &Yaml, key: &str) -> Result<bool> {
     }
 }
 
+/// Get bool param from [`Yaml`] like [`get_param_bool`], returning `default` if it is missing.
+///
+/// # Example
+/// ```ignore
+/// let param = get_param_bool_or(&yaml, "foo", true).unwrap();
+/// assert_eq!(param, true);
+/// ```
+/// [`Yaml`]: ../../yaml_rust/struct.Yaml.
+/// [`get_param_bool`]: fn.get_param_bool.html
+#[inline]
+pub fn get_param_bool_or(yaml: &Yaml, key: &str, default: bool) -> Result<bool> {
+    get_param_bool(yaml, key).or_else(|e| match e.kind() {
+        ErrorKind::NotFound => Ok(default),
+        _ => Err(e),
+    })
+}
+
 /// Get param from [`Yaml`] with `rash` [`Error`] wrappers.
 ///
 /// # Example
@@ -218,3 +659,350 @@ pub fn get_param_list(yaml: &Yaml, key: &str) -> Result<Vec<String>> {
         )),
     }
 }
+
+/// Get param from [`Yaml`] with `rash` [`Error`] wrappers. Non-negative integers and
+/// strings containing them are accepted.
+///
+/// # Example
+/// ```ignore
+/// let param = get_param_u64(&yaml, "timeout").unwrap();
+/// assert_eq!(param, 10);
+/// ```
+/// [`Yaml`]: ../../yaml_rust/struct.Yaml.
+/// [`Error`]: ../error/struct.Error.html
+#[inline]
+pub fn get_param_u64(yaml: &Yaml, key: &str) -> Result<u64> {
+    let value = match get_key(yaml, key)? {
+        Yaml::Integer(x) if x >= 0 => Some(x as u64),
+        Yaml::String(s) => s.trim().parse::<u64>().ok(),
+        _ => None,
+    };
+    value.ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "param '{}' not valid non-negative integer in: {:?}",
+                key, yaml
+            ),
+        )
+    })
+}
+
+/// Params accepted by modules guarded with [`check_creates_removes`].
+///
+/// [`check_creates_removes`]: fn.check_creates_removes.html
+pub const CREATES_REMOVES_PARAMS: &[&str] = &["creates", "removes"];
+
+/// Return a skipped result if `creates` path exists or `removes` path does not exist, so
+/// modules running actions can be idempotent.
+pub fn check_creates_removes(yaml: &Yaml) -> Result<Option<ModuleResult>> {
+    let get_path = |key| match get_param(yaml, key) {
+        Ok(path) => Ok(Some(path)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    };
+    let reason = match (get_path("creates")?, get_path("removes")?) {
+        (Some(creates), _) if Path::new(&creates).exists() => {
+            format!("skipped, since {} exists", creates)
+        }
+        (_, Some(removes)) if !Path::new(&removes).exists() => {
+            format!("skipped, since {} does not exist", removes)
+        }
+        _ => return Ok(None),
+    };
+    trace!("{}", reason);
+    Ok(Some(
+        ModuleResult::builder()
+            .changed(false)
+            .output(reason)
+            .extra("skipped", json!(true))
+            .build(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::tempdir;
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn test_parse_octal() {
+        assert_eq!(parse_octal("644").unwrap(), 0o644);
+        assert_eq!(parse_octal("0644").unwrap(), 0o644);
+        assert_eq!(parse_octal("777").unwrap(), 0o777);
+        assert_eq!(parse_octal("0444").unwrap(), 0o444);
+        assert_eq!(parse_octal("600").unwrap(), 0o600);
+        assert_eq!(parse_octal("0600").unwrap(), 0o600);
+    }
+
+    #[test]
+    fn test_parse_mode_octal() {
+        assert_eq!(parse_mode("0644", 0o600, false).unwrap(), 0o644);
+        assert_eq!(parse_mode("755", 0o600, false).unwrap(), 0o755);
+    }
+
+    #[test]
+    fn test_parse_mode_symbolic() {
+        assert_eq!(parse_mode("u+x", 0o644, false).unwrap(), 0o744);
+        assert_eq!(parse_mode("g-w", 0o664, false).unwrap(), 0o644);
+        assert_eq!(parse_mode("u+rwx,g=rx,o-rwx", 0o666, false).unwrap(), 0o750);
+        assert_eq!(parse_mode("a+r", 0o200, false).unwrap(), 0o644);
+        assert_eq!(parse_mode("+x", 0o644, false).unwrap(), 0o755);
+        assert_eq!(parse_mode("ug=rw,o=", 0o777, false).unwrap(), 0o660);
+        assert_eq!(parse_mode("u=rw-w", 0o700, false).unwrap(), 0o400);
+        assert_eq!(parse_mode("u+s,o+t", 0o755, false).unwrap(), 0o5755);
+    }
+
+    #[test]
+    fn test_parse_mode_symbolic_conditional_execute() {
+        assert_eq!(parse_mode("a+X", 0o644, false).unwrap(), 0o644);
+        assert_eq!(parse_mode("a+X", 0o744, false).unwrap(), 0o755);
+        assert_eq!(parse_mode("a+X", 0o644, true).unwrap(), 0o755);
+    }
+
+    #[test]
+    fn test_parse_mode_invalid() {
+        let error = parse_mode("u+y", 0o644, false).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        let error = parse_mode("z+x", 0o644, false).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        let error = parse_mode("rwx", 0o644, false).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_parse_mode_invalid_octal() {
+        ["", "12345", "0x644", "888", "64"].iter().for_each(|s| {
+            let error = parse_mode(s, 0o644, false).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidData, "{}", s);
+        });
+    }
+
+    #[test]
+    fn test_get_param_mode() {
+        let yaml = YamlLoader::load_from_str("mode: \"0600\"").unwrap()[0].clone();
+        assert_eq!(get_param_mode(&yaml, "0644").unwrap(), "0600");
+        let yaml = YamlLoader::load_from_str("mode: u+x").unwrap()[0].clone();
+        assert_eq!(get_param_mode(&yaml, "0644").unwrap(), "u+x");
+        let yaml = YamlLoader::load_from_str("dest: foo").unwrap()[0].clone();
+        assert_eq!(get_param_mode(&yaml, "0644").unwrap(), "0644");
+    }
+
+    #[test]
+    fn test_get_param_mode_preserve() {
+        let yaml = YamlLoader::load_from_str("mode: preserve").unwrap()[0].clone();
+        assert_eq!(get_param_mode(&yaml, "0644").unwrap(), PRESERVE_MODE);
+    }
+
+    #[test]
+    fn test_resolve_preserve_mode() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("foo");
+        std::fs::write(&path, "foo").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o750)).unwrap();
+
+        assert_eq!(
+            resolve_preserve_mode(PRESERVE_MODE, Some(&path)).unwrap(),
+            "0750"
+        );
+        assert_eq!(resolve_preserve_mode("0600", Some(&path)).unwrap(), "0600");
+        assert_eq!(resolve_preserve_mode("0600", None).unwrap(), "0600");
+        let _ = resolve_preserve_mode(PRESERVE_MODE, None).unwrap_err();
+    }
+
+    #[test]
+    fn test_get_param_mode_invalid() {
+        let yaml = YamlLoader::load_from_str("mode: 0600").unwrap()[0].clone();
+        let error = get_param_mode(&yaml, "0644").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        let yaml = YamlLoader::load_from_str("mode: u+y").unwrap()[0].clone();
+        let error = get_param_mode(&yaml, "0644").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_validate_params() {
+        let yaml = YamlLoader::load_from_str("src: foo\ndest: boo").unwrap()[0].clone();
+        validate_params("template", &yaml, &["src", "dest"], &["mode"]).unwrap();
+    }
+
+    #[test]
+    fn test_validate_params_missing() {
+        let yaml = YamlLoader::load_from_str("src: foo").unwrap()[0].clone();
+        let error = validate_params("template", &yaml, &["src", "dest"], &["mode"]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            format!("{}", error),
+            "module `template` missing required params: dest. Valid params are: dest, mode, src"
+        );
+    }
+
+    #[test]
+    fn test_validate_params_unknown() {
+        let yaml = YamlLoader::load_from_str("src: foo\ndest: boo\nfoo: yea\ndst: boo").unwrap()[0]
+            .clone();
+        let error = validate_params("template", &yaml, &["src", "dest"], &["mode"]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            format!("{}", error),
+            "module `template` unsupported params: foo, dst. Valid params are: dest, mode, src"
+        );
+    }
+
+    #[test]
+    fn test_validate_params_not_hash() {
+        let yaml = YamlLoader::load_from_str("ls -l").unwrap()[0].clone();
+        validate_params("command", &yaml, &[], &["cmd"]).unwrap();
+    }
+
+    #[test]
+    fn test_get_param_u64() {
+        let yaml =
+            YamlLoader::load_from_str("foo: 10\nboo: '20'\nzoo: -1\nyea: bar").unwrap()[0].clone();
+        assert_eq!(get_param_u64(&yaml, "foo").unwrap(), 10);
+        assert_eq!(get_param_u64(&yaml, "boo").unwrap(), 20);
+        assert_eq!(
+            get_param_u64(&yaml, "zoo").unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+        assert_eq!(
+            get_param_u64(&yaml, "yea").unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+        assert_eq!(
+            get_param_u64(&yaml, "missing").unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn test_get_param_bool_or() {
+        let yaml = YamlLoader::load_from_str("foo: true\nboo: false\nyea: bar").unwrap()[0].clone();
+        assert!(get_param_bool_or(&yaml, "foo", false).unwrap());
+        assert!(!get_param_bool_or(&yaml, "boo", true).unwrap());
+        assert!(get_param_bool_or(&yaml, "missing", true).unwrap());
+        assert!(!get_param_bool_or(&yaml, "missing", false).unwrap());
+        assert_eq!(
+            get_param_bool_or(&yaml, "yea", false).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_check_creates_removes() {
+        let dir = tempdir().unwrap();
+        let existing = dir.path().display();
+        let missing = dir.path().join("missing");
+        let missing = missing.display();
+        let check = |s: String| check_creates_removes(&YamlLoader::load_from_str(&s).unwrap()[0]);
+
+        let result = check(format!("creates: {}", existing)).unwrap().unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(result.get_extra(), Some(json!({"skipped": true})));
+        assert_eq!(
+            result.get_output(),
+            Some(format!("skipped, since {} exists", existing))
+        );
+        assert_eq!(check(format!("creates: {}", missing)).unwrap(), None);
+
+        let result = check(format!("removes: {}", missing)).unwrap().unwrap();
+        assert_eq!(
+            result.get_output(),
+            Some(format!("skipped, since {} does not exist", missing))
+        );
+        assert_eq!(check(format!("removes: {}", existing)).unwrap(), None);
+
+        assert_eq!(check("ls -l".to_string()).unwrap(), None);
+        let _ = check("creates: [foo]".to_string()).unwrap_err();
+    }
+
+    #[test]
+    fn test_module_result_builder_default() {
+        let result = ModuleResult::builder().build();
+        assert_eq!(result, ModuleResult::new(false, None, None));
+    }
+
+    #[test]
+    fn test_module_result_builder_copy_shape() {
+        let result = ModuleResult::builder()
+            .changed(true)
+            .output("/tmp/foo.txt")
+            .build();
+        assert_eq!(
+            json!(result),
+            json!({
+                "changed": true,
+                "output": "/tmp/foo.txt",
+                "extra": null,
+            })
+        );
+    }
+
+    #[test]
+    fn test_module_result_builder_diff() {
+        let result = ModuleResult::builder()
+            .changed(true)
+            .diff("foo\n", "boo\n")
+            .build();
+        assert_eq!(
+            result.get_diff(),
+            Some(Diff {
+                before: "foo\n".to_string(),
+                after: "boo\n".to_string(),
+            })
+        );
+        assert_eq!(
+            json!(result)["diff"],
+            json!({"before": "foo\n", "after": "boo\n"})
+        );
+        assert!(json!(ModuleResult::builder().build()).get("diff").is_none());
+    }
+
+    #[test]
+    fn test_module_result_builder_failed() {
+        let result = ModuleResult::builder()
+            .failed(true)
+            .stderr("boo")
+            .output("foo")
+            .build();
+        assert!(result.get_failed());
+        assert_eq!(result.get_failed_msg(), "boo");
+        assert_eq!(json!(result)["failed"], json!(true));
+
+        let result = ModuleResult::builder()
+            .failed(true)
+            .stderr("")
+            .output("foo")
+            .build();
+        assert_eq!(result.get_failed_msg(), "foo");
+        assert!(json!(ModuleResult::builder().build())
+            .get("failed")
+            .is_none());
+    }
+
+    #[test]
+    fn test_module_result_builder_extra() {
+        let result = ModuleResult::builder()
+            .changed(true)
+            .rc(Some(0))
+            .stdout("foo")
+            .stderr("")
+            .extra("boo", json!([1, 2]))
+            .build();
+        assert_eq!(
+            json!(result),
+            json!({
+                "changed": true,
+                "output": null,
+                "extra": {
+                    "rc": 0,
+                    "stdout": "foo",
+                    "stderr": "",
+                    "boo": [1, 2],
+                },
+            })
+        );
+    }
+}
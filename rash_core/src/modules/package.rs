@@ -0,0 +1,241 @@
+/// ANCHOR: module
+/// # package
+///
+/// Manage packages with the package manager of the system, using its module as backend.
+/// Supported backends are `apk` and `dnf`, which is also used in systems with `yum`.
+///
+/// ## Parameters
+///
+/// ```yaml
+/// name:
+///   type: list
+///   required: true
+///   description: |
+///     Packages to manage. It can be a list or a string with comma separated names.
+/// state:
+///   type: string
+///   choices:
+///     - present
+///     - absent
+///     - latest
+///   description: |
+///     Whether packages must be installed, removed or installed and upgraded to their
+///     latest version. Defaults to present.
+/// use:
+///   type: string
+///   choices:
+///     - auto
+///     - apk
+///     - dnf
+///   description: |
+///     Backend module to use. Defaults to auto, which detects the package manager
+///     available in the system.
+/// ```
+///
+/// ## Example
+///
+/// ```yaml
+/// - package:
+///     name:
+///       - curl
+///       - git
+///
+/// - package:
+///     name: nginx
+///     state: latest
+///     use: dnf
+/// ```
+/// ANCHOR_END: module
+use crate::error::{Error, ErrorKind, Result};
+use crate::modules::get_optional_param;
+use crate::modules::package_manager::{is_in_path, parse_list, parse_state, State};
+use crate::modules::{validate_params, ModuleResult, MODULES};
+use crate::vars::Vars;
+
+use yaml_rust::yaml::Hash;
+use yaml_rust::Yaml;
+
+#[derive(Debug, PartialEq)]
+struct Params {
+    name: Vec<String>,
+    state: State,
+    backend: Option<String>,
+}
+
+const REQUIRED_PARAMS: &[&str] = &["name"];
+const OPTIONAL_PARAMS: &[&str] = &["state", "use"];
+
+/// Package manager programs and the module used for each one, in detection order.
+const BACKENDS: &[(&str, &str)] = &[("apk", "apk"), ("dnf", "dnf"), ("yum", "dnf")];
+
+/// Package managers detected but without backend module yet.
+const UNSUPPORTED: &[&str] = &["apt-get", "pacman"];
+
+fn parse_params(yaml: Yaml) -> Result<Params> {
+    trace!("parse params: {:?}", yaml);
+    validate_params("package", &yaml, REQUIRED_PARAMS, OPTIONAL_PARAMS)?;
+    let backend = match get_optional_param(&yaml, "use")?.as_deref() {
+        None | Some("auto") => None,
+        Some(backend) if BACKENDS.iter().any(|(_, module)| *module == backend) => {
+            Some(backend.to_string())
+        }
+        Some(backend) => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "param 'use' must be one of auto, apk or dnf, found: {}",
+                    backend
+                ),
+            ))
+        }
+    };
+    Ok(Params {
+        name: parse_list(&yaml, "name")?,
+        state: parse_state(&yaml)?,
+        backend,
+    })
+}
+
+/// Return the backend module of the first package manager found by `is_available`.
+fn detect_backend<F>(is_available: F) -> Result<String>
+where
+    F: Fn(&str) -> bool,
+{
+    if let Some((_, module)) = BACKENDS.iter().find(|(program, _)| is_available(program)) {
+        return Ok(module.to_string());
+    };
+    match UNSUPPORTED.iter().find(|program| is_available(program)) {
+        Some(program) => Err(Error::new(
+            ErrorKind::NotFound,
+            format!("package manager {} is not supported", program),
+        )),
+        None => Err(Error::new(
+            ErrorKind::NotFound,
+            "no supported package manager found",
+        )),
+    }
+}
+
+/// Params of backend modules, which share `name` and `state` interface.
+fn get_backend_params(params: &Params) -> Yaml {
+    let state = match params.state {
+        State::Present => "present",
+        State::Absent => "absent",
+        State::Latest => "latest",
+    };
+    let mut hash = Hash::new();
+    hash.insert(
+        Yaml::String("name".to_string()),
+        Yaml::Array(params.name.iter().cloned().map(Yaml::String).collect()),
+    );
+    hash.insert(
+        Yaml::String("state".to_string()),
+        Yaml::String(state.to_string()),
+    );
+    Yaml::Hash(hash)
+}
+
+/// Execute `backend` module through `exec_module`, so it can be replaced in tests.
+fn delegate<F>(
+    params: &Params,
+    backend: &str,
+    vars: Vars,
+    exec_module: F,
+) -> Result<(ModuleResult, Vars)>
+where
+    F: FnOnce(&str, Yaml, Vars) -> Result<(ModuleResult, Vars)>,
+{
+    trace!("package backend: {}", backend);
+    exec_module(backend, get_backend_params(params), vars)
+}
+
+fn exec_backend(backend: &str, params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
+    // safe unwrap: backends are always modules
+    MODULES.get(backend).unwrap().exec(params, vars)
+}
+
+pub fn exec(optional_params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
+    let params = parse_params(optional_params)?;
+    let backend = match &params.backend {
+        Some(backend) => backend.clone(),
+        None => detect_backend(is_in_path)?,
+    };
+    delegate(&params, &backend, vars, exec_backend)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use yaml_rust::YamlLoader;
+
+    fn get_params(yaml: &str) -> Params {
+        parse_params(YamlLoader::load_from_str(yaml).unwrap()[0].clone()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_params() {
+        assert_eq!(
+            get_params("name: curl, git\nstate: latest\nuse: dnf"),
+            Params {
+                name: vec!["curl".to_string(), "git".to_string()],
+                state: State::Latest,
+                backend: Some("dnf".to_string()),
+            }
+        );
+        assert_eq!(get_params("name: curl\nuse: auto").backend, None);
+        let error =
+            parse_params(YamlLoader::load_from_str("name: curl\nuse: apt").unwrap()[0].clone())
+                .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_detect_backend() {
+        assert_eq!(detect_backend(|program| program == "apk").unwrap(), "apk");
+        assert_eq!(detect_backend(|program| program == "yum").unwrap(), "dnf");
+        assert_eq!(
+            detect_backend(|program| program == "dnf" || program == "yum").unwrap(),
+            "dnf"
+        );
+        let error = detect_backend(|program| program == "pacman").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        assert_eq!(
+            format!("{}", error),
+            "package manager pacman is not supported"
+        );
+        let error = detect_backend(|_| false).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_delegate() {
+        let params = get_params("name: curl, git\nstate: absent\nuse: apk");
+        let mut called = None;
+        let (result, _) = delegate(&params, "apk", Vars::new(), |backend, yaml, vars| {
+            called = Some((backend.to_string(), yaml));
+            Ok((ModuleResult::builder().changed(true).build(), vars))
+        })
+        .unwrap();
+        assert!(result.get_changed());
+        assert_eq!(
+            called,
+            Some((
+                "apk".to_string(),
+                YamlLoader::load_from_str("name:\n  - curl\n  - git\nstate: absent").unwrap()[0]
+                    .clone()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_get_backend_params_parsed_by_backends() {
+        let params = get_params("name: curl\nstate: latest");
+        for (_, backend) in BACKENDS {
+            assert!(MODULES.contains_key(backend));
+        }
+        let yaml = get_backend_params(&params);
+        assert_eq!(parse_list(&yaml, "name").unwrap(), vec!["curl"]);
+        assert_eq!(parse_state(&yaml).unwrap(), State::Latest);
+    }
+}
@@ -0,0 +1,138 @@
+//! Params parsing and command execution shared by package manager modules.
+use crate::error::{Error, ErrorKind, Result};
+use crate::modules::get_optional_param;
+
+use std::env;
+use std::os::unix::fs::PermissionsExt;
+use std::process::{Command, Output};
+
+use yaml_rust::Yaml;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum State {
+    Present,
+    Absent,
+    Latest,
+}
+
+/// Parse `key` param as a list, accepting strings with comma separated values too.
+/// It is empty if the param is missing.
+pub fn parse_list(yaml: &Yaml, key: &str) -> Result<Vec<String>> {
+    match &yaml[key] {
+        Yaml::BadValue => Ok(Vec::new()),
+        Yaml::String(s) => Ok(s
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect()),
+        Yaml::Array(values) => values
+            .iter()
+            .map(|value| match value.as_str() {
+                Some(s) => Ok(s.to_string()),
+                None => Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{:?} is not a valid string in param '{}'", value, key),
+                )),
+            })
+            .collect(),
+        value => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("param '{}' {:?} must be a string or a list", key, value),
+        )),
+    }
+}
+
+pub fn parse_state(yaml: &Yaml) -> Result<State> {
+    match get_optional_param(yaml, "state")?.as_deref() {
+        None | Some("present") => Ok(State::Present),
+        Some("absent") => Ok(State::Absent),
+        Some("latest") => Ok(State::Latest),
+        Some(state) => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "param 'state' must be one of present, absent or latest, found: {}",
+                state
+            ),
+        )),
+    }
+}
+
+/// Return true if `program` is an executable file in some `PATH` directory.
+pub fn is_in_path(program: &str) -> bool {
+    env::var_os("PATH")
+        .map(|paths| {
+            env::split_paths(&paths).any(|dir| {
+                dir.join(program).metadata().is_ok_and(|metadata| {
+                    metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+                })
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Execute `program` with `args`. Modules receive it as runner, so tests can replace it.
+pub fn run(program: &str, args: &[&str]) -> Result<Output> {
+    trace!("running: {} {}", program, args.join(" "));
+    Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| Error::new(ErrorKind::SubprocessFail, e))
+}
+
+/// Run `program` with `runner`, returning stdout or an error with stderr if it fails.
+pub fn run_checked<F>(runner: &mut F, program: &str, args: &[&str]) -> Result<String>
+where
+    F: FnMut(&str, &[&str]) -> Result<Output>,
+{
+    let output = runner(program, args)?;
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::SubprocessFail,
+            format!(
+                "{} {} failed: {}",
+                program,
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim_end()
+            ),
+        ));
+    };
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn test_parse_list() {
+        let yaml = YamlLoader::load_from_str("name: curl, git\nrepos:\n  - epel\nboo: 1").unwrap()
+            [0]
+        .clone();
+        assert_eq!(parse_list(&yaml, "name").unwrap(), vec!["curl", "git"]);
+        assert_eq!(parse_list(&yaml, "repos").unwrap(), vec!["epel"]);
+        assert_eq!(parse_list(&yaml, "foo").unwrap(), Vec::<String>::new());
+        let error = parse_list(&yaml, "boo").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_is_in_path() {
+        assert!(is_in_path("sh"));
+        assert!(!is_in_path("this-program-does-not-exist"));
+    }
+
+    #[test]
+    fn test_run_checked() {
+        let stdout = run_checked(&mut run, "echo", &["foo"]).unwrap();
+        assert_eq!(stdout, "foo\n");
+        let error = run_checked(&mut run, "sh", &["-c", "echo boo >&2; exit 1"]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::SubprocessFail);
+        assert_eq!(
+            format!("{}", error),
+            "sh -c echo boo >&2; exit 1 failed: boo"
+        );
+    }
+}
@@ -26,13 +26,13 @@
 /// ANCHOR_END: module
 use crate::error::{Error, ErrorKind, Result};
 use crate::modules::ModuleResult;
-use crate::vars::Vars;
+use crate::vars::{merge_layer, Precedence, Vars};
 
 use serde_yaml::Value;
 use yaml_rust::{Yaml, YamlEmitter};
 
 pub fn exec(params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
-    let mut new_vars = vars;
+    let mut new_vars = Vars::new();
 
     params
         .as_hash()
@@ -67,11 +67,7 @@ pub fn exec(params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
         .collect::<Result<Vec<_>>>()?;
 
     Ok((
-        ModuleResult {
-            changed: false,
-            output: None,
-            extra: None,
-        },
-        new_vars,
+        ModuleResult::builder().build(),
+        merge_layer(&vars, Precedence::SetVars, new_vars),
     ))
 }
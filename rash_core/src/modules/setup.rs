@@ -0,0 +1,112 @@
+/// ANCHOR: module
+/// # setup
+///
+/// Gather facts about the system and store them in `rash_facts` var.
+///
+/// Facts gathered:
+///
+/// - `date_time`: current time when facts are gathered, with `epoch` seconds, `iso8601` UTC
+///   timestamp, local `date` and `time`, and `tz` local timezone abbreviation.
+///
+/// ## Example
+///
+/// ```yaml
+/// - setup:
+///
+/// - assert:
+///     that:
+///       - "rash_facts.date_time.epoch is number"
+///       - "rash_facts.date_time.iso8601 is ending_with('Z')"
+/// ```
+/// ANCHOR_END: module
+use crate::error::Result;
+use crate::modules::{validate_params, ModuleResult};
+use crate::utils::time::{format_local_time, format_utc_time, now};
+use crate::vars::Vars;
+
+use serde::Serialize;
+use yaml_rust::Yaml;
+
+#[derive(Debug, PartialEq, Serialize)]
+struct DateTime {
+    epoch: i64,
+    iso8601: String,
+    date: String,
+    time: String,
+    tz: String,
+}
+
+impl DateTime {
+    fn new(timestamp: i64) -> Result<Self> {
+        Ok(DateTime {
+            epoch: timestamp,
+            iso8601: format_utc_time("%Y-%m-%dT%H:%M:%SZ", timestamp)?,
+            date: format_local_time("%Y-%m-%d", timestamp)?,
+            time: format_local_time("%H:%M:%S", timestamp)?,
+            tz: format_local_time("%Z", timestamp)?,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct Facts {
+    date_time: DateTime,
+}
+
+/// Gather facts as if current time was `timestamp`.
+fn gather_facts(timestamp: i64) -> Result<Facts> {
+    Ok(Facts {
+        date_time: DateTime::new(timestamp)?,
+    })
+}
+
+pub fn exec(params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
+    validate_params("setup", &params, &[], &[])?;
+    let facts = json!(gather_facts(now()?)?);
+    let mut new_vars = vars;
+    new_vars.insert("rash_facts", &facts);
+    Ok((ModuleResult::new(false, Some(facts), None), new_vars))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use regex::Regex;
+    use yaml_rust::YamlLoader;
+
+    // 2020-07-01T12:00:00Z
+    const TIMESTAMP: i64 = 1_593_604_800;
+
+    #[test]
+    fn test_gather_facts() {
+        let facts = gather_facts(TIMESTAMP).unwrap();
+        assert_eq!(facts.date_time.epoch, TIMESTAMP);
+        assert_eq!(facts.date_time.iso8601, "2020-07-01T12:00:00Z");
+        assert_eq!(
+            facts.date_time.date,
+            format_local_time("%Y-%m-%d", TIMESTAMP).unwrap()
+        );
+        assert!(Regex::new(r"^\d{2}:\d{2}:\d{2}$")
+            .unwrap()
+            .is_match(&facts.date_time.time));
+    }
+
+    #[test]
+    fn test_exec() {
+        let (result, vars) = exec(Yaml::Null, Vars::new()).unwrap();
+        assert!(!result.get_changed());
+        let facts = vars.into_json()["rash_facts"].clone();
+        assert_eq!(result.get_extra(), Some(facts.clone()));
+        assert!(facts["date_time"]["epoch"].is_i64());
+        assert!(Regex::new(r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z$")
+            .unwrap()
+            .is_match(facts["date_time"]["iso8601"].as_str().unwrap()));
+    }
+
+    #[test]
+    fn test_exec_invalid_params() {
+        let yaml = YamlLoader::load_from_str("foo: boo").unwrap()[0].clone();
+        let _ = exec(yaml, Vars::new()).unwrap_err();
+    }
+}
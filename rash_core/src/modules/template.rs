@@ -3,6 +3,16 @@
 ///
 /// Render [Tera template](https://tera.netlify.app/docs/#templates).
 ///
+/// Templates can use `rash_managed` var to add a banner to generated files. By default,
+/// it is `Managed by rash - do not edit` and it can be changed with `RASH_MANAGED` environment
+/// variable, which admits strftime format codes like `%Y-%m-%d` to include current time.
+///
+/// Destination is only written when the rendered content differs from the existing file, so
+/// changes in vars not used by the template report `changed: false`. When it is written with
+/// `--diff`, the result `diff` holds previous and rendered contents, unless any of them is
+/// binary or bigger than 104448 bytes. The result extra holds the dest content sha256 as
+/// `checksum`. When `src` is a directory, `checksum` maps each rendered file path to its sha256.
+///
 /// ## Parameters
 ///
 /// ```yaml
@@ -12,13 +22,60 @@
 ///   description: |
 ///     Path of Tera formatted template.
 ///     This can be a relative or an absolute path.
+///     Relative paths are looked up in `search_path` directories when it is set.
+///     If it is a directory, every file in its tree is rendered into `dest` directory,
+///     mirroring the tree and removing `.j2` extension from file names. Symlinks in the
+///     tree are copied as symlinks, without following them. Result extra lists the
+///     changed paths as `files`.
 /// dest:
 ///   type: string
 ///   required: true
-///   description: Absolute path where the file should be rendered to.
+///   description: |
+///     Absolute path where the file should be rendered to.
+///     If it ends in `/` or it is an existing directory, file is rendered inside it
+///     using `src` basename.
+///     Missing parent directories are created just before writing it.
 /// mode:
 ///   type: string
-///   description: Permissions of the destination file or directory.
+///   description: |
+///     Permissions of the destination file or directory.
+///     Octal like `"0644"` or symbolic like `u+rwx,g=rx,o-rwx`.
+///     `preserve` sets the same permissions as `src` templates.
+/// owner:
+///   type: string
+///   description: Name or uid of the user that should own the destination file.
+/// group:
+///   type: string
+///   description: Name or gid of the group that should own the destination file.
+/// force:
+///   type: bool
+///   description: |
+///     If false, file is only rendered if destination does not exist. Defaults to true.
+/// backup:
+///   type: bool
+///   description: |
+///     Create a timestamped copy of dest before changing its content, returned as
+///     `backup_file` in the result. Defaults to false.
+/// keep_backups:
+///   type: integer
+///   description: |
+///     Number of backups of dest to keep when `backup` is true. Older ones are removed
+///     after creating a new backup. By default, all backups are kept.
+/// validate:
+///   type: string
+///   description: |
+///     Command to validate the rendered file before writing dest, with `%s` replaced by
+///     its temporary path. If it fails, dest is not modified.
+/// unsafe_writes:
+///   type: bool
+///   description: |
+///     If dest cannot be atomically replaced, like bind mounted files, truncate and
+///     write it in place. Defaults to false.
+/// search_path:
+///   type: list
+///   description: |
+///     Directories where relative `src` is searched, in order, like Ansible role
+///     `templates` directories. The first one containing `src` is used.
 /// ```
 /// ## Examples
 ///
@@ -27,74 +84,428 @@
 ///     src: "template.j2"
 ///     dest: /tmp/MY_PASSWORD_FILE.txt
 ///     mode: "0400"
+///
+/// - template:
+///     src: templates/nginx.conf.j2
+///     dest: /etc/nginx/nginx.conf
+///     validate: nginx -t -c %s
+///
+/// - template:
+///     src: templates/nginx
+///     dest: /etc/nginx
+///
+/// - template:
+///     src: app.conf.j2
+///     dest: /etc/app.conf
+///     search_path:
+///       - roles/app/templates
+///       - templates
 /// ```
 /// ANCHOR_END: module
 use crate::error::{Error, ErrorKind, Result};
-use crate::modules::copy::verify_file;
-use crate::modules::copy::Params as CopyParams;
-use crate::modules::{get_param, ModuleResult};
-use crate::utils::parse_octal;
+use crate::modules::copy::{
+    backup_dest, copy_symlink, create_parent_dir, parse_keep_backups_param, parse_validate_param,
+    resolve_dest, run_validate, verify_permissions,
+};
+use crate::modules::{
+    get_param, get_param_bool_or, get_param_list, get_param_mode, resolve_preserve_mode,
+    validate_params, ModuleResult,
+};
+use crate::utils::file::{get_checksum, get_tmp_path, replace_dest};
+use crate::utils::tera::render_to_writer;
+use crate::utils::time::{format_local_time, now};
+use crate::utils::user::{get_gid, get_uid};
+use crate::vars::builtin::is_diff;
 use crate::vars::Vars;
 
-use std::path::Path;
+use std::env;
+use std::fs::{create_dir_all, read, read_dir, remove_file, rename, set_permissions, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::os::unix::fs::{chown, MetadataExt};
+use std::path::{Path, PathBuf};
 
-use tera::Tera;
+use serde_json::{Map, Value};
 use yaml_rust::Yaml;
 
 #[derive(Debug, PartialEq)]
 struct Params {
     src: String,
     dest: String,
-    mode: u32,
+    mode: String,
+    owner: Option<String>,
+    group: Option<String>,
+    force: bool,
+    backup: bool,
+    keep_backups: Option<usize>,
+    validate: Option<String>,
+    search_path: Vec<String>,
+    unsafe_writes: bool,
 }
 
+const DEFAULT_RASH_MANAGED: &str = "Managed by rash - do not edit";
+
+fn get_rash_managed(format: Option<String>, timestamp: i64) -> Result<String> {
+    format_local_time(
+        &format.unwrap_or_else(|| DEFAULT_RASH_MANAGED.to_string()),
+        timestamp,
+    )
+}
+
+const REQUIRED_PARAMS: &[&str] = &["src", "dest"];
+const OPTIONAL_PARAMS: &[&str] = &[
+    "mode",
+    "owner",
+    "group",
+    "force",
+    "backup",
+    "keep_backups",
+    "validate",
+    "search_path",
+    "unsafe_writes",
+];
+
 fn parse_params(yaml: Yaml) -> Result<Params> {
     trace!("parse params: {:?}", yaml);
-    let mode_string = get_param(&yaml, "mode").or_else(|e| match e.kind() {
-        ErrorKind::NotFound => Ok("0644".to_string()),
-        _ => Err(e),
-    })?;
+    validate_params("template", &yaml, REQUIRED_PARAMS, OPTIONAL_PARAMS)?;
     Ok(Params {
         src: get_param(&yaml, "src")?,
         dest: get_param(&yaml, "dest")?,
-        mode: parse_octal(&mode_string)?,
+        mode: get_param_mode(&yaml, "0644")?,
+        owner: get_param(&yaml, "owner")
+            .map(Some)
+            .or_else(|e| match e.kind() {
+                ErrorKind::NotFound => Ok(None),
+                _ => Err(e),
+            })?,
+        group: get_param(&yaml, "group")
+            .map(Some)
+            .or_else(|e| match e.kind() {
+                ErrorKind::NotFound => Ok(None),
+                _ => Err(e),
+            })?,
+        force: get_param_bool_or(&yaml, "force", true)?,
+        backup: get_param_bool_or(&yaml, "backup", false)?,
+        keep_backups: parse_keep_backups_param(&yaml)?,
+        validate: parse_validate_param(&yaml)?,
+        search_path: get_param_list(&yaml, "search_path").or_else(|e| match e.kind() {
+            ErrorKind::NotFound => Ok(Vec::new()),
+            _ => Err(e),
+        })?,
+        unsafe_writes: get_param_bool_or(&yaml, "unsafe_writes", false)?,
     })
 }
 
-fn render_content(params: Params, vars: Vars) -> Result<CopyParams> {
-    let mut tera = Tera::default();
-    tera.add_template_file(Path::new(&params.src), None)
-        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
-    Ok(CopyParams::new(
-        tera.render(&params.src, &vars)
-            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?,
-        params.dest.clone(),
-        params.mode,
-    ))
+/// Return the first path of relative `src` found in `search_path` directories, or `src`
+/// unchanged if it is absolute or there is no search path.
+fn resolve_src(src: &str, search_path: &[String]) -> Result<String> {
+    if search_path.is_empty() || Path::new(src).is_absolute() {
+        return Ok(src.to_string());
+    };
+    let candidates = search_path
+        .iter()
+        .map(|dir| Path::new(dir).join(src))
+        .collect::<Vec<PathBuf>>();
+    match candidates.iter().find(|path| path.exists()) {
+        Some(path) => Ok(path.to_string_lossy().to_string()),
+        None => Err(Error::new(
+            ErrorKind::NotFound,
+            format!(
+                "template {} not found in search path: {}",
+                src,
+                candidates
+                    .iter()
+                    .map(|path| path.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        )),
+    }
+}
+
+/// Compare files content without loading them in memory.
+fn is_same_content(path: &Path, other: &Path) -> Result<bool> {
+    if !other.exists() || path.metadata()?.len() != other.metadata()?.len() {
+        return Ok(false);
+    };
+    let mut bytes = BufReader::new(File::open(path)?).bytes();
+    let mut other_bytes = BufReader::new(File::open(other)?).bytes();
+    loop {
+        match (bytes.next().transpose()?, other_bytes.next().transpose()?) {
+            (None, None) => return Ok(true),
+            (a, b) if a != b => return Ok(false),
+            _ => (),
+        }
+    }
+}
+
+/// Files bigger than this are not diffed, the same limit as Ansible.
+const DIFF_MAX_SIZE: u64 = 104_448;
+
+/// Return `path` content to be diffed, empty if it does not exist. Files bigger than
+/// [`DIFF_MAX_SIZE`] or with null bytes, like binaries, return `None`.
+///
+/// [`DIFF_MAX_SIZE`]: constant.DIFF_MAX_SIZE.html
+fn read_diff_content(path: &Path) -> Result<Option<String>> {
+    if !path.exists() {
+        return Ok(Some(String::new()));
+    };
+    if path.metadata()?.len() > DIFF_MAX_SIZE {
+        debug!(
+            "diff skipped: {} is bigger than {} bytes",
+            path.display(),
+            DIFF_MAX_SIZE
+        );
+        return Ok(None);
+    };
+    let content = read(path)?;
+    if content.contains(&0) {
+        debug!("diff skipped: {} is binary", path.display());
+        return Ok(None);
+    };
+    Ok(Some(String::from_utf8_lossy(&content).to_string()))
+}
+
+/// Return `dest` current content, empty if it does not exist, and `rendered` content, if both
+/// of them can be diffed.
+fn get_diff(dest: &Path, rendered: &Path) -> Result<Option<(String, String)>> {
+    match read_diff_content(dest)? {
+        Some(before) => Ok(read_diff_content(rendered)?.map(|after| (before, after))),
+        None => Ok(None),
+    }
+}
+
+fn render_file(params: Params, vars: Vars) -> Result<ModuleResult> {
+    trace!("params: {:?}", params);
+    let mode = resolve_preserve_mode(&params.mode, Some(Path::new(&params.src)))?;
+    let dest = resolve_dest(&params.dest, Some(&params.src))?;
+    let dest_path = Path::new(&dest);
+    if dest_path.is_dir() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("dest {} is a directory", dest),
+        ));
+    }
+    if !params.force && dest_path.exists() {
+        trace!("dest already exists and force is false: {:?}", &dest);
+        return Ok(ModuleResult::builder()
+            .changed(false)
+            .extra("checksum", json!(get_checksum(dest_path)?))
+            .output(dest)
+            .build());
+    }
+    let uid = params.owner.as_deref().map(get_uid).transpose()?;
+    let gid = params.group.as_deref().map(get_gid).transpose()?;
+
+    create_parent_dir(dest_path)?;
+    let tmp_path = get_tmp_path(dest_path)?;
+    let render_result = File::create(&tmp_path)
+        .map_err(Error::from)
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            render_to_writer(Path::new(&params.src), &vars, &mut writer)?;
+            Ok(writer.flush()?)
+        })
+        .and_then(|_| is_same_content(&tmp_path, dest_path));
+
+    let mut backup_file = None;
+    let mut diff = None;
+    let changed = match render_result {
+        Ok(true) => {
+            remove_file(&tmp_path)?;
+            false
+        }
+        Ok(false) => {
+            trace!("changing content: {:?}", &dest);
+            let validated = match &params.validate {
+                Some(validate) => run_validate(&tmp_path, validate),
+                None => Ok(()),
+            };
+            let diffed = validated.and_then(|_| {
+                if is_diff(&vars) {
+                    get_diff(dest_path, &tmp_path)
+                } else {
+                    Ok(None)
+                }
+            });
+            diff = match diffed {
+                Ok(diff) => diff,
+                Err(e) => {
+                    let _ = remove_file(&tmp_path);
+                    return Err(e);
+                }
+            };
+            if params.backup && dest_path.exists() {
+                backup_file = match backup_dest(dest_path, params.keep_backups) {
+                    Ok(backup_path) => Some(backup_path),
+                    Err(e) => {
+                        let _ = remove_file(&tmp_path);
+                        return Err(e);
+                    }
+                };
+            };
+            if let Ok(metadata) = dest_path.metadata() {
+                set_permissions(&tmp_path, metadata.permissions())?;
+                if let Err(e) = chown(&tmp_path, Some(metadata.uid()), Some(metadata.gid())) {
+                    trace!("cannot preserve ownership of {}: {}", dest, e);
+                };
+            };
+            replace_dest(&tmp_path, dest_path, params.unsafe_writes, |from, to| {
+                rename(from, to)
+            })?;
+            true
+        }
+        Err(e) => {
+            let _ = remove_file(&tmp_path);
+            return Err(e);
+        }
+    };
+
+    let permissions_changed = verify_permissions(&dest, &mode, uid, gid)?;
+    let mut builder = ModuleResult::builder()
+        .changed(changed || permissions_changed)
+        .extra("checksum", json!(get_checksum(dest_path)?))
+        .output(dest);
+    if let Some(backup_file) = backup_file {
+        builder = builder.extra("backup_file", json!(backup_file));
+    };
+    if let Some((before, after)) = diff {
+        builder = builder.diff(before, after);
+    };
+    Ok(builder.build())
+}
+
+/// Render every file in `src` directory tree into `dest`, without `.j2` extension.
+fn render_dir(params: Params, vars: Vars) -> Result<ModuleResult> {
+    trace!("params: {:?}", params);
+    let mut rendered = Vec::new();
+    let mut checksums = Map::new();
+    let changed = render_dir_recursive(
+        Path::new(&params.src),
+        Path::new(&params.dest),
+        &params,
+        &vars,
+        &mut rendered,
+        &mut checksums,
+    )?;
+    Ok(ModuleResult::builder()
+        .changed(changed)
+        .extra("files", json!(rendered))
+        .extra("checksum", Value::Object(checksums))
+        .output(params.dest)
+        .build())
+}
+
+fn render_dir_recursive(
+    src: &Path,
+    dest: &Path,
+    params: &Params,
+    vars: &Vars,
+    rendered: &mut Vec<String>,
+    checksums: &mut Map<String, Value>,
+) -> Result<bool> {
+    let mut changed = false;
+    if !dest.exists() {
+        trace!("creating directory: {:?}", dest);
+        create_dir_all(dest)?;
+        changed = true;
+    };
+
+    let mut entries = read_dir(src)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<PathBuf>>>()?;
+    entries.sort();
+
+    for entry in entries {
+        // read_dir entries always have a file name
+        let file_name = entry.file_name().unwrap().to_string_lossy().to_string();
+        let file_type = entry.symlink_metadata()?.file_type();
+        if file_type.is_symlink() {
+            let entry_dest = dest.join(&file_name);
+            if copy_symlink(&entry, &entry_dest, params.force)? {
+                rendered.push(entry_dest.to_string_lossy().to_string());
+                changed = true;
+            };
+        } else if file_type.is_dir() {
+            changed |= render_dir_recursive(
+                &entry,
+                &dest.join(file_name),
+                params,
+                vars,
+                rendered,
+                checksums,
+            )?;
+        } else {
+            let entry_dest = dest
+                .join(file_name.strip_suffix(".j2").unwrap_or(&file_name))
+                .to_string_lossy()
+                .to_string();
+            let result = render_file(
+                Params {
+                    src: entry.to_string_lossy().to_string(),
+                    dest: entry_dest.clone(),
+                    mode: params.mode.clone(),
+                    owner: params.owner.clone(),
+                    group: params.group.clone(),
+                    force: params.force,
+                    backup: params.backup,
+                    keep_backups: params.keep_backups,
+                    validate: params.validate.clone(),
+                    search_path: Vec::new(),
+                    unsafe_writes: params.unsafe_writes,
+                },
+                vars.clone(),
+            )?;
+            if let Some(checksum) = result
+                .get_extra()
+                .and_then(|extra| extra.get("checksum").cloned())
+            {
+                checksums.insert(entry_dest.clone(), checksum);
+            };
+            if result.get_changed() {
+                rendered.push(entry_dest);
+                changed = true;
+            };
+        }
+    }
+    Ok(changed)
 }
 
 pub fn exec(optional_params: Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
-    Ok((
-        verify_file(render_content(
-            parse_params(optional_params)?,
-            vars.clone(),
-        )?)?,
-        vars,
-    ))
+    let mut render_vars = vars.clone();
+    if !render_vars.contains_key("rash_managed") {
+        render_vars.insert(
+            "rash_managed",
+            &get_rash_managed(env::var("RASH_MANAGED").ok(), now()?)?,
+        );
+    };
+    let mut params = parse_params(optional_params)?;
+    params.src = resolve_src(&params.src, &params.search_path)?;
+    let result = if Path::new(&params.src).is_dir() {
+        render_dir(params, render_vars)?
+    } else {
+        render_file(params, render_vars)?
+    };
+    Ok((result, vars))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::modules::Diff;
     use crate::vars;
 
-    use std::fs::File;
-    use std::io::Write;
+    use std::fs::{create_dir, read_link, read_to_string, write};
+    use std::os::unix::fs::{symlink, PermissionsExt};
 
+    use sha2::{Digest, Sha256};
     use tempfile::tempdir;
     use yaml_rust::YamlLoader;
 
+    fn get_sha256(path: &Path) -> String {
+        format!("{:x}", Sha256::digest(read(path).unwrap()))
+    }
+
     #[test]
     fn test_parse_params() {
         let yaml = YamlLoader::load_from_str(
@@ -114,7 +525,15 @@ mod tests {
             Params {
                 src: "/tmp/foo.j2".to_string(),
                 dest: "/tmp/buu.txt".to_string(),
-                mode: 0o600,
+                mode: "0600".to_string(),
+                owner: None,
+                group: None,
+                force: true,
+                backup: false,
+                keep_backups: None,
+                validate: None,
+                search_path: Vec::new(),
+                unsafe_writes: false,
             }
         );
     }
@@ -136,6 +555,40 @@ mod tests {
         assert_eq!(error.kind(), ErrorKind::InvalidData);
     }
 
+    #[test]
+    fn test_parse_params_missing_dest() {
+        let yaml = YamlLoader::load_from_str(
+            r#"
+        src: "/tmp/boo.j2"
+        "#,
+        )
+        .unwrap()
+        .first()
+        .unwrap()
+        .clone();
+        let error = parse_params(yaml).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(format!("{}", error).contains("missing required params: dest"));
+    }
+
+    #[test]
+    fn test_parse_params_unknown_param() {
+        let yaml = YamlLoader::load_from_str(
+            r#"
+        src: "/tmp/boo.j2"
+        dest: "/tmp/buu.txt"
+        foo: boo
+        "#,
+        )
+        .unwrap()
+        .first()
+        .unwrap()
+        .clone();
+        let error = parse_params(yaml).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(format!("{}", error).contains("unsupported params: foo"));
+    }
+
     #[test]
     fn test_parse_params_no_mode() {
         let yaml = YamlLoader::load_from_str(
@@ -154,32 +607,648 @@ mod tests {
             Params {
                 src: "/tmp/boo.j2".to_string(),
                 dest: "/tmp/buu.txt".to_string(),
-                mode: 0o644,
+                mode: "0644".to_string(),
+                owner: None,
+                group: None,
+                force: true,
+                backup: false,
+                keep_backups: None,
+                validate: None,
+                search_path: Vec::new(),
+                unsafe_writes: false,
             }
         );
     }
 
     #[test]
-    fn test_render_content() {
+    fn test_render_file() {
+        let dir = tempdir().unwrap();
+
+        let file_path = dir.path().join("template.j2");
+        let mut file = File::create(file_path.clone()).unwrap();
+        #[allow(clippy::write_literal)]
+        writeln!(file, "{}", "{{ boo }}").unwrap();
+        let dest_path = dir.path().join("buu.txt");
+
+        let vars = vars::from_iter(vec![("boo", "test")].into_iter());
+        let params = || Params {
+            src: file_path.to_str().unwrap().to_owned(),
+            dest: dest_path.to_str().unwrap().to_owned(),
+            mode: "0600".to_string(),
+            owner: None,
+            group: None,
+            force: true,
+            backup: false,
+            keep_backups: None,
+            validate: None,
+            search_path: Vec::new(),
+            unsafe_writes: false,
+        };
+
+        let result = render_file(params(), vars.clone()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(read_to_string(&dest_path).unwrap(), "test\n");
+        let mode = dest_path.metadata().unwrap().permissions().mode();
+        assert_eq!(format!("{:o}", mode & 0o7777), format!("{:o}", 0o600));
+        assert!(!get_tmp_path(&dest_path).unwrap().exists());
+
+        let result = render_file(params(), vars).unwrap();
+        assert!(!result.get_changed());
+    }
+
+    #[test]
+    fn test_render_file_force() {
         let dir = tempdir().unwrap();
 
         let file_path = dir.path().join("template.j2");
         let mut file = File::create(file_path.clone()).unwrap();
         #[allow(clippy::write_literal)]
         writeln!(file, "{}", "{{ boo }}").unwrap();
+        let dest_path = dir.path().join("buu.txt");
+        let mut dest_file = File::create(dest_path.clone()).unwrap();
+        writeln!(dest_file, "old").unwrap();
 
         let vars = vars::from_iter(vec![("boo", "test")].into_iter());
+        let params = |force| Params {
+            src: file_path.to_str().unwrap().to_owned(),
+            dest: dest_path.to_str().unwrap().to_owned(),
+            mode: "0644".to_string(),
+            owner: None,
+            group: None,
+            force,
+            backup: false,
+            keep_backups: None,
+            validate: None,
+            search_path: Vec::new(),
+            unsafe_writes: false,
+        };
+
+        let result = render_file(params(false), vars.clone()).unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(read_to_string(&dest_path).unwrap(), "old\n");
+
+        let result = render_file(params(true), vars).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(read_to_string(&dest_path).unwrap(), "test\n");
+    }
+
+    #[test]
+    fn test_exec_rash_managed() {
+        let dir = tempdir().unwrap();
+
+        let file_path = dir.path().join("template.j2");
+        let mut file = File::create(file_path.clone()).unwrap();
+        #[allow(clippy::write_literal)]
+        writeln!(file, "{}", "# {{ rash_managed }}").unwrap();
+        let dest_path = dir.path().join("buu.txt");
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: {}",
+            file_path.to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
+        let (_, vars) = exec(yaml, Vars::new()).unwrap();
+        assert_eq!(
+            read_to_string(&dest_path).unwrap(),
+            format!("# {}\n", DEFAULT_RASH_MANAGED)
+        );
+        assert!(!vars.contains_key("rash_managed"));
+    }
+
+    #[test]
+    fn test_exec_unchanged_unused_var() {
+        let dir = tempdir().unwrap();
+
+        let file_path = dir.path().join("template.j2");
+        let mut file = File::create(file_path.clone()).unwrap();
+        #[allow(clippy::write_literal)]
+        writeln!(file, "{}", "port={{ port }}").unwrap();
+        let dest_path = dir.path().join("app.conf");
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: {}",
+            file_path.to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
+
+        let vars = vars::from_iter(vec![("port", "80"), ("unused", "foo")].into_iter());
+        let (result, _) = exec(yaml.clone(), vars).unwrap();
+        assert!(result.get_changed());
+        let inode = dest_path.metadata().unwrap().ino();
+
+        let vars = vars::from_iter(vec![("port", "80"), ("unused", "boo")].into_iter());
+        let (result, _) = exec(yaml.clone(), vars).unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(dest_path.metadata().unwrap().ino(), inode);
+
+        let vars = vars::from_iter(vec![("port", "8080"), ("unused", "boo")].into_iter());
+        let (result, _) = exec(yaml, vars).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(read_to_string(&dest_path).unwrap(), "port=8080\n");
+    }
+
+    #[test]
+    fn test_exec_checksum() {
+        let dir = tempdir().unwrap();
+
+        let file_path = dir.path().join("template.j2");
+        let mut file = File::create(file_path.clone()).unwrap();
+        #[allow(clippy::write_literal)]
+        writeln!(file, "{}", "port={{ port }}").unwrap();
+        let dest_path = dir.path().join("app.conf");
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: {}",
+            file_path.to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
+
+        let vars = vars::from_iter(vec![("port", "8080")].into_iter());
+        let (result, _) = exec(yaml.clone(), vars.clone()).unwrap();
+        assert!(result.get_changed());
+        let checksum = format!("{:x}", Sha256::digest(read(&dest_path).unwrap()));
+        assert_eq!(
+            result.get_extra(),
+            Some(json!({ "checksum": checksum.clone() }))
+        );
+
+        let (result, _) = exec(yaml, vars).unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(result.get_extra(), Some(json!({ "checksum": checksum })));
+    }
+
+    #[test]
+    fn test_exec_backup() {
+        let dir = tempdir().unwrap();
+
+        let file_path = dir.path().join("template.j2");
+        let mut file = File::create(file_path.clone()).unwrap();
+        #[allow(clippy::write_literal)]
+        writeln!(file, "{}", "port={{ port }}").unwrap();
+        let dest_path = dir.path().join("app.conf");
+        let mut dest_file = File::create(dest_path.clone()).unwrap();
+        writeln!(dest_file, "port=80").unwrap();
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: {}\nbackup: true",
+            file_path.to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
+
+        let vars = vars::from_iter(vec![("port", "80")].into_iter());
+        let (result, _) = exec(yaml.clone(), vars).unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(result.get_extra().unwrap().get("backup_file"), None);
 
-        let copy_params = render_content(
+        let vars = vars::from_iter(vec![("port", "8080")].into_iter());
+        let (result, _) = exec(yaml, vars).unwrap();
+        assert!(result.get_changed());
+        let backup_file = result.get_extra().unwrap()["backup_file"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(read_to_string(&backup_file).unwrap(), "port=80\n");
+        assert_eq!(read_to_string(&dest_path).unwrap(), "port=8080\n");
+    }
+
+    #[test]
+    fn test_exec_keep_backups() {
+        let dir = tempdir().unwrap();
+
+        let file_path = dir.path().join("template.j2");
+        let mut file = File::create(file_path.clone()).unwrap();
+        #[allow(clippy::write_literal)]
+        writeln!(file, "{}", "port={{ port }}").unwrap();
+        let dest_path = dir.path().join("app.conf");
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: {}\nbackup: true\nkeep_backups: 1",
+            file_path.to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
+
+        let backup_files = ["80", "8080", "9090"]
+            .iter()
+            .filter_map(|port| {
+                let vars = vars::from_iter(IntoIterator::into_iter([("port", *port)]));
+                let (result, _) = exec(yaml.clone(), vars).unwrap();
+                result.get_extra().unwrap()["backup_file"]
+                    .as_str()
+                    .map(String::from)
+            })
+            .collect::<Vec<String>>();
+
+        assert_eq!(backup_files.len(), 2);
+        assert!(!Path::new(&backup_files[0]).exists());
+        assert_eq!(read_to_string(&backup_files[1]).unwrap(), "port=8080\n");
+        assert_eq!(read_to_string(&dest_path).unwrap(), "port=9090\n");
+    }
+
+    #[test]
+    fn test_exec_diff() {
+        let dir = tempdir().unwrap();
+
+        let file_path = dir.path().join("template.j2");
+        let mut file = File::create(file_path.clone()).unwrap();
+        #[allow(clippy::write_literal)]
+        writeln!(file, "{}", "port={{ port }}").unwrap();
+        let dest_path = dir.path().join("app.conf");
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: {}",
+            file_path.to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
+
+        let diff_vars = |port: &str| {
+            let mut vars = vars::from_iter(IntoIterator::into_iter([("port", port)]));
+            vars.insert("rash", &json!({ "diff": true }));
+            vars
+        };
+        let (result, _) = exec(yaml.clone(), diff_vars("80")).unwrap();
+        assert_eq!(
+            result.get_diff(),
+            Some(Diff {
+                before: "".to_string(),
+                after: "port=80\n".to_string(),
+            })
+        );
+
+        let (result, _) = exec(yaml.clone(), diff_vars("80")).unwrap();
+        assert_eq!(result.get_diff(), None);
+
+        let vars = vars::from_iter(IntoIterator::into_iter([("port", "9090")]));
+        let (result, _) = exec(yaml.clone(), vars).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(result.get_diff(), None);
+
+        let (result, _) = exec(yaml, diff_vars("8080")).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(
+            result.get_diff(),
+            Some(Diff {
+                before: "port=9090\n".to_string(),
+                after: "port=8080\n".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_get_diff_skipped() {
+        let dir = tempdir().unwrap();
+        let dest_path = dir.path().join("app.conf");
+        let rendered_path = dir.path().join("rendered.conf");
+        write(&rendered_path, "port=80\n").unwrap();
+
+        assert_eq!(
+            get_diff(&dest_path, &rendered_path).unwrap(),
+            Some(("".to_string(), "port=80\n".to_string()))
+        );
+
+        write(&dest_path, [b'a', 0, b'b']).unwrap();
+        assert_eq!(get_diff(&dest_path, &rendered_path).unwrap(), None);
+
+        write(&dest_path, "a".repeat(DIFF_MAX_SIZE as usize + 1)).unwrap();
+        assert_eq!(get_diff(&dest_path, &rendered_path).unwrap(), None);
+        assert_eq!(get_diff(&rendered_path, &dest_path).unwrap(), None);
+    }
+
+    #[test]
+    fn test_exec_validate() {
+        let dir = tempdir().unwrap();
+
+        let file_path = dir.path().join("template.j2");
+        let mut file = File::create(file_path.clone()).unwrap();
+        #[allow(clippy::write_literal)]
+        writeln!(file, "{}", "port={{ port }}").unwrap();
+        let dest_path = dir.path().join("app.conf");
+        let mut dest_file = File::create(dest_path.clone()).unwrap();
+        writeln!(dest_file, "port=80").unwrap();
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: {}\nvalidate: \"grep -q '^port=[0-9]*$' %s\"",
+            file_path.to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
+
+        let vars = vars::from_iter(vec![("port", "foo")].into_iter());
+        let error = exec(yaml.clone(), vars).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(read_to_string(&dest_path).unwrap(), "port=80\n");
+        assert!(!get_tmp_path(&dest_path).unwrap().exists());
+
+        let vars = vars::from_iter(vec![("port", "8080")].into_iter());
+        let (result, _) = exec(yaml, vars).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(read_to_string(&dest_path).unwrap(), "port=8080\n");
+    }
+
+    #[test]
+    fn test_exec_mode_preserve() {
+        let dir = tempdir().unwrap();
+
+        let file_path = dir.path().join("script.sh.j2");
+        let mut file = File::create(file_path.clone()).unwrap();
+        writeln!(file, "echo rash").unwrap();
+        set_permissions(&file_path, std::fs::Permissions::from_mode(0o750)).unwrap();
+        let dest_path = dir.path().join("script.sh");
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: {}\nmode: preserve",
+            file_path.to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
+
+        let (result, _) = exec(yaml.clone(), Vars::new()).unwrap();
+        assert!(result.get_changed());
+        let mode = dest_path.metadata().unwrap().permissions().mode() & 0o7777;
+        assert_eq!(format!("{:o}", mode), "750");
+
+        let (result, _) = exec(yaml, Vars::new()).unwrap();
+        assert!(!result.get_changed());
+    }
+
+    #[test]
+    fn test_exec_dir() {
+        let src_dir = tempdir().unwrap();
+        let mut file = File::create(src_dir.path().join("foo.conf.j2")).unwrap();
+        #[allow(clippy::write_literal)]
+        writeln!(file, "{}", "foo={{ foo }}").unwrap();
+        create_dir(src_dir.path().join("conf.d")).unwrap();
+        let mut file = File::create(src_dir.path().join("conf.d/boo.conf")).unwrap();
+        #[allow(clippy::write_literal)]
+        writeln!(file, "{}", "boo={{ boo }}").unwrap();
+
+        let dest_dir = tempdir().unwrap();
+        let dest_path = dest_dir.path().join("etc");
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: {}",
+            src_dir.path().to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
+        let vars = vars::from_iter(vec![("foo", "1"), ("boo", "2")].into_iter());
+
+        let (result, _) = exec(yaml.clone(), vars.clone()).unwrap();
+        assert!(result.get_changed());
+        let checksums = json!({
+            dest_path.join("conf.d/boo.conf").to_str().unwrap():
+                get_sha256(&dest_path.join("conf.d/boo.conf")),
+            dest_path.join("foo.conf").to_str().unwrap(): get_sha256(&dest_path.join("foo.conf")),
+        });
+        assert_eq!(
+            result.get_extra(),
+            Some(json!({
+                "files": [
+                    dest_path.join("conf.d/boo.conf").to_str().unwrap(),
+                    dest_path.join("foo.conf").to_str().unwrap(),
+                ],
+                "checksum": checksums,
+            }))
+        );
+        assert_eq!(
+            read_to_string(dest_path.join("foo.conf")).unwrap(),
+            "foo=1\n"
+        );
+        assert_eq!(
+            read_to_string(dest_path.join("conf.d/boo.conf")).unwrap(),
+            "boo=2\n"
+        );
+        assert!(!dest_path.join("foo.conf.j2").exists());
+
+        let (result, _) = exec(yaml.clone(), vars).unwrap();
+        assert!(!result.get_changed());
+        assert_eq!(
+            result.get_extra(),
+            Some(json!({"files": [], "checksum": checksums}))
+        );
+
+        let vars = vars::from_iter(vec![("foo", "1"), ("boo", "3")].into_iter());
+        let (result, _) = exec(yaml, vars).unwrap();
+        assert_eq!(
+            result.get_extra().unwrap()["files"],
+            json!([dest_path.join("conf.d/boo.conf").to_str().unwrap()])
+        );
+        assert_eq!(
+            result.get_extra().unwrap()["checksum"]
+                [dest_path.join("conf.d/boo.conf").to_str().unwrap()],
+            json!(get_sha256(&dest_path.join("conf.d/boo.conf")))
+        );
+    }
+
+    #[test]
+    fn test_exec_dir_symlinks() {
+        let src_dir = tempdir().unwrap();
+        write(src_dir.path().join("foo.conf.j2"), "foo={{ foo }}").unwrap();
+        create_dir(src_dir.path().join("conf.d")).unwrap();
+        symlink("../foo.conf.j2", src_dir.path().join("conf.d/foo.conf")).unwrap();
+        // loop which would be followed forever
+        symlink("..", src_dir.path().join("conf.d/parent")).unwrap();
+
+        let dest_dir = tempdir().unwrap();
+        let dest_path = dest_dir.path().join("etc");
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: {}\ndest: {}",
+            src_dir.path().to_str().unwrap(),
+            dest_path.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
+        let vars = vars::from_iter(vec![("foo", "1")].into_iter());
+
+        let (result, _) = exec(yaml.clone(), vars.clone()).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(read_to_string(dest_path.join("foo.conf")).unwrap(), "foo=1");
+        assert_eq!(
+            read_link(dest_path.join("conf.d/foo.conf")).unwrap(),
+            PathBuf::from("../foo.conf.j2")
+        );
+        assert_eq!(
+            read_link(dest_path.join("conf.d/parent")).unwrap(),
+            PathBuf::from("..")
+        );
+
+        let (result, _) = exec(yaml, vars).unwrap();
+        assert!(!result.get_changed());
+    }
+
+    #[test]
+    fn test_get_rash_managed() {
+        // 2020-07-01T12:00:00Z
+        let timestamp = 1_593_604_800;
+        assert_eq!(
+            get_rash_managed(None, timestamp).unwrap(),
+            DEFAULT_RASH_MANAGED
+        );
+        assert_eq!(
+            get_rash_managed(Some("Managed by rash on %Y-%m".to_string()), timestamp).unwrap(),
+            "Managed by rash on 2020-07"
+        );
+    }
+
+    #[test]
+    fn test_render_file_error() {
+        let dir = tempdir().unwrap();
+
+        let file_path = dir.path().join("template.j2");
+        let mut file = File::create(file_path.clone()).unwrap();
+        #[allow(clippy::write_literal)]
+        writeln!(file, "{}", "{{ boo }}").unwrap();
+        let dest_path = dir.path().join("buu.txt");
+
+        let error = render_file(
             Params {
                 src: file_path.to_str().unwrap().to_owned(),
-                dest: "/tmp/buu.txt".to_string(),
-                mode: 0o644,
+                dest: dest_path.to_str().unwrap().to_owned(),
+                mode: "0644".to_string(),
+                owner: None,
+                group: None,
+                force: true,
+                backup: false,
+                keep_backups: None,
+                validate: None,
+                search_path: Vec::new(),
+                unsafe_writes: false,
             },
-            vars,
+            Vars::new(),
         )
-        .unwrap();
+        .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(!dest_path.exists());
+        assert!(!get_tmp_path(&dest_path).unwrap().exists());
+    }
+
+    #[test]
+    fn test_is_same_content() {
+        let dir = tempdir().unwrap();
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        let c = dir.path().join("c");
+        File::create(&a).unwrap().write_all(b"foo").unwrap();
+        File::create(&b).unwrap().write_all(b"foo").unwrap();
+        File::create(&c).unwrap().write_all(b"boo").unwrap();
+
+        assert!(is_same_content(&a, &b).unwrap());
+        assert!(!is_same_content(&a, &c).unwrap());
+        assert!(!is_same_content(&a, &dir.path().join("d")).unwrap());
+    }
+
+    #[test]
+    fn test_exec_dest_dir() {
+        let dir = tempdir().unwrap();
+
+        let file_path = dir.path().join("template.conf");
+        let mut file = File::create(file_path.clone()).unwrap();
+        writeln!(file, "foo").unwrap();
+
+        let dest_dirs = [
+            dir.path().join("existing_dir"),
+            dir.path().join("trailing_slash_dir"),
+        ];
+        create_dir(&dest_dirs[0]).unwrap();
+        let dests = [
+            dest_dirs[0].to_str().unwrap().to_string(),
+            format!("{}/", dest_dirs[1].to_str().unwrap()),
+        ];
+
+        dests.iter().for_each(|dest| {
+            let yaml = YamlLoader::load_from_str(&format!(
+                "src: {}\ndest: {}",
+                file_path.to_str().unwrap(),
+                dest
+            ))
+            .unwrap()
+            .first()
+            .unwrap()
+            .clone();
+            let _ = exec(yaml, Vars::new()).unwrap();
+        });
+
+        dest_dirs.iter().for_each(|dest_dir| {
+            let contents = read_to_string(dest_dir.join("template.conf")).unwrap();
+            assert_eq!(contents, "foo\n");
+        });
+    }
+
+    #[test]
+    fn test_resolve_src() {
+        let dir = tempdir().unwrap();
+        let roles_dir = dir.path().join("roles");
+        let templates_dir = dir.path().join("templates");
+        create_dir(&roles_dir).unwrap();
+        create_dir(&templates_dir).unwrap();
+        File::create(templates_dir.join("app.conf.j2")).unwrap();
+        let search_path = vec![
+            roles_dir.to_str().unwrap().to_string(),
+            templates_dir.to_str().unwrap().to_string(),
+        ];
+
+        let src = resolve_src("app.conf.j2", &search_path).unwrap();
+        assert_eq!(Path::new(&src), templates_dir.join("app.conf.j2"));
+
+        File::create(roles_dir.join("app.conf.j2")).unwrap();
+        let src = resolve_src("app.conf.j2", &search_path).unwrap();
+        assert_eq!(Path::new(&src), roles_dir.join("app.conf.j2"));
+
+        assert_eq!(
+            resolve_src("/tmp/app.conf.j2", &search_path).unwrap(),
+            "/tmp/app.conf.j2"
+        );
+        assert_eq!(resolve_src("app.conf.j2", &[]).unwrap(), "app.conf.j2");
+    }
+
+    #[test]
+    fn test_resolve_src_not_found() {
+        let search_path = vec!["/tmp/roles".to_string(), "/tmp/templates".to_string()];
+        let error = resolve_src("missing.j2", &search_path).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        assert_eq!(
+            format!("{}", error),
+            "template missing.j2 not found in search path: /tmp/roles/missing.j2, /tmp/templates/missing.j2"
+        );
+    }
+
+    #[test]
+    fn test_exec_search_path() {
+        let dir = tempdir().unwrap();
+        let roles_dir = dir.path().join("roles");
+        let templates_dir = dir.path().join("templates");
+        create_dir(&roles_dir).unwrap();
+        create_dir(&templates_dir).unwrap();
+        let mut file = File::create(templates_dir.join("app.conf.j2")).unwrap();
+        #[allow(clippy::write_literal)]
+        writeln!(file, "{}", "port={{ port }}").unwrap();
+        let dest_path = dir.path().join("app.conf");
+
+        let yaml = YamlLoader::load_from_str(&format!(
+            "src: app.conf.j2\ndest: {}\nsearch_path:\n  - {}\n  - {}",
+            dest_path.to_str().unwrap(),
+            roles_dir.to_str().unwrap(),
+            templates_dir.to_str().unwrap()
+        ))
+        .unwrap()[0]
+            .clone();
 
-        assert_eq!(copy_params.get_content(), "test\n");
+        let vars = vars::from_iter(vec![("port", "80")].into_iter());
+        let (result, _) = exec(yaml, vars).unwrap();
+        assert!(result.get_changed());
+        assert_eq!(read_to_string(&dest_path).unwrap(), "port=80\n");
     }
 }
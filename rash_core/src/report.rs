@@ -0,0 +1,318 @@
+//! Machine readable serialization of module results, e.g.: for CI integrations.
+//!
+//! Every [`ModuleResult`] is reported with a stable schema:
+//!
+//! ```yaml
+//! changed: bool
+//! failed: bool
+//! skipped: bool
+//! rc: int or null
+//! output: string or null
+//! extra: dict or null
+//! diff: dict with before and after strings, or null
+//! ```
+//!
+//! `rc` and `skipped` are taken out of module `extra`.
+//!
+//! Results of tasks executed in a thread are collected between [`start_collecting`] and
+//! [`take_collected`], and `rash --output json|yaml` prints them at the end of the run.
+//!
+//! [`ModuleResult`]: ../modules/struct.ModuleResult.html
+//! [`start_collecting`]: fn.start_collecting.html
+//! [`take_collected`]: fn.take_collected.html
+use crate::error::{Error, ErrorKind, Result};
+use crate::modules::ModuleResult;
+
+use std::cell::RefCell;
+use std::str::FromStr;
+
+use serde_json::{Map, Value};
+
+const EXTRACTED_KEYS: [&str; 2] = ["rc", "skipped"];
+
+thread_local! {
+    static COLLECTED: RefCell<Vec<Vec<ModuleResult>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Start collecting results of tasks executed in current thread. Collections can be nested,
+/// e.g.: by `block` module, and results are recorded in the innermost one.
+pub fn start_collecting() {
+    COLLECTED.with(|collected| collected.borrow_mut().push(Vec::new()));
+}
+
+/// Add task `result` to innermost collection, if collecting was started in current thread.
+pub fn record(result: &ModuleResult) {
+    COLLECTED.with(|collected| {
+        if let Some(results) = collected.borrow_mut().last_mut() {
+            results.push(result.clone());
+        };
+    });
+}
+
+/// Stop innermost collection and return its results, in execution order. They are also
+/// added to the outer collection, if any.
+pub fn take_collected() -> Vec<ModuleResult> {
+    COLLECTED.with(|collected| {
+        let mut collected = collected.borrow_mut();
+        let results = collected.pop().unwrap_or_default();
+        if let Some(outer) = collected.last_mut() {
+            outer.extend(results.iter().cloned());
+        };
+        results
+    })
+}
+
+/// Available serialization formats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Yaml,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Format::Json),
+            "yaml" => Ok(Format::Yaml),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("format '{}' is not supported, use json or yaml", s),
+            )),
+        }
+    }
+}
+
+/// Return [`ModuleResult`] as a JSON value with the report schema.
+///
+/// [`ModuleResult`]: ../modules/struct.ModuleResult.html
+pub fn to_value(result: &ModuleResult) -> Value {
+    let extra = result.get_extra();
+    let get_extra_key = |key: &str| {
+        extra
+            .as_ref()
+            .and_then(|extra| extra.get(key))
+            .cloned()
+            .unwrap_or(Value::Null)
+    };
+    let remaining_extra = match &extra {
+        Some(Value::Object(map)) => {
+            let remaining: Map<String, Value> = map
+                .iter()
+                .filter(|(k, _)| !EXTRACTED_KEYS.contains(&k.as_str()))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            if remaining.is_empty() {
+                Value::Null
+            } else {
+                Value::Object(remaining)
+            }
+        }
+        Some(value) => value.clone(),
+        None => Value::Null,
+    };
+
+    json!({
+        "changed": result.get_changed(),
+        "failed": result.get_failed(),
+        "skipped": get_extra_key("skipped").as_bool().unwrap_or(false),
+        "rc": get_extra_key("rc"),
+        "output": result.get_output(),
+        "extra": remaining_extra,
+        "diff": result.get_diff(),
+    })
+}
+
+/// Return a report of accumulated results with a `stats` summary.
+pub fn to_report(results: &[ModuleResult]) -> Value {
+    let values: Vec<Value> = results.iter().map(to_value).collect();
+    let count = |key: &str| {
+        values
+            .iter()
+            .filter(|value| value[key] == Value::Bool(true))
+            .count()
+    };
+    json!({
+        "results": values,
+        "stats": {
+            "total": values.len(),
+            "changed": count("changed"),
+            "failed": count("failed"),
+            "skipped": count("skipped"),
+        },
+    })
+}
+
+/// Serialize accumulated results in `format`.
+pub fn serialize(results: &[ModuleResult], format: Format) -> Result<String> {
+    let report = to_report(results);
+    match format {
+        Format::Json => {
+            serde_json::to_string_pretty(&report).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+        }
+        Format::Yaml => {
+            serde_yaml::to_string(&report).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::context::Context;
+    use crate::task::parse_tasks;
+    use crate::vars::Vars;
+
+    use yaml_rust::YamlLoader;
+
+    fn get_results() -> Vec<ModuleResult> {
+        vec![
+            ModuleResult::builder()
+                .changed(true)
+                .rc(Some(0))
+                .stdout("foo")
+                .stderr("")
+                .output("foo")
+                .build(),
+            ModuleResult::builder()
+                .changed(true)
+                .failed(true)
+                .rc(Some(2))
+                .stderr("boo")
+                .build(),
+            ModuleResult::builder()
+                .extra("skipped", json!(true))
+                .build(),
+        ]
+    }
+
+    #[test]
+    fn test_to_value() {
+        let result = get_results()[0].clone();
+        assert_eq!(
+            to_value(&result),
+            json!({
+                "changed": true,
+                "failed": false,
+                "skipped": false,
+                "rc": 0,
+                "output": "foo",
+                "extra": {"stdout": "foo", "stderr": ""},
+                "diff": null,
+            })
+        );
+    }
+
+    #[test]
+    fn test_to_value_skipped() {
+        let result = get_results()[2].clone();
+        assert_eq!(
+            to_value(&result),
+            json!({
+                "changed": false,
+                "failed": false,
+                "skipped": true,
+                "rc": null,
+                "output": null,
+                "extra": null,
+                "diff": null,
+            })
+        );
+    }
+
+    #[test]
+    fn test_to_value_diff() {
+        let result = ModuleResult::builder()
+            .changed(true)
+            .diff("foo", "boo")
+            .build();
+        assert_eq!(
+            to_value(&result)["diff"],
+            json!({"before": "foo", "after": "boo"})
+        );
+    }
+
+    #[test]
+    fn test_serialize_json() {
+        let json: Value =
+            serde_json::from_str(&serialize(&get_results(), Format::Json).unwrap()).unwrap();
+        assert_eq!(
+            json["stats"],
+            json!({"total": 3, "changed": 2, "failed": 1, "skipped": 1})
+        );
+        assert_eq!(json["results"][1]["rc"], json!(2));
+        assert_eq!(json["results"][1]["extra"], json!({"stderr": "boo"}));
+    }
+
+    #[test]
+    fn test_serialize_yaml() {
+        let yaml = serialize(&get_results()[2..], Format::Yaml).unwrap();
+        let value: Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(value, to_report(&get_results()[2..]));
+        assert!(yaml.contains("skipped: true"));
+    }
+
+    #[test]
+    fn test_collect_task_results() {
+        let s = r#"
+        - command: echo foo
+        - debug:
+            msg: boo
+          when: false
+        - command: "echo {{ item }}"
+          loop:
+            - 1
+            - 2
+        "#;
+        let tasks = parse_tasks(&YamlLoader::load_from_str(s).unwrap()[0]).unwrap();
+        record(&ModuleResult::builder().build());
+        start_collecting();
+        Context::exec_all(Context::new(tasks, Vars::new())).unwrap();
+        let results = take_collected();
+
+        assert_eq!(
+            to_report(&results)["stats"],
+            json!({"total": 4, "changed": 3, "failed": 0, "skipped": 1})
+        );
+        assert_eq!(results[0].get_output(), Some("foo\n".to_string()));
+        assert_eq!(results[3].get_output(), Some("2\n".to_string()));
+        assert!(take_collected().is_empty());
+    }
+
+    #[test]
+    fn test_collect_nested() {
+        start_collecting();
+        record(&ModuleResult::builder().output("foo").build());
+        start_collecting();
+        record(&ModuleResult::builder().output("boo").build());
+        let inner = take_collected();
+        let outer = take_collected();
+
+        assert_eq!(inner.len(), 1);
+        assert_eq!(inner[0].get_output(), Some("boo".to_string()));
+        assert_eq!(
+            outer
+                .iter()
+                .map(|result| result.get_output().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["foo", "boo"]
+        );
+        assert!(take_collected().is_empty());
+    }
+
+    #[test]
+    fn test_record_without_collecting() {
+        record(&ModuleResult::builder().build());
+        assert!(take_collected().is_empty());
+    }
+
+    #[test]
+    fn test_format_from_str() {
+        assert_eq!(Format::from_str("JSON").unwrap(), Format::Json);
+        assert_eq!(Format::from_str("yaml").unwrap(), Format::Yaml);
+        let error = Format::from_str("toml").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -1,18 +1,25 @@
 mod new;
 mod valid;
 
+use crate::context::Context;
 use crate::error::{Error, ErrorKind, Result};
-use crate::modules::{Module, ModuleResult};
+use crate::modules::{Module, ModuleResult, MODULES};
+use crate::report;
 use crate::task::new::TaskNew;
-use crate::utils::get_yaml;
-use crate::utils::tera::{is_render_string, render_as_json, render_string};
-use crate::vars::Vars;
+use crate::utils::tera::{is_render_string, render_as_json, render_string, OMIT_PLACEHOLDER};
+use crate::utils::{get_yaml, yaml_to_json};
+use crate::vars::{diff_vars, merge_layer, snapshot, Precedence, Vars};
 
 use rash_derive::FieldNames;
 
 use std::fs;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
 
+use serde::Serialize;
 use serde_json::Value;
 use yaml_rust::{Yaml, YamlLoader};
 
@@ -36,18 +43,100 @@ pub struct Task {
     /// Task name.
     name: Option<String>,
     /// Template expression passed directly without {{ }}; if false skip task execution.
+    /// Booleans, integers and `yes`/`no` strings are accepted too.
     when: Option<String>,
     /// Variable name to store [`ModuleOutput`].
     ///
     /// [`ModuleResult`]: ../modules/struct.ModuleResult.html
     register: Option<String>,
-    /// Template expression passed directly without {{ }}; if true errors are ignored.
+    /// If true, errors are logged and the execution continues. The failure is registered
+    /// as a [`ModuleResult`] with `failed` set.
+    ///
+    /// [`ModuleResult`]: ../modules/struct.ModuleResult.html
     ignore_errors: Option<bool>,
     /// `loop` field receives a Template (with {{ }}) or a list to iterate over it.
     r#loop: Option<Yaml>,
+    /// Modifiers of `loop` behaviour, see [`LoopControl`].
+    ///
+    /// [`LoopControl`]: struct.LoopControl.html
+    loop_control: Option<LoopControl>,
+    /// Template expression passed directly without {{ }}; task is retried until it is true.
+    /// Module result is accessible from `register` var.
+    until: Option<String>,
+    /// Max number of retries when `until` is defined. Defaults to 3.
+    retries: Option<u64>,
+    /// Seconds to wait between retries. Defaults to 5.
+    delay: Option<u64>,
+    /// Tasks executed if `block` fails. If they succeed, the failure is cleared.
+    rescue: Option<Tasks>,
+    /// Tasks executed after `block` and `rescue`, even if they fail.
+    always: Option<Tasks>,
+    /// Tags to select tasks from command line. Tasks tagged `always` are always executed
+    /// and tasks tagged `never` only when one of their tags is requested.
+    tags: Option<Vec<String>>,
+    /// Vars only accessible during this task execution.
+    vars: Option<Yaml>,
+    /// Host where the task is executed; it is recorded in [`ModuleResult`].
+    /// Only `localhost` is supported for now.
+    ///
+    /// [`ModuleResult`]: ../modules/struct.ModuleResult.html
+    delegate_to: Option<String>,
+    /// If true, task is executed a single time and its result is shared. With `loop`, the
+    /// module runs once with the whole list as `item`, and a single result is registered.
+    run_once: Option<bool>,
 }
 // ANCHOR_END: task
 
+const DEFAULT_RETRIES: u64 = 3;
+const DEFAULT_DELAY: u64 = 5;
+/// Vars defined in each `loop` iteration, restored to their previous values after the loop.
+const LOOP_VARS: &[&str] = &["item", "loop"];
+
+/// Loop modifiers defined in `loop_control` field.
+#[derive(Debug, Clone, PartialEq)]
+// ANCHOR: loop_control
+pub struct LoopControl {
+    /// Template expression passed directly without {{ }}, evaluated in each iteration;
+    /// if false the item is skipped and its result is marked as `skipped`.
+    when: Option<String>,
+    /// Number of iterations executed concurrently. Results keep the `loop` order.
+    /// Use it only when iterations are independent of each other.
+    parallel: Option<u64>,
+    /// Template rendered in each iteration to describe the item in logs, instead of the
+    /// whole item. It is stored as `label` in each result.
+    label: Option<String>,
+}
+// ANCHOR_END: loop_control
+
+/// Loop metadata accessible from `loop` var in each iteration.
+#[derive(Debug, PartialEq, Serialize)]
+// ANCHOR: loop_info
+struct LoopInfo {
+    /// Current iteration, starting in 1.
+    index: usize,
+    /// Current iteration, starting in 0.
+    index0: usize,
+    /// True in the first iteration.
+    first: bool,
+    /// True in the last iteration.
+    last: bool,
+    /// Number of items in loop.
+    length: usize,
+}
+// ANCHOR_END: loop_info
+
+impl LoopInfo {
+    fn new(index0: usize, length: usize) -> Self {
+        LoopInfo {
+            index: index0 + 1,
+            index0,
+            first: index0 == 0,
+            last: index0 + 1 == length,
+            length,
+        }
+    }
+}
+
 /// A lists of [`Task`]
 ///
 /// [`Task`]: struct.Task.html
@@ -73,18 +162,37 @@ impl Task {
     }
 
     fn render_params(&self, vars: Vars) -> Result<Yaml> {
-        let original_params = self.params.clone();
+        if self.module.get_name() == "block" {
+            // block tasks are rendered when each of them is executed
+            return Ok(self.params.clone());
+        };
+        Task::render_yaml(&self.params, vars)
+    }
+
+    /// Render strings and lists of strings in `original_params` with [`Vars`], recursing into
+    /// nested hashes. Params rendered as [`omit`] placeholder are removed.
+    ///
+    /// [`Vars`]: ../vars/struct.Vars.html
+    /// [`omit`]: ../utils/tera/constant.OMIT_PLACEHOLDER.html
+    fn render_yaml(original_params: &Yaml, vars: Vars) -> Result<Yaml> {
         match original_params.as_hash() {
             Some(hash) => match hash
-                .clone()
                 .iter()
-                .map(|t| match &t.1.clone().as_str() {
-                    Some(s) => match render_string(s, vars.clone()) {
-                        Ok(s) => Ok((t.0.clone(), Yaml::String(s))),
-                        Err(e) => Err(e),
-                    },
-                    None => match t.1.clone().as_vec() {
-                        Some(x) => match x
+                .map(|t| {
+                    let key =
+                        t.0.as_str()
+                            .map_or_else(|| format!("{:?}", t.0), String::from);
+                    match t.1 {
+                        Yaml::String(s) => match render_string(s, vars.clone()) {
+                            Ok(s) if s == OMIT_PLACEHOLDER => Ok(None),
+                            Ok(s) => Ok(Some((t.0.clone(), Yaml::String(s)))),
+                            Err(e) => Err(e.context(format!("param '{}'", key))),
+                        },
+                        Yaml::Hash(_) => match Task::render_yaml(t.1, vars.clone()) {
+                            Ok(rendered_hash) => Ok(Some((t.0.clone(), rendered_hash))),
+                            Err(e) => Err(e.context(format!("param '{}'", key))),
+                        },
+                        Yaml::Array(x) => match x
                             .iter()
                             .map(|yaml| match yaml.as_str() {
                                 Some(s) => Ok(s.to_string()),
@@ -93,27 +201,36 @@ impl Task {
                                     format!("{:?} invalid string", yaml),
                                 )),
                             })
-                            .map(|result_s| match result_s {
-                                Ok(s) => match render_string(&s, vars.clone()) {
-                                    Ok(rendered_s) => Ok(Yaml::String(rendered_s)),
+                            .enumerate()
+                            .filter_map(|(i, result_s)| {
+                                match result_s {
+                                    Ok(s) => match render_string(&s, vars.clone()) {
+                                        Ok(rendered_s) if rendered_s == OMIT_PLACEHOLDER => {
+                                            return None
+                                        }
+                                        Ok(rendered_s) => Ok(Yaml::String(rendered_s)),
+                                        Err(e) => Err(e),
+                                    },
                                     Err(e) => Err(e),
-                                },
-                                Err(e) => Err(e),
+                                }
+                                .map_err(|e| e.context(format!("param '{}[{}]'", key, i)))
+                                .into()
                             })
                             .collect::<Result<Vec<Yaml>>>()
                         {
-                            Ok(rendered_vec) => Ok((t.0.clone(), Yaml::Array(rendered_vec))),
+                            Ok(rendered_vec) => Ok(Some((t.0.clone(), Yaml::Array(rendered_vec)))),
                             Err(e) => Err(e),
                         },
-                        None => Ok((t.0.clone(), t.1.clone())),
-                    },
+                        _ => Ok(Some((t.0.clone(), t.1.clone()))),
+                    }
                 })
+                .filter_map(Result::transpose)
                 .collect::<Result<_>>()
             {
                 Ok(hash) => Ok(Yaml::Hash(hash)),
-                Err(e) => Err(Error::new(ErrorKind::InvalidData, e)),
+                Err(e) => Err(e),
             },
-
+            None if original_params.is_null() => Ok(Yaml::Null),
             None => Ok(Yaml::String(render_string(
                 original_params.as_str().ok_or_else(|| {
                     Error::new(
@@ -133,31 +250,133 @@ impl Task {
         }
     }
 
-    fn get_iterator(yaml: &Yaml, vars: Vars) -> Result<Vec<String>> {
+    fn is_item_exec(&self, vars: Vars) -> Result<bool> {
+        match self
+            .loop_control
+            .as_ref()
+            .and_then(|loop_control| loop_control.when.as_ref())
+        {
+            Some(s) => is_render_string(s, vars),
+            None => Ok(true),
+        }
+    }
+
+    fn exec_module(&self, params: &Yaml, vars: Vars) -> Result<(ModuleResult, Vars)> {
+        let delegate_to = self.render_delegate_to(vars.clone())?;
+        let (mut result, new_vars) = self.exec_module_with_sleep(params, vars, thread::sleep)?;
+        result.set_delegate_to(delegate_to);
+        if let Some(diff) = result.get_diff() {
+            info!(target: "diff",
+                "--- before\n{}\n+++ after\n{}",
+                diff.before.trim_end_matches('\n'),
+                diff.after.trim_end_matches('\n')
+            );
+        };
+        if result.get_failed() {
+            let error = Error::new(ErrorKind::InvalidData, result.get_failed_msg());
+            match self.ignore_errors {
+                Some(true) => info!(target: "ignoring", "{}", error),
+                _ => return Err(error),
+            }
+        };
+        Ok((result, new_vars))
+    }
+
+    fn render_delegate_to(&self, vars: Vars) -> Result<Option<String>> {
+        let delegate_to = match &self.delegate_to {
+            Some(s) => render_string(s, vars)?,
+            None => return Ok(None),
+        };
+        match delegate_to.as_str() {
+            "localhost" | "127.0.0.1" | "::1" => Ok(Some(delegate_to)),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "delegate_to '{}' is not supported, tasks can only run in localhost",
+                    delegate_to
+                ),
+            )),
+        }
+    }
+
+    /// Execute module retrying while `until` is false, calling `sleep` between attempts.
+    fn exec_module_with_sleep<F>(
+        &self,
+        params: &Yaml,
+        vars: Vars,
+        sleep: F,
+    ) -> Result<(ModuleResult, Vars)>
+    where
+        F: Fn(Duration),
+    {
+        let until = match &self.until {
+            Some(s) => s,
+            None => return self.module.exec(params.clone(), vars),
+        };
+        let retries = self.retries.unwrap_or(DEFAULT_RETRIES);
+        let delay = Duration::from_secs(self.delay.unwrap_or(DEFAULT_DELAY));
+
+        let mut attempt = 0;
+        loop {
+            let result = self.module.exec(params.clone(), vars.clone());
+            let is_done = match &result {
+                Ok((module_result, new_vars)) => {
+                    let mut until_vars = new_vars.clone();
+                    if let Some(register) = &self.register {
+                        until_vars.insert(register, module_result);
+                    };
+                    is_render_string(until, until_vars)?
+                }
+                Err(_) => false,
+            };
+            if is_done {
+                return result;
+            };
+            if attempt >= retries {
+                let context = format!("until '{}' not true after {} attempts", until, attempt + 1);
+                return match result {
+                    Ok(_) => Err(Error::new(ErrorKind::Other, context)),
+                    Err(e) => Err(e.context(context)),
+                };
+            };
+            attempt += 1;
+            info!(target: "retrying", "{}/{}", attempt, retries);
+            sleep(delay);
+        }
+    }
+
+    fn get_iterator(yaml: &Yaml, vars: Vars) -> Result<Vec<Value>> {
         match yaml.as_vec() {
             Some(v) => Ok(v
                 .iter()
                 .map(|item| match item.clone() {
-                    Yaml::Real(s) | Yaml::String(s) => Ok(render_string(&s, vars.clone())?),
-                    Yaml::Integer(x) => Ok(render_string(&x.to_string(), vars.clone())?),
+                    Yaml::Real(s) | Yaml::String(s) => {
+                        Ok(Value::String(render_string(&s, vars.clone())?))
+                    }
+                    Yaml::Integer(x) => {
+                        Ok(Value::String(render_string(&x.to_string(), vars.clone())?))
+                    }
+                    Yaml::Hash(_) | Yaml::Array(_) => {
+                        yaml_to_json(&Task::render_yaml(item, vars.clone())?)
+                    }
                     _ => Err(Error::new(
                         ErrorKind::InvalidData,
                         format!("{:?} is not a valid string", item),
                     )),
                 })
-                .collect::<Result<Vec<String>>>()?),
+                .collect::<Result<Vec<Value>>>()?),
             None => Err(Error::new(ErrorKind::NotFound, "loop is not iterable")),
         }
     }
 
-    fn render_iterator(&self, vars: Vars) -> Result<Vec<String>> {
+    fn render_iterator(&self, vars: Vars) -> Result<Vec<Value>> {
         // safe unwrap, previous verification self.r#loop.is_some()
         let loop_some = self.r#loop.clone().unwrap();
         match loop_some.as_str() {
             Some(s) => {
                 let yaml = get_yaml(&render_as_json(&s, vars.clone())?)?;
                 match yaml.as_str() {
-                    Some(s) => Ok(vec![s.to_string()]),
+                    Some(s) => Ok(vec![Value::String(s.to_string())]),
                     None => Task::get_iterator(&yaml, vars),
                 }
             }
@@ -165,109 +384,271 @@ impl Task {
         }
     }
 
-    /// Execute [`Module`] rendering `self.params` with [`Vars`].
+    /// Execute [`Module`] rendering `self.params` with [`Vars`], followed by `rescue` tasks on
+    /// failure and `always` tasks.
     ///
     /// [`Module`]: ../modules/struct.Module.html
     /// [`Vars`]: ../vars/struct.Vars.html
     pub fn exec(&self, vars: Vars) -> Result<Vars> {
+        let task_vars = match &self.vars {
+            Some(task_vars) => Task::render_yaml(task_vars, vars.clone())?,
+            None => return self.exec_with_rescue(vars),
+        };
+        // safe unwrap: set_vars is always a module
+        let (_, task_vars_layer) = MODULES
+            .get("set_vars")
+            .unwrap()
+            .exec(task_vars.clone(), Vars::new())?;
+        let scoped_vars = merge_layer(&vars, Precedence::TaskVars, task_vars_layer);
+
+        let mut new_vars = self.exec_with_rescue(scoped_vars)?;
+        task_vars
+            .as_hash()
+            .into_iter()
+            .flat_map(|hash| hash.keys())
+            .filter_map(|key| key.as_str())
+            .filter(|key| self.register.as_deref() != Some(*key))
+            .for_each(|key| match vars.get(key) {
+                Some(value) => new_vars.insert(key, value),
+                None => {
+                    new_vars.remove(key);
+                }
+            });
+        Ok(new_vars)
+    }
+
+    fn exec_with_rescue(&self, vars: Vars) -> Result<Vars> {
+        let result = match (self.exec_module_task(vars.clone()), &self.rescue) {
+            (Err(e), Some(rescue)) => {
+                info!(target: "rescuing", "{}", e);
+                Context::exec_all(Context::new(rescue.clone(), vars.clone()))
+            }
+            (result, _) => result,
+        };
+
+        match &self.always {
+            Some(always) => {
+                let always_vars = match &result {
+                    Ok(new_vars) => new_vars.clone(),
+                    Err(_) => vars,
+                };
+                let new_vars = Context::exec_all(Context::new(always.clone(), always_vars))?;
+                result.map(|_| new_vars)
+            }
+            None => result,
+        }
+    }
+
+    /// Return a failed result with the error as output if `ignore_errors` is true, so the
+    /// failure can be registered, or propagate the error otherwise.
+    fn handle_error(&self, error: Error, vars: Vars) -> Result<(ModuleResult, Vars)> {
+        match self.ignore_errors {
+            Some(true) => {
+                info!(target: "ignoring", "{}", error);
+                let result = ModuleResult::builder()
+                    .failed(true)
+                    .output(error.to_string())
+                    .build();
+                Ok((result, vars))
+            }
+            _ => {
+                error!("{}", error);
+                Err(error)
+            }
+        }
+    }
+
+    /// Render `loop_control.label` for the current item in `vars`.
+    fn render_label(&self, vars: Vars) -> Result<Option<String>> {
+        match self
+            .loop_control
+            .as_ref()
+            .and_then(|loop_control| loop_control.label.as_ref())
+        {
+            Some(label) => Ok(Some(render_string(label, vars)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn exec_item(
+        &self,
+        item: &Value,
+        index: usize,
+        length: usize,
+        vars: &Vars,
+    ) -> Result<(ModuleResult, Vars)> {
+        let mut exec_vars = vars.clone();
+        exec_vars.insert("item", item);
+        exec_vars.insert("loop", &LoopInfo::new(index, length));
+        let label = self.render_label(exec_vars.clone())?;
+        if !self.is_item_exec(exec_vars.clone())? {
+            info!(target: "skipping", "{}", label.clone().unwrap_or_else(|| match item {
+                Value::String(s) => s.clone(),
+                item => item.to_string(),
+            }));
+            let mut result = ModuleResult::builder()
+                .changed(false)
+                .extra("skipped", json!(true))
+                .build();
+            result.set_label(label);
+            return Ok((result, exec_vars));
+        };
+        let rendered_params = self.render_params(exec_vars.clone())?;
+        let result_wrapped = self.exec_module(&rendered_params, exec_vars.clone());
+        let (mut result, new_vars) = match result_wrapped {
+            Ok((result, new_vars)) => {
+                info!(target: if result.get_changed() {"changed"} else { "ok"},
+                    "{:?}",
+                    label.clone().or_else(|| result.get_output()).unwrap_or_else(
+                        || format!("{:?}", rendered_params)
+                    )
+                );
+                Ok((result, new_vars))
+            }
+            Err(e) => self.handle_error(e, exec_vars),
+        }?;
+        result.set_label(label);
+        Ok((result, new_vars))
+    }
+
+    /// Execute loop `items` in `workers` threads, returning results in `items` order.
+    /// If some iterations fail, the error of the first one is returned.
+    fn exec_items_parallel(
+        &self,
+        items: &[Value],
+        vars: &Vars,
+        workers: usize,
+    ) -> Result<Vec<(ModuleResult, Vars)>> {
+        let next_index = AtomicUsize::new(0);
+        let results = items.iter().map(|_| Mutex::new(None)).collect::<Vec<_>>();
+        thread::scope(|scope| {
+            for _ in 0..workers.min(items.len()) {
+                scope.spawn(|| loop {
+                    let index = next_index.fetch_add(1, Ordering::SeqCst);
+                    let item = match items.get(index) {
+                        Some(item) => item,
+                        None => break,
+                    };
+                    let result = self.exec_item(item, index, items.len(), vars);
+                    // safe unwrap: each result is only locked by one thread
+                    *results[index].lock().unwrap() = Some(result);
+                });
+            }
+        });
+        results
+            .into_iter()
+            .map(|result| {
+                // safe unwrap: every index lower than items length is executed
+                result.into_inner().unwrap().unwrap()
+            })
+            .collect()
+    }
+
+    fn exec_module_task(&self, vars: Vars) -> Result<Vars> {
         debug!("Module: {}", self.module.get_name());
         debug!("Params: {:?}", self.params);
+        let before = if log_enabled!(log::Level::Debug) {
+            Some(snapshot(&vars))
+        } else {
+            None
+        };
 
         let new_vars = if self.is_exec(vars.clone())? {
             let result_json_vars: Result<(Value, Vars)> = if self.r#loop.is_some() {
-                let results_with_vars = self
-                    .render_iterator(vars.clone())?
-                    .into_iter()
-                    .map(|item| {
-                        let mut exec_vars = vars.clone();
-                        exec_vars.insert("item", &item);
-                        let rendered_params = self.render_params(exec_vars.clone())?;
-                        let result_wrapped =
-                            self.module.exec(rendered_params.clone(), exec_vars.clone());
-                        match result_wrapped {
-                            Ok((result, new_vars)) => {
-                                info!(target: if result.get_changed() {"changed"} else { "ok"},
-                                    "{:?}",
-                                    result.get_output().unwrap_or_else(
-                                        || format!("{:?}", rendered_params)
-                                    )
-                                );
-                                Ok((result, new_vars))
-                            }
-                            Err(e) => match self.ignore_errors {
-                                Some(is_true) => {
-                                    if is_true {
-                                        info!(target: "ignoring", "{}", e);
-                                        Ok((ModuleResult::new(false, None, None), exec_vars))
-                                    } else {
-                                        error!("{}", e);
-                                        Err(e)
-                                    }
-                                }
-                                None => {
-                                    error!("{}", e);
-                                    Err(e)
-                                }
-                            },
-                        }
-                    })
-                    .collect::<Result<Vec<(ModuleResult, Vars)>>>()?;
+                let items = self.render_iterator(vars.clone())?;
+                let parallel = self
+                    .loop_control
+                    .as_ref()
+                    .and_then(|loop_control| loop_control.parallel);
+                let run_once = self.run_once == Some(true);
+                let results_with_vars = match parallel {
+                    _ if run_once => vec![self.exec_item(&json!(items), 0, 1, &vars)?],
+                    Some(workers) => self.exec_items_parallel(&items, &vars, workers as usize)?,
+                    None => items
+                        .iter()
+                        .enumerate()
+                        .map(|(index, item)| self.exec_item(item, index, items.len(), &vars))
+                        .collect::<Result<Vec<(ModuleResult, Vars)>>>()?,
+                };
                 let mut new_vars = Vars::new();
                 results_with_vars
                     .iter()
                     .for_each(|(_, vars)| new_vars.extend(vars.clone()));
+                LOOP_VARS.iter().for_each(|key| match vars.get(key) {
+                    Some(value) => new_vars.insert(*key, value),
+                    None => {
+                        new_vars.remove(key);
+                    }
+                });
                 let results: Vec<ModuleResult> = results_with_vars
                     .iter()
                     .map(|(result, _)| result)
                     .cloned()
                     .collect();
-                Ok((json!(results), new_vars))
+                results.iter().for_each(report::record);
+                match results.first() {
+                    Some(result) if run_once => Ok((json!(result), new_vars)),
+                    _ => Ok((json!(results), new_vars)),
+                }
             } else {
                 let rendered_params = self.render_params(vars.clone())?;
-                let (result, new_vars) =
-                    match self.module.exec(rendered_params.clone(), vars.clone()) {
-                        Ok((result, new_vars)) => {
-                            info!(target: if result.get_changed() {"changed"} else { "ok"},
-                                "{}",
-                                result.get_output().unwrap_or_else(
-                                    || format!("{:?}", rendered_params)
-                                )
-                            );
-                            Ok((result, new_vars))
-                        }
-                        Err(e) => match self.ignore_errors {
-                            Some(is_true) => {
-                                if is_true {
-                                    info!(target: "ignoring", "{}", e);
-                                    Ok((ModuleResult::new(false, None, None), vars))
-                                } else {
-                                    error!("{}", e);
-                                    Err(e)
-                                }
-                            }
-                            None => {
-                                error!("{}", e);
-                                Err(e)
-                            }
-                        },
-                    }?;
+                let (result, new_vars) = match self.exec_module(&rendered_params, vars.clone()) {
+                    Ok((result, new_vars)) => {
+                        info!(target: if result.get_changed() {"changed"} else { "ok"},
+                            "{}",
+                            result.get_output().unwrap_or_else(
+                                || format!("{:?}", rendered_params)
+                            )
+                        );
+                        Ok((result, new_vars))
+                    }
+                    Err(e) => self.handle_error(e, vars),
+                }?;
+                report::record(&result);
                 Ok((json!(result), new_vars))
             };
             let json_vars = result_json_vars?;
             let result = json_vars.0;
             let mut new_vars = json_vars.1;
-            if self.register.is_some() {
-                new_vars.insert(self.register.as_ref().unwrap(), &result);
+            if let Some(register) = &self.register {
+                let mut registered = Vars::new();
+                registered.insert(register, &result);
+                new_vars = merge_layer(&new_vars, Precedence::SetVars, registered);
             }
             new_vars
         } else {
             info!(target: "skipping", "");
+            report::record(
+                &ModuleResult::builder()
+                    .extra("skipped", json!(true))
+                    .build(),
+            );
             vars
         };
 
+        if let Some(before) = before {
+            debug!(
+                "Vars changes: {:?}",
+                diff_vars(&before, &snapshot(&new_vars))
+            );
+        };
         Ok(new_vars)
     }
 
+    /// Return true if task must be executed when `tags` are requested. When no tags are
+    /// requested, or `all` is, every task is executed except the `never` tagged ones.
+    pub fn is_tagged(&self, tags: &[String]) -> bool {
+        let task_tags = self.tags.clone().unwrap_or_default();
+        let has_tag = |tag: &str| task_tags.iter().any(|t| t == tag);
+        if task_tags.iter().any(|t| tags.contains(t)) {
+            return true;
+        };
+        if has_tag("never") {
+            return false;
+        };
+        has_tag("always") || tags.is_empty() || tags.iter().any(|t| t == "all")
+    }
+
     /// Return name.
     pub fn get_name(&self) -> Option<String> {
         self.name.clone()
@@ -302,6 +683,16 @@ impl Task {
             register: None,
             ignore_errors: None,
             r#loop: None,
+            loop_control: None,
+            until: None,
+            retries: None,
+            delay: None,
+            rescue: None,
+            always: None,
+            tags: None,
+            vars: None,
+            run_once: None,
+            delegate_to: None,
             params: YamlLoader::load_from_str("cmd: ls")
                 .unwrap()
                 .first()
@@ -324,9 +715,50 @@ pub fn read_file(tasks_file_path: PathBuf) -> Result<Tasks> {
         )
     })?;
 
-    yaml.clone()
+    parse_tasks(yaml)
+}
+
+/// Select [`Tasks`] which must be executed when `tags` are requested.
+///
+/// [`Tasks`]: type.Tasks.html
+pub fn filter_by_tags(tasks: Tasks, tags: &[String]) -> Tasks {
+    tasks
         .into_iter()
-        .map(|task| Task::new(&task))
+        .filter(|task| task.is_tagged(tags))
+        .collect()
+}
+
+/// Skip [`Tasks`] before the first one named `name`, to resume a partially completed
+/// execution. Skipped tasks are not executed, so they do not change any var.
+///
+/// [`Tasks`]: type.Tasks.html
+pub fn start_at_task(tasks: Tasks, name: &str) -> Result<Tasks> {
+    match tasks
+        .iter()
+        .position(|task| task.name.as_deref() == Some(name))
+    {
+        Some(index) => Ok(tasks.into_iter().skip(index).collect()),
+        None => Err(Error::new(
+            ErrorKind::NotFound,
+            format!("task '{}' not found", name),
+        )),
+    }
+}
+
+/// Create [`Tasks`] from a [`Yaml`] list.
+///
+/// [`Tasks`]: type.Tasks.html
+/// [`Yaml`]: ../../yaml_rust/struct.Yaml.html
+pub fn parse_tasks(yaml: &Yaml) -> Result<Tasks> {
+    yaml.as_vec()
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("tasks {:?} must be a list", yaml),
+            )
+        })?
+        .iter()
+        .map(Task::new)
         .collect::<Result<Tasks>>()
 }
 
@@ -349,7 +781,7 @@ mod tests {
     use crate::vars;
 
     use std::collections::HashMap;
-    use std::fs::File;
+    use std::fs::{read_to_string, File};
     use std::io::Write;
 
     use tempfile::tempdir;
@@ -422,7 +854,232 @@ mod tests {
         let out = YamlLoader::load_from_str(&s).unwrap();
         let yaml = out.first().unwrap();
         let task = Task::from(yaml);
-        assert_eq!(task.is_exec(vars).unwrap(), false);
+        assert!(!task.is_exec(vars).unwrap());
+    }
+
+    #[test]
+    fn test_is_exec_defined() {
+        vec![
+            ("myvar is defined", false),
+            ("myvar is undefined", true),
+            ("myvar is defined and myvar.foo == 1", false),
+            ("boo is defined", true),
+        ]
+        .into_iter()
+        .for_each(|(when, expected)| {
+            let s = format!("when: \"{}\"\ncommand: 'example'", when);
+            let out = YamlLoader::load_from_str(&s).unwrap();
+            let yaml = out.first().unwrap();
+            let task = Task::from(yaml);
+            let vars = vars::from_iter(vec![("boo", "test")].into_iter());
+            assert_eq!(task.is_exec(vars).unwrap(), expected, "{}", when);
+        });
+    }
+
+    #[test]
+    fn test_is_exec_yaml_bool_and_int() {
+        vec![
+            ("true", true),
+            ("false", false),
+            ("1", true),
+            ("0", false),
+            ("yes", true),
+            ("'no'", false),
+        ]
+        .into_iter()
+        .for_each(|(when, expected)| {
+            let s = format!("when: {}\ncommand: 'example'", when);
+            let out = YamlLoader::load_from_str(&s).unwrap();
+            let yaml = out.first().unwrap();
+            let task = Task::from(yaml);
+            assert_eq!(task.is_exec(Vars::new()).unwrap(), expected, "{}", when);
+        });
+    }
+
+    #[test]
+    fn test_task_new_when_invalid() {
+        let s = "when: [1, 2]\ncommand: 'example'";
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let yaml = out.first().unwrap();
+        let error = Task::new(yaml).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_task_new_loop_control_invalid() {
+        let s = "loop_control:\n  foo: boo\nloop: [1]\ncommand: 'example'";
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let yaml = out.first().unwrap();
+        let error = Task::new(yaml).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        let s = "loop_control: true\nloop: [1]\ncommand: 'example'";
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let yaml = out.first().unwrap();
+        let error = Task::new(yaml).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        let s = "loop_control:\n  label: [1]\nloop: [1]\ncommand: 'example'";
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let yaml = out.first().unwrap();
+        let error = Task::new(yaml).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        let s = "loop_control:\n  parallel: 0\nloop: [1]\ncommand: 'example'";
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let yaml = out.first().unwrap();
+        let error = Task::new(yaml).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_task_execute_loop_control_parallel() {
+        let s = r#"
+        command: sleep 0.5 && echo {{ item }}
+        loop:
+          - 1
+          - 2
+          - 3
+          - 4
+        loop_control:
+          parallel: 4
+        register: result
+        "#;
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        let start = std::time::Instant::now();
+        let new_vars = task.exec(Vars::new()).unwrap();
+        assert!(start.elapsed() < Duration::from_millis(2000));
+        let outputs = new_vars.into_json()["result"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|result| result["output"].clone())
+            .collect::<Vec<Value>>();
+        assert_eq!(
+            outputs,
+            vec![json!("1\n"), json!("2\n"), json!("3\n"), json!("4\n")]
+        );
+    }
+
+    #[test]
+    fn test_task_execute_loop_control_parallel_error() {
+        let s = r#"
+        command: test {{ item }} != 2
+        loop:
+          - 1
+          - 2
+          - 3
+        loop_control:
+          parallel: 2
+        "#;
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        let _ = task.exec(Vars::new()).unwrap_err();
+    }
+
+    #[test]
+    fn test_task_execute_loop_control_parallel_when() {
+        let s = r#"
+        set_vars:
+          foo: "{{ item }}"
+        loop:
+          - a
+          - b
+          - c
+        loop_control:
+          parallel: 2
+          when: item != "b"
+        register: result
+        "#;
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        let new_vars = task.exec(Vars::new()).unwrap();
+        let results = new_vars.into_json()["result"].clone();
+        assert_eq!(results.as_array().unwrap().len(), 3);
+        assert_eq!(results[1]["extra"]["skipped"], json!(true));
+    }
+
+    #[test]
+    fn test_task_execute_loop_control_label() {
+        let s = r#"
+        set_vars:
+          home: "/home/{{ item.name }}"
+        loop: "{{ users }}"
+        loop_control:
+          label: "{{ item.name }}"
+          when: item.name != "bob"
+        register: result
+        "#;
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        let vars = Vars::from_serialize(json!({
+            "users": [
+                {"name": "alice", "groups": ["admin", "dev"]},
+                {"name": "bob", "groups": []},
+            ]
+        }))
+        .unwrap();
+        let new_vars = task.exec(vars).unwrap();
+        let results = new_vars.get("result").unwrap();
+        assert_eq!(results[0]["label"], json!("alice"));
+        assert_eq!(results[1]["label"], json!("bob"));
+        assert_eq!(results[1]["extra"]["skipped"], json!(true));
+        assert_eq!(new_vars.get("home").unwrap(), &json!("/home/alice"));
+    }
+
+    #[test]
+    fn test_render_iterator_hash() {
+        let s = r#"
+        command: 'example'
+        loop:
+          - name: "{{ boo }}"
+            port: 80
+        "#;
+        let vars = vars::from_iter(vec![("boo", "test")].into_iter());
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::from(out.first().unwrap());
+        assert_eq!(
+            task.render_iterator(vars).unwrap(),
+            vec![json!({"name": "test", "port": 80})]
+        );
+    }
+
+    #[test]
+    fn test_render_params_omit() {
+        let s = r#"
+        copy:
+          content: "{{ boo }}"
+          mode: "{{ file_mode | default(value=omit()) }}"
+          dest: "{{ dest | default(value=omit()) }}"
+        "#;
+        let vars = vars::from_iter(vec![("boo", "test"), ("dest", "/tmp/boo")].into_iter());
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        assert_eq!(
+            task.render_params(vars).unwrap(),
+            YamlLoader::load_from_str("content: test\ndest: /tmp/boo").unwrap()[0]
+        );
+    }
+
+    #[test]
+    fn test_render_params_omit_nested() {
+        let s = r#"
+        command:
+          argv:
+            - ls
+            - "{{ flag | default(value=omit()) }}"
+          env:
+            FOO: "{{ boo }}"
+            BAR: "{{ bar | default(value=omit()) }}"
+        "#;
+        let vars = vars::from_iter(vec![("boo", "test")].into_iter());
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::from(out.first().unwrap());
+        assert_eq!(
+            task.render_params(vars).unwrap(),
+            YamlLoader::load_from_str("argv:\n  - ls\nenv:\n  FOO: test").unwrap()[0]
+        );
     }
 
     #[test]
@@ -486,6 +1143,679 @@ mod tests {
         assert_eq!(task.render_iterator(vars).unwrap(), vec!["test", "2"]);
     }
 
+    #[test]
+    fn test_task_execute_loop_fileglob() {
+        let dir = tempdir().unwrap();
+        ["a.conf", "b.conf", "c.txt"].iter().for_each(|name| {
+            let mut file = File::create(dir.path().join(name)).unwrap();
+            writeln!(file, "{}", name).unwrap();
+        });
+        let s: String = r#"
+        command: cat {{ item }}
+        loop: "{{ fileglob(pattern=conf_dir ~ '/*.conf') }}"
+        register: result
+        "#
+        .to_owned();
+        let out = YamlLoader::load_from_str(&s).unwrap();
+        let yaml = out.first().unwrap();
+        let task = Task::from(yaml);
+        let vars = vars::from_iter(vec![("conf_dir", dir.path().to_str().unwrap())].into_iter());
+        let new_vars = task.exec(vars).unwrap();
+        let outputs = new_vars.into_json()["result"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|result| result["output"].as_str().unwrap().to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(outputs, vec!["a.conf\n", "b.conf\n"]);
+    }
+
+    #[test]
+    fn test_task_execute_loop_fileglob_no_matches() {
+        let dir = tempdir().unwrap();
+        let s: String = r#"
+        command: cat {{ item }}
+        loop: "{{ fileglob(pattern=conf_dir ~ '/*.conf') }}"
+        register: result
+        "#
+        .to_owned();
+        let out = YamlLoader::load_from_str(&s).unwrap();
+        let yaml = out.first().unwrap();
+        let task = Task::from(yaml);
+        let vars = vars::from_iter(vec![("conf_dir", dir.path().to_str().unwrap())].into_iter());
+        let new_vars = task.exec(vars).unwrap();
+        assert_eq!(new_vars.into_json()["result"], json!([]));
+    }
+
+    #[test]
+    fn test_loop_info() {
+        assert_eq!(
+            LoopInfo::new(0, 3),
+            LoopInfo {
+                index: 1,
+                index0: 0,
+                first: true,
+                last: false,
+                length: 3,
+            }
+        );
+        assert_eq!(
+            LoopInfo::new(2, 3),
+            LoopInfo {
+                index: 3,
+                index0: 2,
+                first: false,
+                last: true,
+                length: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn test_task_execute_loop_info() {
+        let s: String = r#"
+        command: echo {{ loop.index }} {{ loop.first }} {{ loop.last }} {{ item }}
+        loop:
+          - a
+          - b
+          - c
+        register: result
+        "#
+        .to_owned();
+        let out = YamlLoader::load_from_str(&s).unwrap();
+        let yaml = out.first().unwrap();
+        let task = Task::from(yaml);
+        let new_vars = task.exec(Vars::new()).unwrap();
+        let outputs = new_vars
+            .into_json()
+            .get("result")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|result| result.get("output").unwrap().as_str().unwrap().to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            outputs,
+            vec!["1 true false a\n", "2 false false b\n", "3 false true c\n"]
+        );
+    }
+
+    #[test]
+    fn test_task_execute_copy_content() {
+        let dir = tempdir().unwrap();
+        let dest_path = dir.path().join("dest.txt");
+        let s = format!(
+            r#"
+        copy:
+          content: "{{{{ rendered }}}}"
+          dest: {}
+        "#,
+            dest_path.to_str().unwrap()
+        );
+        let out = YamlLoader::load_from_str(&s).unwrap();
+        let yaml = out.first().unwrap();
+        let task = Task::from(yaml);
+        let vars = vars::from_iter(vec![("rendered", "foo")].into_iter());
+        task.exec(vars).unwrap();
+        assert_eq!(std::fs::read_to_string(&dest_path).unwrap(), "foo");
+    }
+
+    #[test]
+    fn test_task_execute_loop_control_when() {
+        let s: String = r#"
+        command: echo {{ item }}
+        loop:
+          - 1
+          - 2
+          - 3
+          - 4
+          - 5
+        loop_control:
+          when: item == "2" or loop.last
+        register: result
+        "#
+        .to_owned();
+        let out = YamlLoader::load_from_str(&s).unwrap();
+        let yaml = out.first().unwrap();
+        let task = Task::new(yaml).unwrap();
+        let new_vars = task.exec(Vars::new()).unwrap();
+        let results = new_vars
+            .into_json()
+            .get("result")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .clone();
+        let outputs = results
+            .iter()
+            .map(|result| result.get("output").unwrap().as_str().map(String::from))
+            .collect::<Vec<Option<String>>>();
+        assert_eq!(
+            outputs,
+            vec![
+                None,
+                Some("2\n".to_string()),
+                None,
+                None,
+                Some("5\n".to_string())
+            ]
+        );
+        let skipped = results
+            .iter()
+            .map(|result| result["extra"]["skipped"] == json!(true))
+            .collect::<Vec<bool>>();
+        assert_eq!(skipped, vec![true, false, true, true, false]);
+    }
+
+    #[test]
+    fn test_exec_module_until() {
+        let dir = tempdir().unwrap();
+        let counter_path = dir.path().join("counter");
+        let s = format!(
+            r#"
+        command:
+          argv:
+            - sh
+            - -c
+            - echo x >> {path} && wc -l < {path}
+        until: '"3" in result.output'
+        retries: 5
+        delay: 1
+        register: result
+        "#,
+            path = counter_path.to_str().unwrap()
+        );
+        let out = YamlLoader::load_from_str(&s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        let params = task.render_params(Vars::new()).unwrap();
+
+        let sleeps = std::cell::RefCell::new(Vec::new());
+        let (result, _) = task
+            .exec_module_with_sleep(&params, Vars::new(), |d| sleeps.borrow_mut().push(d))
+            .unwrap();
+        assert!(result.get_output().unwrap().contains('3'));
+        assert_eq!(sleeps.into_inner(), vec![Duration::from_secs(1); 2]);
+    }
+
+    #[test]
+    fn test_exec_module_until_retries_exhausted() {
+        let s = r#"
+        command: echo foo
+        until: result.output == "boo"
+        retries: 2
+        register: result
+        "#;
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        let params = task.render_params(Vars::new()).unwrap();
+
+        let sleeps = std::cell::RefCell::new(Vec::new());
+        let error = task
+            .exec_module_with_sleep(&params, Vars::new(), |d| sleeps.borrow_mut().push(d))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Other);
+        assert_eq!(
+            sleeps.into_inner(),
+            vec![Duration::from_secs(DEFAULT_DELAY); 2]
+        );
+    }
+
+    #[test]
+    fn test_task_new_retries_invalid() {
+        let s = "until: 'true'\nretries: -1\ncommand: 'example'";
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let error = Task::new(out.first().unwrap()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    fn exec_block(s: &str) -> Result<Vars> {
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        task.exec(Vars::new())
+    }
+
+    #[test]
+    fn test_task_execute_block_rescue() {
+        let vars = exec_block(
+            r#"
+        block:
+          - set_vars:
+              foo: boo
+          - assert:
+              that:
+                - foo == 'yea'
+          - set_vars:
+              not_executed: true
+        rescue:
+          - set_vars:
+              rescued: true
+        "#,
+        )
+        .unwrap();
+        let json = vars.into_json();
+        assert_eq!(json["rescued"], json!(true));
+        assert_eq!(json["foo"], Value::Null);
+        assert_eq!(json["not_executed"], Value::Null);
+    }
+
+    #[test]
+    fn test_task_execute_block_rescue_fail() {
+        let error = exec_block(
+            r#"
+        block:
+          - assert:
+              that:
+                - "1 == 2"
+        rescue:
+          - command: this-command-does-not-exist
+        "#,
+        )
+        .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_task_execute_block_always() {
+        let vars = exec_block(
+            r#"
+        block:
+          - set_vars:
+              foo: boo
+        always:
+          - set_vars:
+              always: "{{ foo }}"
+        "#,
+        )
+        .unwrap();
+        assert_eq!(vars.into_json()["always"], json!("boo"));
+
+        let dir = tempdir().unwrap();
+        let always_path = dir.path().join("always");
+        let error = exec_block(&format!(
+            r#"
+        block:
+          - assert:
+              that:
+                - "1 == 2"
+        always:
+          - copy:
+              content: "always"
+              dest: {}
+        "#,
+            always_path.to_str().unwrap()
+        ))
+        .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Other);
+        assert_eq!(fs::read_to_string(&always_path).unwrap(), "always");
+    }
+
+    #[test]
+    fn test_task_new_rescue_without_block() {
+        let s = "command: ls\nrescue:\n  - command: ls";
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let error = Task::new(out.first().unwrap()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_task_new_block_invalid_task() {
+        let s = "block:\n  - no_module: ls";
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let error = Task::new(out.first().unwrap()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_task_execute_include_tasks_templated() {
+        let dir = tempdir().unwrap();
+        let mut file = File::create(dir.path().join("debian.rh")).unwrap();
+        writeln!(file, "- set_vars:\n    included: true").unwrap();
+
+        let s = format!(
+            "include_tasks: \"{}/{{{{ os_family }}}}.rh\"",
+            dir.path().to_str().unwrap()
+        );
+        let out = YamlLoader::load_from_str(&s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        let vars = vars::from_iter(vec![("os_family", "debian")].into_iter());
+        let new_vars = task.exec(vars).unwrap();
+        assert_eq!(new_vars.into_json()["included"], json!(true));
+    }
+
+    fn get_tagged_task_names(tags: &[&str]) -> Vec<String> {
+        let s = r#"
+        - name: install
+          command: echo install
+          tags: install
+        - name: configure
+          command: echo configure
+          tags:
+            - configure
+            - config
+        - name: untagged
+          command: echo untagged
+        - name: always
+          command: echo always
+          tags: always
+        - name: debug
+          command: echo debug
+          tags:
+            - never
+            - debug
+        "#;
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let tasks = parse_tasks(out.first().unwrap()).unwrap();
+        let tags = tags.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        filter_by_tags(tasks, &tags)
+            .iter()
+            .map(|task| task.get_name().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_filter_by_tags() {
+        assert_eq!(
+            get_tagged_task_names(&["config"]),
+            vec!["configure", "always"]
+        );
+        assert_eq!(
+            get_tagged_task_names(&["install", "debug"]),
+            vec!["install", "always", "debug"]
+        );
+    }
+
+    #[test]
+    fn test_filter_by_tags_all() {
+        let all = vec!["install", "configure", "untagged", "always"];
+        assert_eq!(get_tagged_task_names(&[]), all);
+        assert_eq!(get_tagged_task_names(&["all"]), all);
+    }
+
+    #[test]
+    fn test_start_at_task() {
+        let s = r#"
+        - name: first
+          set_vars:
+            foo: boo
+        - name: second
+          set_vars:
+            bar: "{{ foo | default(value='none') }}"
+        - name: third
+          set_vars:
+            baz: yea
+        "#;
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let tasks = start_at_task(parse_tasks(out.first().unwrap()).unwrap(), "second").unwrap();
+        assert_eq!(
+            tasks
+                .iter()
+                .map(|task| task.get_name().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["second", "third"]
+        );
+
+        let new_vars = Context::exec_all(Context::new(tasks, Vars::new())).unwrap();
+        assert!(!new_vars.contains_key("foo"));
+        assert_eq!(new_vars.get("bar").unwrap(), "none");
+        assert_eq!(new_vars.get("baz").unwrap(), "yea");
+    }
+
+    #[test]
+    fn test_start_at_task_not_found() {
+        let out = YamlLoader::load_from_str("- name: first\n  command: ls").unwrap();
+        let error =
+            start_at_task(parse_tasks(out.first().unwrap()).unwrap(), "second").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        assert_eq!(format!("{}", error), "task 'second' not found");
+    }
+
+    #[test]
+    fn test_task_new_tags_invalid() {
+        let s = "command: ls\ntags:\n  - foo: boo";
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let error = Task::new(out.first().unwrap()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_task_execute_vars() {
+        let s = r#"
+        - set_vars:
+            greeting: "{{ message }} {{ name }}"
+          vars:
+            message: hello
+            name: "{{ user }}"
+        - assert:
+            that:
+              - message is not defined
+              - name == "foo"
+        "#;
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let tasks = parse_tasks(out.first().unwrap()).unwrap();
+        let mut vars = vars::from_iter(vec![("user", "boo"), ("name", "foo")].into_iter());
+        vars.insert("user", "boo");
+
+        let new_vars = tasks[0].exec(vars).unwrap();
+        // previous vars take precedence over task vars
+        assert_eq!(new_vars.get("greeting").unwrap(), "hello foo");
+        assert!(!new_vars.contains_key("message"));
+        assert_eq!(new_vars.get("name").unwrap(), "foo");
+        tasks[1].exec(new_vars).unwrap();
+    }
+
+    #[test]
+    fn test_task_execute_extra_vars() {
+        let s = r#"
+        - set_vars:
+            foo: set_vars
+        - command: echo {{ foo }}
+          register: foo
+        - set_vars:
+            greeting: "hello {{ foo }}"
+          vars:
+            foo: task
+        "#;
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let tasks = parse_tasks(out.first().unwrap()).unwrap();
+        let mut vars = vars::from_iter(vec![("foo", "extra")].into_iter());
+        vars.insert("rash", &json!({"extra_vars": {"foo": "extra"}}));
+
+        let new_vars = tasks
+            .iter()
+            .fold(vars, |vars, task| task.exec(vars).unwrap());
+        assert_eq!(new_vars.get("foo").unwrap(), "extra");
+        assert_eq!(new_vars.get("greeting").unwrap(), "hello extra");
+    }
+
+    #[test]
+    fn test_task_new_vars_invalid() {
+        let s = "command: ls\nvars: foo";
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let error = Task::new(out.first().unwrap()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_task_execute_delegate_to() {
+        let s = r#"
+        command: echo foo
+        delegate_to: "{{ host }}"
+        register: result
+        "#;
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        let vars = vars::from_iter(vec![("host", "localhost")].into_iter());
+        let new_vars = task.exec(vars).unwrap();
+        assert_eq!(
+            new_vars.into_json()["result"]["delegate_to"],
+            json!("localhost")
+        );
+
+        let params = task.render_params(Vars::new()).unwrap();
+        let vars = vars::from_iter(vec![("host", "127.0.0.1")].into_iter());
+        let (result, _) = task.exec_module(&params, vars).unwrap();
+        assert_eq!(result.get_delegate_to(), Some("127.0.0.1".to_string()));
+    }
+
+    #[test]
+    fn test_task_execute_delegate_to_remote() {
+        let s = "command: echo foo\ndelegate_to: example.com";
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        let error = task.exec(Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_task_execute_no_delegate_to() {
+        let s = "command: echo foo\nregister: result";
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        let new_vars = task.exec(Vars::new()).unwrap();
+        let result = new_vars.into_json()["result"].clone();
+        assert!(result.get("delegate_to").is_none());
+    }
+
+    #[test]
+    fn test_task_execute_command_failed_ignore_errors() {
+        let s = r#"
+        command: sh -c 'echo foo; echo boo >&2; exit 2'
+        ignore_errors: true
+        register: result
+        "#;
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        let new_vars = task.exec(Vars::new()).unwrap();
+        let result = new_vars.into_json()["result"].clone();
+        assert_eq!(result["failed"], json!(true));
+        assert_eq!(result["extra"]["rc"], json!(2));
+        assert_eq!(result["extra"]["stdout"], json!("foo\n"));
+        assert_eq!(result["extra"]["stderr"], json!("boo\n"));
+    }
+
+    #[test]
+    fn test_task_execute_command_failed() {
+        let s = "command: sh -c 'echo boo >&2; exit 2'";
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        let error = task.exec(Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(format!("{}", error), "boo\n");
+    }
+
+    #[test]
+    fn test_task_execute_ignore_errors() {
+        let s = r#"
+        - assert:
+            that:
+              - "1 == 2"
+          ignore_errors: true
+          register: failed_result
+        - set_vars:
+            after: true
+        "#;
+        let tasks = parse_tasks(&YamlLoader::load_from_str(s).unwrap()[0]).unwrap();
+        let new_vars = Context::exec_all(Context::new(tasks, Vars::new())).unwrap();
+        let json = new_vars.into_json();
+        assert_eq!(json["after"], json!(true));
+        assert_eq!(json["failed_result"]["failed"], json!(true));
+        assert_eq!(json["failed_result"]["changed"], json!(false));
+        assert!(json["failed_result"]["output"]
+            .as_str()
+            .unwrap()
+            .contains("1 == 2"));
+    }
+
+    #[test]
+    fn test_task_execute_ignore_errors_false() {
+        let s = r#"
+        assert:
+          that:
+            - "1 == 2"
+        ignore_errors: false
+        "#;
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        let _ = task.exec(Vars::new()).unwrap_err();
+    }
+
+    #[test]
+    fn test_task_execute_loop_vars_removed() {
+        let s = r#"
+        set_vars:
+          last_item: "{{ item }}"
+        loop:
+          - foo
+          - boo
+        "#;
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        let new_vars = task.exec(Vars::new()).unwrap();
+        assert_eq!(new_vars.get("last_item").unwrap(), "boo");
+        assert!(!new_vars.contains_key("item"));
+        assert!(!new_vars.contains_key("loop"));
+    }
+
+    #[test]
+    fn test_task_execute_loop_vars_restored() {
+        let s = r#"
+        debug:
+          msg: "{{ loop.index }} {{ item }}"
+        loop:
+          - foo
+        "#;
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        let vars = vars::from_iter(vec![("loop", "user loop")].into_iter());
+        let new_vars = task.exec(vars).unwrap();
+        assert_eq!(new_vars.get("loop").unwrap(), "user loop");
+        assert!(!new_vars.contains_key("item"));
+    }
+
+    #[test]
+    fn test_task_execute_run_once_loop() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("executions");
+        let s = format!(
+            r#"
+        command:
+          cmd: "echo {{{{ item | join(sep=',') }}}} >> {}"
+        loop:
+          - foo
+          - boo
+          - zoo
+        run_once: true
+        register: result
+        "#,
+            file_path.display()
+        );
+        let out = YamlLoader::load_from_str(&s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        let new_vars = task.exec(Vars::new()).unwrap();
+        assert_eq!(read_to_string(&file_path).unwrap(), "foo,boo,zoo\n");
+        let result = &new_vars.into_json()["result"];
+        assert!(result.is_object());
+        assert_eq!(result["changed"], json!(true));
+    }
+
+    #[test]
+    fn test_task_execute_run_once_false_loop() {
+        let s = r#"
+        debug:
+          msg: "{{ item }}"
+        loop:
+          - foo
+          - boo
+        run_once: false
+        register: result
+        "#;
+        let out = YamlLoader::load_from_str(s).unwrap();
+        let task = Task::new(out.first().unwrap()).unwrap();
+        let new_vars = task.exec(Vars::new()).unwrap();
+        assert_eq!(new_vars.into_json()["result"].as_array().unwrap().len(), 2);
+    }
+
     #[test]
     fn test_task_execute() {
         let task = Task::test_example();
@@ -559,6 +1889,29 @@ mod tests {
         assert_eq!(rendered_params["cmd"].as_str().unwrap(), "ls boo");
     }
 
+    #[test]
+    fn test_render_params_error_key() {
+        let s0 = r#"
+        name: task 1
+        command:
+          cmd: ls {{ boo }}
+          argv:
+            - ls
+            - "{{ foo }}"
+        "#
+        .to_owned();
+        let yaml = get_yaml(&s0).unwrap();
+        let task = Task::from(&yaml);
+
+        let error = task.render_params(Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(format!("{}", error).starts_with("param 'cmd': "));
+
+        let vars = vars::from_iter(vec![("boo", "boo")].into_iter());
+        let error = task.render_params(vars).unwrap_err();
+        assert!(format!("{}", error).starts_with("param 'argv[1]': "));
+    }
+
     #[test]
     fn test_render_params_no_hash_map() {
         let s0 = r#"
@@ -580,4 +1933,18 @@ mod tests {
         let rendered_params = task.render_params(vars).unwrap();
         assert_eq!(rendered_params.as_str().unwrap(), "ls boo");
     }
+
+    #[test]
+    fn test_render_params_null() {
+        let s0 = r#"
+        name: task 1
+        setup:
+        "#
+        .to_owned();
+        let yaml = get_yaml(&s0).unwrap();
+        let task = Task::from(&yaml);
+
+        let rendered_params = task.render_params(Vars::new()).unwrap();
+        assert!(rendered_params.is_null());
+    }
 }
@@ -1,6 +1,6 @@
 use crate::error::{Error, ErrorKind, Result};
 use crate::modules::{is_module, MODULES};
-use crate::task::Task;
+use crate::task::{parse_tasks, LoopControl, Task, Tasks};
 
 use std::collections::HashSet;
 
@@ -56,11 +56,138 @@ impl TaskValid {
             })
     }
 
+    /// Get `when` as string, accepting native YAML booleans and integers too.
+    fn get_when(when: &Yaml) -> Result<Option<String>> {
+        match when {
+            Yaml::String(s) => Ok(Some(s.clone())),
+            Yaml::Boolean(b) => Ok(Some(b.to_string())),
+            Yaml::Integer(i) => Ok(Some(i.to_string())),
+            Yaml::BadValue | Yaml::Null => Ok(None),
+            when => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("when {:?} must be a string, boolean or integer", when),
+            )),
+        }
+    }
+
+    fn get_loop_control(&self) -> Result<Option<LoopControl>> {
+        let loop_control = &self.attrs["loop_control"];
+        match loop_control.as_hash() {
+            Some(hash) => {
+                if let Some(key) = hash.keys().find(|key| {
+                    !matches!(
+                        key.as_str(),
+                        Some("when") | Some("parallel") | Some("label")
+                    )
+                }) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("loop_control {:?} is not a valid key", key),
+                    ));
+                };
+                let parallel = match &loop_control["parallel"] {
+                    Yaml::Integer(i) if *i > 0 => Some(*i as u64),
+                    Yaml::BadValue => None,
+                    value => {
+                        return Err(Error::new(
+                            ErrorKind::InvalidData,
+                            format!(
+                                "loop_control parallel {:?} must be a positive integer",
+                                value
+                            ),
+                        ))
+                    }
+                };
+                let label = match &loop_control["label"] {
+                    Yaml::String(s) => Some(s.clone()),
+                    Yaml::BadValue => None,
+                    value => {
+                        return Err(Error::new(
+                            ErrorKind::InvalidData,
+                            format!("loop_control label {:?} must be a string", value),
+                        ))
+                    }
+                };
+                Ok(Some(LoopControl {
+                    when: TaskValid::get_when(&loop_control["when"])?,
+                    parallel,
+                    label,
+                }))
+            }
+            None if loop_control.is_badvalue() => Ok(None),
+            None => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("loop_control {:?} must be a hash", loop_control),
+            )),
+        }
+    }
+
+    fn get_u64(&self, key: &str) -> Result<Option<u64>> {
+        match &self.attrs[key] {
+            Yaml::Integer(i) if *i >= 0 => Ok(Some(*i as u64)),
+            Yaml::BadValue => Ok(None),
+            value => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{} {:?} must be a positive integer", key, value),
+            )),
+        }
+    }
+
+    /// Get `rescue` or `always` tasks, only valid in `block` tasks.
+    fn get_block_tasks(&self, key: &str, module_name: &str) -> Result<Option<Tasks>> {
+        match &self.attrs[key] {
+            Yaml::BadValue => Ok(None),
+            _ if module_name != "block" => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{} is only valid in block tasks", key),
+            )),
+            yaml => Ok(Some(parse_tasks(yaml)?)),
+        }
+    }
+
+    /// Get `tags` from a string or a list of strings.
+    fn get_tags(&self) -> Result<Option<Vec<String>>> {
+        let invalid = || {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "tags {:?} must be a string or a list of strings",
+                    self.attrs["tags"]
+                ),
+            )
+        };
+        match &self.attrs["tags"] {
+            Yaml::BadValue => Ok(None),
+            Yaml::String(s) => Ok(Some(vec![s.clone()])),
+            Yaml::Array(v) => v
+                .iter()
+                .map(|tag| tag.as_str().map(String::from).ok_or_else(invalid))
+                .collect::<Result<Vec<String>>>()
+                .map(Some),
+            _ => Err(invalid()),
+        }
+    }
+
+    fn get_vars(&self) -> Result<Option<Yaml>> {
+        match &self.attrs["vars"] {
+            Yaml::BadValue => Ok(None),
+            Yaml::Hash(_) => Ok(Some(self.attrs["vars"].clone())),
+            vars => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("vars {:?} must be a dict", vars),
+            )),
+        }
+    }
+
     pub fn get_task(&self) -> Result<Task> {
         let module_name: &str = &self.get_module_name()?;
+        if module_name == "block" {
+            // validate block tasks before running
+            parse_tasks(&self.attrs[module_name])?;
+        };
         Ok(Task {
             name: self.attrs["name"].as_str().map(String::from),
-            when: self.attrs["when"].as_str().map(String::from),
+            when: TaskValid::get_when(&self.attrs["when"])?,
             register: self.attrs["register"].as_str().map(String::from),
             ignore_errors: self.attrs["ignore_errors"].as_bool(),
             r#loop: if self.attrs["loop"].is_badvalue() {
@@ -68,6 +195,16 @@ impl TaskValid {
             } else {
                 Some(self.attrs["loop"].clone())
             },
+            loop_control: self.get_loop_control()?,
+            until: TaskValid::get_when(&self.attrs["until"])?,
+            retries: self.get_u64("retries")?,
+            delay: self.get_u64("delay")?,
+            rescue: self.get_block_tasks("rescue", module_name)?,
+            always: self.get_block_tasks("always", module_name)?,
+            tags: self.get_tags()?,
+            vars: self.get_vars()?,
+            delegate_to: self.attrs["delegate_to"].as_str().map(String::from),
+            run_once: self.attrs["run_once"].as_bool(),
             module: MODULES
                 .get::<str>(&module_name)
                 .ok_or_else(|| {
@@ -1 +1,303 @@
+use crate::error::{Error, ErrorKind, Result};
+use crate::utils::time::format_utc_time;
 
+use std::fs::{copy, read, read_dir, remove_file, write, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use sha2::{Digest, Sha256};
+
+const BACKUP_TIME_FORMAT: &str = "%Y-%m-%d@%H:%M:%S";
+// `2020-07-01@12:00:00.000000`
+const BACKUP_TIMESTAMP_LEN: usize = 26;
+
+/// Errors of atomic rename which `unsafe_writes` falls back from, the same as Ansible.
+const UNSAFE_WRITES_ERRNOS: &[i32] = &[
+    libc::EPERM,
+    libc::EXDEV,
+    libc::EACCES,
+    libc::ETXTBSY,
+    libc::EBUSY,
+];
+
+fn get_file_name(path: &Path) -> Result<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("{:?} has no file name", path),
+            )
+        })
+}
+
+/// Temporary file in the same directory as `dest`, so it can be atomically renamed.
+pub fn get_tmp_path(dest: &Path) -> Result<PathBuf> {
+    let file_name = dest.file_name().ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("dest {:?} has no file name", dest),
+        )
+    })?;
+    Ok(dest.with_file_name(format!(".{}.rash.tmp", file_name.to_string_lossy())))
+}
+
+/// Replace `dest` with `tmp_path` using `rename_fn`. If it fails and `unsafe_writes` is set,
+/// `dest` is truncated and written in place instead.
+pub fn replace_dest<F>(
+    tmp_path: &Path,
+    dest: &Path,
+    unsafe_writes: bool,
+    rename_fn: F,
+) -> Result<()>
+where
+    F: Fn(&Path, &Path) -> io::Result<()>,
+{
+    let result = match rename_fn(tmp_path, dest) {
+        Ok(()) => return Ok(()),
+        Err(e)
+            if unsafe_writes
+                && e.raw_os_error()
+                    .is_some_and(|errno| UNSAFE_WRITES_ERRNOS.contains(&errno)) =>
+        {
+            trace!(
+                "cannot rename to {}, writing in place: {}",
+                dest.display(),
+                e
+            );
+            read(tmp_path).and_then(|content| write(dest, content))
+        }
+        Err(e) => Err(e),
+    };
+    let _ = remove_file(tmp_path);
+    Ok(result?)
+}
+
+/// Return true if `name` is a backup file name of `file_name`, as created by [`backup`].
+fn is_backup_of(name: &str, file_name: &str) -> bool {
+    name.strip_prefix(file_name)
+        .and_then(|rest| rest.strip_prefix('.'))
+        .and_then(|rest| rest.strip_suffix('~'))
+        .is_some_and(|timestamp| {
+            timestamp.len() == BACKUP_TIMESTAMP_LEN
+                && timestamp
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || "-@:.".contains(c))
+        })
+}
+
+/// Copy `path` to a timestamped file in the same directory, returning backup path.
+/// E.g.: `foo.conf.2020-07-01@12:00:00.000000~`. Permissions are preserved.
+/// Timestamp is in UTC, so name order keeps being creation order across timezone changes.
+pub fn backup(path: &Path) -> Result<String> {
+    let file_name = get_file_name(path)?;
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| Error::new(ErrorKind::Other, e))?;
+    let backup_path = path.with_file_name(format!(
+        "{}.{}.{:06}~",
+        file_name,
+        format_utc_time(BACKUP_TIME_FORMAT, time.as_secs() as i64)?,
+        time.subsec_micros()
+    ));
+    trace!("backup {:?} to {:?}", path, backup_path);
+    copy(path, &backup_path)?;
+    Ok(backup_path.to_string_lossy().to_string())
+}
+
+/// Return sha256 of `path` content as a lowercase hex string.
+pub fn get_checksum(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Remove backups of `path` created by [`backup`], except the newest `keep` ones.
+/// Return removed backup paths.
+pub fn remove_old_backups(path: &Path, keep: usize) -> Result<Vec<String>> {
+    let file_name = get_file_name(path)?;
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut backups = read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|entry| {
+            entry.is_file()
+                && entry
+                    .file_name()
+                    .is_some_and(|name| is_backup_of(&name.to_string_lossy(), &file_name))
+        })
+        .collect::<Vec<_>>();
+    // timestamps have fixed width, so name order is creation order
+    backups.sort();
+    backups.reverse();
+
+    backups
+        .iter()
+        .skip(keep)
+        .map(|backup_path| {
+            trace!("removing backup {:?}", backup_path);
+            remove_file(backup_path)?;
+            Ok(backup_path.to_string_lossy().to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::{read_to_string, File};
+    use std::io::Write;
+    use std::os::unix::fs::MetadataExt;
+
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_is_backup_of() {
+        assert!(is_backup_of(
+            "foo.conf.2020-07-01@12:00:00.000000~",
+            "foo.conf"
+        ));
+        assert!(!is_backup_of(
+            "foo.conf.2020-07-01@12:00:00.000000",
+            "foo.conf"
+        ));
+        assert!(!is_backup_of("foo.conf.bak~", "foo.conf"));
+        assert!(!is_backup_of(
+            "boo.conf.2020-07-01@12:00:00.000000~",
+            "foo.conf"
+        ));
+    }
+
+    #[test]
+    fn test_backup() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("foo.conf");
+        let mut file = File::create(&path).unwrap();
+        writeln!(file, "foo").unwrap();
+
+        let backup_path = backup(&path).unwrap();
+        assert_ne!(backup_path, path.to_str().unwrap());
+        assert_eq!(read_to_string(&backup_path).unwrap(), "foo\n");
+        assert!(is_backup_of(
+            Path::new(&backup_path)
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "foo.conf"
+        ));
+    }
+
+    #[test]
+    fn test_get_checksum() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("foo.conf");
+        let mut file = File::create(&path).unwrap();
+        write!(file, "foo").unwrap();
+
+        assert_eq!(
+            get_checksum(&path).unwrap(),
+            "2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae"
+        );
+        let _ = get_checksum(&dir.path().join("boo.conf")).unwrap_err();
+    }
+
+    #[test]
+    fn test_remove_old_backups() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("foo.conf");
+        let backup_names = [
+            "foo.conf.2020-07-01@12:00:00.000000~",
+            "foo.conf.2020-07-01@12:00:00.000001~",
+            "foo.conf.2020-07-02@08:00:00.000000~",
+            "foo.conf.2021-01-01@00:00:00.000000~",
+        ];
+        backup_names
+            .iter()
+            .chain(
+                [
+                    "foo.conf",
+                    "foo.conf.bak~",
+                    "boo.conf.2019-01-01@00:00:00.000000~",
+                ]
+                .iter(),
+            )
+            .for_each(|name| {
+                File::create(dir.path().join(name)).unwrap();
+            });
+
+        let removed = remove_old_backups(&path, 2).unwrap();
+        assert_eq!(
+            removed,
+            vec![
+                dir.path().join(backup_names[1]).to_str().unwrap(),
+                dir.path().join(backup_names[0]).to_str().unwrap(),
+            ]
+        );
+        assert!(dir.path().join(backup_names[2]).exists());
+        assert!(dir.path().join(backup_names[3]).exists());
+        assert!(path.exists());
+        assert!(dir.path().join("foo.conf.bak~").exists());
+        assert!(dir
+            .path()
+            .join("boo.conf.2019-01-01@00:00:00.000000~")
+            .exists());
+
+        assert_eq!(remove_old_backups(&path, 2).unwrap(), Vec::<String>::new());
+        assert_eq!(remove_old_backups(&path, 0).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_remove_old_backups_created() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("foo.conf");
+        File::create(&path).unwrap();
+        let backups = (0..3)
+            .map(|_| backup(&path).unwrap())
+            .collect::<Vec<String>>();
+
+        let removed = remove_old_backups(&path, 1).unwrap();
+        assert_eq!(removed, vec![backups[1].clone(), backups[0].clone()]);
+        assert!(Path::new(&backups[2]).exists());
+    }
+
+    #[test]
+    fn test_replace_dest_unsafe_writes() {
+        let dir = tempdir().unwrap();
+        let dest_path = dir.path().join("app.conf");
+        let mut dest_file = File::create(dest_path.clone()).unwrap();
+        writeln!(dest_file, "port=80").unwrap();
+        let inode = dest_path.metadata().unwrap().ino();
+        let tmp_path = get_tmp_path(&dest_path).unwrap();
+        let busy = |_: &Path, _: &Path| Err(io::Error::from_raw_os_error(libc::EBUSY));
+
+        write(&tmp_path, "port=8080\n").unwrap();
+        let error = replace_dest(&tmp_path, &dest_path, false, busy).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::IOError);
+        assert_eq!(read_to_string(&dest_path).unwrap(), "port=80\n");
+        assert!(!tmp_path.exists());
+
+        write(&tmp_path, "port=8080\n").unwrap();
+        replace_dest(&tmp_path, &dest_path, true, busy).unwrap();
+        assert_eq!(read_to_string(&dest_path).unwrap(), "port=8080\n");
+        assert_eq!(dest_path.metadata().unwrap().ino(), inode);
+        assert!(!tmp_path.exists());
+    }
+
+    #[test]
+    fn test_replace_dest_unsafe_writes_other_error() {
+        let dir = tempdir().unwrap();
+        let dest_path = dir.path().join("app.conf");
+        let tmp_path = get_tmp_path(&dest_path).unwrap();
+        write(&tmp_path, "port=8080\n").unwrap();
+        let not_found = |_: &Path, _: &Path| Err(io::Error::from_raw_os_error(libc::ENOENT));
+        let _ = replace_dest(&tmp_path, &dest_path, true, not_found).unwrap_err();
+        assert!(!dest_path.exists());
+    }
+}
@@ -1,44 +1,80 @@
 pub mod file;
 pub mod tera;
+pub mod time;
+pub mod user;
+pub mod vault;
 
 use crate::error::{Error, ErrorKind, Result};
 
-use yaml_rust::{Yaml, YamlLoader};
-
-pub fn parse_octal(s: &str) -> Result<u32> {
-    match s.len() {
-        3 => u32::from_str_radix(&s, 8).map_err(|e| Error::new(ErrorKind::InvalidData, e)),
-        4 => u32::from_str_radix(s.get(1..).unwrap(), 8)
-            .map_err(|e| Error::new(ErrorKind::InvalidData, e)),
-        _ => Err(Error::new(
-            ErrorKind::InvalidData,
-            format!("{} cannot be parsed to octal", s),
-        )),
-    }
-}
+use serde_json::Value;
+use yaml_rust::{Yaml, YamlEmitter, YamlLoader};
 
 pub fn get_yaml(s: &str) -> Result<Yaml> {
     let doc = YamlLoader::load_from_str(&s).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
     Ok(doc.first().unwrap().clone())
 }
 
+/// Convert `yaml` to its JSON value.
+pub fn yaml_to_json(yaml: &Yaml) -> Result<Value> {
+    let mut yaml_str = String::new();
+    YamlEmitter::new(&mut yaml_str)
+        .dump(yaml)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    serde_yaml::from_str(&yaml_str).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Translate Python replacement syntax to `regex` crate one: `\1` and `\g<name>` to `${1}` and
+/// `${name}`, escaping `$` which is literal in Python.
+pub fn to_regex_replacement(s: &str) -> String {
+    let mut replacement = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('$', _) => replacement.push_str("$$"),
+            ('\\', Some(d)) if d.is_ascii_digit() => {
+                let mut group = String::new();
+                while let Some(d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                    group.push(*d);
+                    chars.next();
+                }
+                replacement.push_str(&format!("${{{}}}", group));
+            }
+            ('\\', Some('g')) => {
+                let rest = chars.clone().skip(1).collect::<String>();
+                match rest.strip_prefix('<').and_then(|rest| rest.split_once('>')) {
+                    Some((name, _)) => {
+                        replacement.push_str(&format!("${{{}}}", name));
+                        // skip `g<name>`
+                        chars.nth(name.chars().count() + 2);
+                    }
+                    None => replacement.push(c),
+                }
+            }
+            ('\\', Some('\\')) => {
+                replacement.push('\\');
+                chars.next();
+            }
+            _ => replacement.push(c),
+        }
+    }
+    replacement
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_parse_octal() {
-        assert_eq!(parse_octal("644").unwrap(), 0o644);
-        assert_eq!(parse_octal("0644").unwrap(), 0o644);
-        assert_eq!(parse_octal("777").unwrap(), 0o777);
-        assert_eq!(parse_octal("0444").unwrap(), 0o444);
-        assert_eq!(parse_octal("600").unwrap(), 0o600);
-        assert_eq!(parse_octal("0600").unwrap(), 0o600);
-    }
-
     #[test]
     fn test_get_yaml() {
         let yaml = get_yaml(&"foo: boo").unwrap();
         assert_eq!(yaml["foo"].as_str().unwrap(), "boo")
     }
+
+    #[test]
+    fn test_to_regex_replacement() {
+        assert_eq!(to_regex_replacement(r"\1-\2"), "${1}-${2}");
+        assert_eq!(to_regex_replacement(r"\g<name>x"), "${name}x");
+        assert_eq!(to_regex_replacement(r"$1 \\1 \n"), r"$$1 \1 \n");
+        assert_eq!(to_regex_replacement(r"\g"), r"\g");
+    }
 }
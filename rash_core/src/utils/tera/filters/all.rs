@@ -0,0 +1,58 @@
+/// ANCHOR: filter
+/// ## all
+///
+/// Return true if every element of a list is truthy. Empty lists are `true`.
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     mirrors:
+///       - "eu.example.com"
+///       - "us.example.com"
+///     checks:
+///       - ""
+///       - "ok"
+///
+/// - assert:
+///     that:
+///       - "mirrors | all"
+///       - "not checks | all"
+/// ```
+/// ANCHOR_END: filter
+use crate::utils::tera::filters::is_truthy;
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Result};
+
+pub fn all(value: &Value, _: &HashMap<String, Value>) -> Result<Value> {
+    let list = try_get_value!("all", "value", Vec<Value>, value);
+    Ok(Value::Bool(list.iter().all(is_truthy)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_all() {
+        let args = HashMap::new();
+        assert_eq!(all(&json!([true, 1, "yes"]), &args).unwrap(), json!(true));
+        assert_eq!(all(&json!([true, 0, "yes"]), &args).unwrap(), json!(false));
+        assert_eq!(all(&json!([false, "", null]), &args).unwrap(), json!(false));
+        assert_eq!(all(&json!([]), &args).unwrap(), json!(true));
+        let _ = all(&json!("foo"), &args).unwrap_err();
+    }
+
+    #[test]
+    fn test_all_render() {
+        let vars = Vars::from_serialize(json!({"checks": [true, true]})).unwrap();
+        let result = render_string("{% if checks | all %}ok{% endif %}", vars).unwrap();
+        assert_eq!(result, "ok");
+    }
+}
@@ -0,0 +1,59 @@
+/// ANCHOR: filter
+/// ## any
+///
+/// Return true if at least one element of a list is truthy. Empty lists are `false`.
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     checks:
+///       - ""
+///       - "ok"
+///     empty: []
+///
+/// - assert:
+///     that:
+///       - "checks | any"
+///       - "not empty | any"
+/// ```
+/// ANCHOR_END: filter
+use crate::utils::tera::filters::is_truthy;
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Result};
+
+pub fn any(value: &Value, _: &HashMap<String, Value>) -> Result<Value> {
+    let list = try_get_value!("any", "value", Vec<Value>, value);
+    Ok(Value::Bool(list.iter().any(is_truthy)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_any() {
+        let args = HashMap::new();
+        assert_eq!(any(&json!([false, 0, "yes"]), &args).unwrap(), json!(true));
+        assert_eq!(any(&json!([true, 1]), &args).unwrap(), json!(true));
+        assert_eq!(
+            any(&json!([false, "", null, 0]), &args).unwrap(),
+            json!(false)
+        );
+        assert_eq!(any(&json!([]), &args).unwrap(), json!(false));
+        let _ = any(&json!(1), &args).unwrap_err();
+    }
+
+    #[test]
+    fn test_any_render() {
+        let vars = Vars::from_serialize(json!({"checks": [false, true]})).unwrap();
+        let result = render_string("{% if checks | any %}ok{% endif %}", vars).unwrap();
+        assert_eq!(result, "ok");
+    }
+}
@@ -0,0 +1,118 @@
+/// ANCHOR: filter
+/// ## batch
+///
+/// Split a list into lists of `linecount` elements, as Jinja2 `batch`. The last one is shorter
+/// unless `fill_with` is set.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// linecount:
+///   type: integer
+///   required: true
+///   description: Number of elements in each list.
+/// fill_with:
+///   type: any
+///   description: Value used to fill up the last list.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     hosts:
+///       - web1
+///       - web2
+///       - web3
+///
+/// - assert:
+///     that:
+///       - "hosts | batch(linecount=2) | length == 2"
+///       - "hosts | batch(linecount=2) | last | length == 1"
+///       - "hosts | batch(linecount=2, fill_with='none') | last | last == 'none'"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+pub fn batch(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let list = try_get_value!("batch", "value", Vec<Value>, value);
+    let linecount = match args.get("linecount") {
+        Some(v) => try_get_value!("batch", "linecount", usize, v),
+        None => {
+            return Err(Error::msg(
+                "Filter `batch` expected an arg called `linecount`",
+            ))
+        }
+    };
+    if linecount == 0 {
+        return Err(Error::msg(
+            "Filter `batch` linecount must be greater than 0",
+        ));
+    };
+
+    Ok(Value::Array(
+        list.chunks(linecount)
+            .map(|chunk| {
+                let mut chunk = chunk.to_vec();
+                if let Some(fill_with) = args.get("fill_with") {
+                    chunk.resize(linecount, fill_with.clone());
+                };
+                Value::Array(chunk)
+            })
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    fn get_args(linecount: usize, fill_with: Option<Value>) -> HashMap<String, Value> {
+        vec![("linecount".to_string(), json!(linecount))]
+            .into_iter()
+            .chain(fill_with.map(|v| ("fill_with".to_string(), v)))
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_exact_multiple() {
+        let result = batch(&json!([1, 2, 3, 4, 5, 6]), &get_args(3, Some(json!(0)))).unwrap();
+        assert_eq!(result, json!([[1, 2, 3], [4, 5, 6]]));
+    }
+
+    #[test]
+    fn test_batch() {
+        let result = batch(&json!([1, 2, 3, 4, 5]), &get_args(2, None)).unwrap();
+        assert_eq!(result, json!([[1, 2], [3, 4], [5]]));
+    }
+
+    #[test]
+    fn test_batch_fill_with() {
+        let result = batch(&json!([1, 2, 3, 4, 5]), &get_args(3, Some(json!("none")))).unwrap();
+        assert_eq!(result, json!([[1, 2, 3], [4, 5, "none"]]));
+    }
+
+    #[test]
+    fn test_batch_invalid() {
+        let _ = batch(&json!([1, 2]), &get_args(0, None)).unwrap_err();
+        let _ = batch(&json!([1, 2]), &HashMap::new()).unwrap_err();
+        let _ = batch(&json!("foo"), &get_args(1, None)).unwrap_err();
+    }
+
+    #[test]
+    fn test_batch_render() {
+        let vars = Vars::from_serialize(json!({"items": ["a", "b", "c"]})).unwrap();
+        let result = render_string(
+            "{% for row in items | batch(linecount=2, fill_with='-') %}{{ row | join(sep=' ') }};{% endfor %}",
+            vars,
+        )
+        .unwrap();
+        assert_eq!(result, "a b;c -;");
+    }
+}
@@ -0,0 +1,99 @@
+/// ANCHOR: filter
+/// ## bool
+///
+/// Convert common boolean representations to a real boolean, case insensitively:
+/// `yes`, `true`, `on`, `1` are `true` and `no`, `false`, `off`, `0` are `false`.
+/// Any other value fails.
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     enabled: "Yes"
+///     debug: "off"
+///
+/// - assert:
+///     that:
+///       - "enabled | bool"
+///       - "not debug | bool"
+///       - "1 | bool"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{Error, Result};
+
+pub fn bool(value: &Value, _: &HashMap<String, Value>) -> Result<Value> {
+    let b = match value {
+        Value::Bool(b) => Some(*b),
+        Value::Number(n) if n.as_f64() == Some(1.0) => Some(true),
+        Value::Number(n) if n.as_f64() == Some(0.0) => Some(false),
+        Value::String(s) => match s.trim().to_lowercase().as_str() {
+            "yes" | "true" | "on" | "1" => Some(true),
+            "no" | "false" | "off" | "0" => Some(false),
+            _ => None,
+        },
+        _ => None,
+    };
+    b.map(Value::Bool)
+        .ok_or_else(|| Error::msg(format!("Filter `bool` cannot convert {} to bool", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_bool_true() {
+        [
+            json!("yes"),
+            json!("Yes"),
+            json!("true"),
+            json!("TRUE"),
+            json!("on"),
+            json!("1"),
+            json!(1),
+            json!(1.0),
+            json!(true),
+        ]
+        .iter()
+        .for_each(|v| assert_eq!(bool(v, &HashMap::new()).unwrap(), json!(true), "{}", v));
+    }
+
+    #[test]
+    fn test_bool_false() {
+        [
+            json!("no"),
+            json!("NO"),
+            json!("false"),
+            json!("False"),
+            json!("off"),
+            json!("0"),
+            json!(0),
+            json!(false),
+        ]
+        .iter()
+        .for_each(|v| assert_eq!(bool(v, &HashMap::new()).unwrap(), json!(false), "{}", v));
+    }
+
+    #[test]
+    fn test_bool_invalid() {
+        [json!("maybe"), json!(2), json!(null), json!([1])]
+            .iter()
+            .for_each(|v| {
+                let _ = bool(v, &HashMap::new()).unwrap_err();
+            });
+    }
+
+    #[test]
+    fn test_bool_render() {
+        let mut vars = Vars::new();
+        vars.insert("enabled", "Yes");
+        let result = render_string("{% if enabled | bool %}on{% endif %}", vars).unwrap();
+        assert_eq!(result, "on");
+    }
+}
@@ -0,0 +1,102 @@
+/// ANCHOR: filter
+/// ## checksum
+///
+/// Return the hexadecimal checksum of a string. Useful to generate stable identifiers.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// algorithm:
+///   type: string
+///   description: Hash algorithm to use, `sha1` or `sha256`. Defaults to `sha1`.
+/// length:
+///   type: integer
+///   description: Return only the first `length` characters of the checksum.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "'hello' | checksum == 'aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d'"
+///       - "'hello' | checksum(length=7) == 'aaf4c61'"
+///       - "'hello' | checksum(algorithm='sha256') | length == 64"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use tera::{try_get_value, Error, Result};
+
+pub fn checksum(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let s = try_get_value!("checksum", "value", String, value);
+    let algorithm = match args.get("algorithm") {
+        Some(v) => try_get_value!("checksum", "algorithm", String, v),
+        None => "sha1".to_string(),
+    };
+
+    let hex = match algorithm.as_str() {
+        "sha1" => format!("{:x}", Sha1::digest(s.as_bytes())),
+        "sha256" => format!("{:x}", Sha256::digest(s.as_bytes())),
+        _ => {
+            return Err(Error::msg(format!(
+                "Filter `checksum` received an unsupported algorithm: {}",
+                algorithm
+            )))
+        }
+    };
+
+    match args.get("length") {
+        Some(v) => {
+            let length = try_get_value!("checksum", "length", usize, v);
+            Ok(Value::String(hex.chars().take(length).collect()))
+        }
+        None => Ok(Value::String(hex)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_checksum() {
+        let result = checksum(&json!("hello"), &HashMap::new()).unwrap();
+        assert_eq!(result, json!("aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d"));
+    }
+
+    #[test]
+    fn test_checksum_sha256() {
+        let args = args!("algorithm" => json!("sha256"));
+        let result = checksum(&json!("hello"), &args).unwrap();
+        assert_eq!(
+            result,
+            json!("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824")
+        );
+    }
+
+    #[test]
+    fn test_checksum_length() {
+        let args = args!("length" => json!(7));
+        let result = checksum(&json!("hello"), &args).unwrap();
+        assert_eq!(result, json!("aaf4c61"));
+    }
+
+    #[test]
+    fn test_checksum_invalid_algorithm() {
+        let args = args!("algorithm" => json!("md5"));
+        let _ = checksum(&json!("hello"), &args).unwrap_err();
+    }
+
+    #[test]
+    fn test_checksum_render() {
+        let result = render_string("{{ 'hello' | checksum }}", Vars::new()).unwrap();
+        assert_eq!(result, "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d");
+    }
+}
@@ -0,0 +1,183 @@
+/// ANCHOR: filter
+/// ## comment
+///
+/// Wrap a multi-line string in comment lines, as in Ansible `comment` filter.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// style:
+///   type: string
+///   description: |
+///     Comment style: `plain` (#), `erlang`, `c` (//), `cblock` or `xml`.
+///     Defaults to `plain`.
+/// decoration:
+///   type: string
+///   description: String prepended to each line of the text.
+/// beginning:
+///   type: string
+///   description: First line of the comment block.
+/// end:
+///   type: string
+///   description: Last line of the comment block.
+/// prefix:
+///   type: string
+///   description: Line added before the text. Defaults to decoration without trailing spaces.
+/// postfix:
+///   type: string
+///   description: Line added after the text. Defaults to decoration without trailing spaces.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       # 'foo' | comment renders three lines: '#', '# foo' and '#'
+///       - "'foo' | comment | length == 9"
+///       - "'foo' | comment(style='c') | length == 12"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+struct Style {
+    beginning: &'static str,
+    decoration: &'static str,
+    end: &'static str,
+}
+
+fn get_style(name: &str) -> Option<Style> {
+    match name {
+        "plain" | "#" => Some(Style {
+            beginning: "",
+            decoration: "# ",
+            end: "",
+        }),
+        "erlang" => Some(Style {
+            beginning: "",
+            decoration: "% ",
+            end: "",
+        }),
+        "c" | "//" => Some(Style {
+            beginning: "",
+            decoration: "// ",
+            end: "",
+        }),
+        "cblock" => Some(Style {
+            beginning: "/*",
+            decoration: " * ",
+            end: " */",
+        }),
+        "xml" => Some(Style {
+            beginning: "<!--",
+            decoration: " - ",
+            end: "-->",
+        }),
+        _ => None,
+    }
+}
+
+fn get_string_arg(args: &HashMap<String, Value>, key: &str, default: &str) -> Result<String> {
+    match args.get(key) {
+        Some(v) => Ok(try_get_value!("comment", key, String, v)),
+        None => Ok(default.to_string()),
+    }
+}
+
+fn get_count_arg(args: &HashMap<String, Value>, key: &str) -> Result<usize> {
+    match args.get(key) {
+        Some(v) => Ok(try_get_value!("comment", key, usize, v)),
+        None => Ok(1),
+    }
+}
+
+pub fn comment(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let text = try_get_value!("comment", "value", String, value);
+    let style_name = get_string_arg(args, "style", "plain")?;
+    let style = get_style(&style_name)
+        .ok_or_else(|| Error::msg(format!("comment style '{}' not supported", style_name)))?;
+
+    let decoration = get_string_arg(args, "decoration", style.decoration)?;
+    let beginning = get_string_arg(args, "beginning", style.beginning)?;
+    let end = get_string_arg(args, "end", style.end)?;
+    let prefix = get_string_arg(args, "prefix", decoration.trim_end())?;
+    let postfix = get_string_arg(args, "postfix", decoration.trim_end())?;
+    let prefix_count = get_count_arg(args, "prefix_count")?;
+    let postfix_count = get_count_arg(args, "postfix_count")?;
+
+    let mut lines = Vec::new();
+    if !beginning.is_empty() {
+        lines.push(beginning);
+    }
+    if !prefix.is_empty() {
+        lines.extend(vec![prefix; prefix_count]);
+    }
+    lines.extend(text.split('\n').map(|line| {
+        if line.is_empty() {
+            decoration.trim_end().to_string()
+        } else {
+            format!("{}{}", decoration, line)
+        }
+    }));
+    lines.extend(vec![postfix; postfix_count]);
+    if !end.is_empty() {
+        lines.push(end);
+    }
+
+    Ok(Value::String(lines.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_comment() {
+        let result = comment(&json!("foo\nboo"), &HashMap::new()).unwrap();
+        assert_eq!(result, json!("#\n# foo\n# boo\n#"));
+    }
+
+    #[test]
+    fn test_comment_c_style() {
+        let args = args!("style" => json!("c"));
+        let result = comment(&json!("foo\nboo"), &args).unwrap();
+        assert_eq!(result, json!("//\n// foo\n// boo\n//"));
+    }
+
+    #[test]
+    fn test_comment_cblock_style() {
+        let args = args!("style" => json!("cblock"));
+        let result = comment(&json!("foo"), &args).unwrap();
+        assert_eq!(result, json!("/*\n *\n * foo\n *\n */"));
+    }
+
+    #[test]
+    fn test_comment_custom() {
+        let args = args!(
+            "decoration" => json!("; "),
+            "beginning" => json!("start"),
+            "end" => json!("end"),
+            "prefix" => json!(""),
+        );
+        let result = comment(&json!("foo\n\nboo"), &args).unwrap();
+        assert_eq!(result, json!("start\n; foo\n;\n; boo\n;\nend"));
+    }
+
+    #[test]
+    fn test_comment_invalid_style() {
+        let args = args!("style" => json!("boo"));
+        let _ = comment(&json!("foo"), &args).unwrap_err();
+    }
+
+    #[test]
+    fn test_comment_render() {
+        let result = render_string("{{ 'foo' | comment(style='xml') }}", Vars::new()).unwrap();
+        assert_eq!(result, "<!--\n -\n - foo\n -\n-->");
+    }
+}
@@ -0,0 +1,81 @@
+/// ANCHOR: filter
+/// ## expanduser
+///
+/// Expand a leading `~` or `~user` in a path to the user home directory. `~` uses `HOME`
+/// environment variable and `~user` the passwd database.
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "'~root/.config' | expanduser == '/root/.config'"
+///       - "'/etc/rash' | expanduser == '/etc/rash'"
+/// ```
+/// ANCHOR_END: filter
+use crate::utils::user::get_home_dir;
+
+use std::collections::HashMap;
+use std::env;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+pub fn expanduser(value: &Value, _: &HashMap<String, Value>) -> Result<Value> {
+    let path = try_get_value!("expanduser", "value", String, value);
+    let rest = match path.strip_prefix('~') {
+        Some(rest) => rest,
+        None => return Ok(Value::String(path)),
+    };
+
+    let (user, tail) = match rest.find('/') {
+        Some(i) => rest.split_at(i),
+        None => (rest, ""),
+    };
+    let home_dir = if user.is_empty() {
+        env::var("HOME").map_err(|e| Error::chain("expanduser cannot get HOME", e))?
+    } else {
+        get_home_dir(user).map_err(|e| Error::msg(e.to_string()))?
+    };
+    Ok(Value::String(format!("{}{}", home_dir, tail)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_expanduser_home() {
+        let home = env::var("HOME").unwrap();
+        let result = expanduser(&json!("~/.config/app"), &HashMap::new()).unwrap();
+        assert_eq!(result, json!(format!("{}/.config/app", home)));
+        let result = expanduser(&json!("~"), &HashMap::new()).unwrap();
+        assert_eq!(result, json!(home));
+    }
+
+    #[test]
+    fn test_expanduser_user() {
+        let result = expanduser(&json!("~root/.config"), &HashMap::new()).unwrap();
+        assert_eq!(result, json!("/root/.config"));
+    }
+
+    #[test]
+    fn test_expanduser_user_not_found() {
+        let _ = expanduser(&json!("~this_user_does_not_exist/foo"), &HashMap::new()).unwrap_err();
+    }
+
+    #[test]
+    fn test_expanduser_unchanged() {
+        let result = expanduser(&json!("/etc/rash/~foo"), &HashMap::new()).unwrap();
+        assert_eq!(result, json!("/etc/rash/~foo"));
+    }
+
+    #[test]
+    fn test_expanduser_render() {
+        let result = render_string("{{ '~root' | expanduser }}", Vars::new()).unwrap();
+        assert_eq!(result, "/root");
+    }
+}
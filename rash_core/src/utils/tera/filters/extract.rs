@@ -0,0 +1,133 @@
+/// ANCHOR: filter
+/// ## extract
+///
+/// Use the value as an index or key to lookup in a container, as in Ansible `extract` filter.
+/// When the value is a list, each element is extracted, the same as Ansible
+/// `map('extract', container)`.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// container:
+///   type: list or dict
+///   required: true
+///   description: List indexed by position or dict indexed by key.
+/// morekeys:
+///   type: string or list
+///   description: Keys used to extract nested values from the result.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     letters:
+///       - a
+///       - b
+///       - c
+///
+/// - assert:
+///     that:
+///       - "1 | extract(container=letters) == 'b'"
+///       - "'HOME' | extract(container=env) == env.HOME"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{Error, Result};
+
+fn get_element<'a>(container: &'a Value, key: &Value) -> Result<&'a Value> {
+    let element = match (container, key) {
+        (Value::Array(list), Value::Number(n)) => n.as_u64().and_then(|i| list.get(i as usize)),
+        (Value::Array(list), Value::String(s)) => s.parse::<usize>().ok().and_then(|i| list.get(i)),
+        (Value::Object(map), Value::String(s)) => map.get(s),
+        (Value::Object(map), Value::Number(n)) => map.get(&n.to_string()),
+        _ => None,
+    };
+    element.ok_or_else(|| Error::msg(format!("{} not found in {}", key, container)))
+}
+
+fn extract_element(key: &Value, container: &Value, morekeys: &[Value]) -> Result<Value> {
+    morekeys
+        .iter()
+        .try_fold(get_element(container, key)?, |value, k| {
+            get_element(value, k)
+        })
+        .cloned()
+}
+
+pub fn extract(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let container = args
+        .get("container")
+        .ok_or_else(|| Error::msg("Filter `extract` expected an arg called `container`"))?;
+    let morekeys = match args.get("morekeys") {
+        Some(Value::Array(keys)) => keys.clone(),
+        Some(key) => vec![key.clone()],
+        None => vec![],
+    };
+
+    match value {
+        Value::Array(keys) => keys
+            .iter()
+            .map(|key| extract_element(key, container, &morekeys))
+            .collect::<Result<Vec<Value>>>()
+            .map(Value::Array),
+        key => extract_element(key, container, &morekeys),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_extract_list_index() {
+        let args = args!("container" => json!(["a", "b", "c"]));
+        assert_eq!(extract(&json!(1), &args).unwrap(), json!("b"));
+        assert_eq!(extract(&json!([0, 2]), &args).unwrap(), json!(["a", "c"]));
+    }
+
+    #[test]
+    fn test_extract_dict_key() {
+        let args = args!("container" => json!({"foo": 1, "boo": 2}));
+        assert_eq!(extract(&json!("boo"), &args).unwrap(), json!(2));
+        assert_eq!(
+            extract(&json!(["boo", "foo"]), &args).unwrap(),
+            json!([2, 1])
+        );
+    }
+
+    #[test]
+    fn test_extract_morekeys() {
+        let args = args!(
+            "container" => json!({"foo": {"ips": ["10.0.0.1", "10.0.0.2"]}}),
+            "morekeys" => json!(["ips", 1]),
+        );
+        assert_eq!(extract(&json!("foo"), &args).unwrap(), json!("10.0.0.2"));
+    }
+
+    #[test]
+    fn test_extract_not_found() {
+        let args = args!("container" => json!(["a"]));
+        let _ = extract(&json!(3), &args).unwrap_err();
+    }
+
+    #[test]
+    fn test_extract_no_container() {
+        let _ = extract(&json!(0), &HashMap::new()).unwrap_err();
+    }
+
+    #[test]
+    fn test_extract_render() {
+        let result = render_string(
+            "{{ [0, 2] | extract(container=['a', 'b', 'c']) | join(sep=',') }}",
+            Vars::new(),
+        )
+        .unwrap();
+        assert_eq!(result, "a,c");
+    }
+}
@@ -0,0 +1,134 @@
+/// ANCHOR: filter
+/// ## first
+///
+/// Return the first element of a list or the first character of a string. Overrides Tera
+/// builtin `first` so empty inputs return `default` arg, or `null` if it is not set, instead
+/// of an empty string. Tera `default` filter only applies to missing vars in the first filter
+/// of an expression, so it cannot replace the result of `first`.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// default:
+///   type: any
+///   description: Value returned when input is empty. Defaults to null.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     packages:
+///       - curl
+///       - git
+///     empty: []
+///
+/// - assert:
+///     that:
+///       - "packages | first == 'curl'"
+///       - "'rash' | first == 'r'"
+///       - "empty | first(default='none') == 'none'"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{Error, Result};
+
+/// Get element at the start, or at the end if `from_end`, for `filter`. Empty inputs return
+/// `default` arg or `null`.
+pub fn get_edge(
+    filter: &str,
+    value: &Value,
+    args: &HashMap<String, Value>,
+    from_end: bool,
+) -> Result<Value> {
+    let default = args.get("default").cloned().unwrap_or(Value::Null);
+    match value {
+        Value::Array(list) => {
+            let element = if from_end { list.last() } else { list.first() };
+            Ok(element.cloned().unwrap_or(default))
+        }
+        Value::String(s) => {
+            let c = if from_end {
+                s.chars().last()
+            } else {
+                s.chars().next()
+            };
+            Ok(c.map_or(default, |c| Value::String(c.to_string())))
+        }
+        _ => Err(Error::msg(format!(
+            "Filter `{}` requires a list or a string, found: {}",
+            filter, value
+        ))),
+    }
+}
+
+pub fn first(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    get_edge("first", value, args, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_first() {
+        let result = first(&json!([1, 2, 3]), &HashMap::new()).unwrap();
+        assert_eq!(result, json!(1));
+        let result = first(&json!("foo"), &HashMap::new()).unwrap();
+        assert_eq!(result, json!("f"));
+    }
+
+    #[test]
+    fn test_first_empty() {
+        assert_eq!(first(&json!([]), &HashMap::new()).unwrap(), json!(null));
+        assert_eq!(first(&json!(""), &HashMap::new()).unwrap(), json!(null));
+    }
+
+    #[test]
+    fn test_first_invalid() {
+        let _ = first(&json!(1), &HashMap::new()).unwrap_err();
+    }
+
+    #[test]
+    fn test_first_default_arg() {
+        let args = args!("default" => json!("none"));
+        assert_eq!(first(&json!([]), &args).unwrap(), json!("none"));
+        assert_eq!(first(&json!(""), &args).unwrap(), json!("none"));
+        assert_eq!(first(&json!(["curl"]), &args).unwrap(), json!("curl"));
+    }
+
+    #[test]
+    fn test_first_default_arg_render() {
+        let vars = Vars::from_serialize(json!({"packages": []})).unwrap();
+        let result = render_string("{{ packages | first(default='none') }}", vars).unwrap();
+        assert_eq!(result, "none");
+
+        let vars = Vars::from_serialize(json!({"packages": ["curl"]})).unwrap();
+        let result = render_string("{{ packages | first(default='none') }}", vars).unwrap();
+        assert_eq!(result, "curl");
+    }
+
+    #[test]
+    fn test_first_empty_default() {
+        let vars = Vars::from_serialize(json!({"packages": []})).unwrap();
+        let result = render_string(
+            "{% set package = packages | first %}{{ package | default(value='none') }}",
+            vars,
+        )
+        .unwrap();
+        assert_eq!(result, "none");
+
+        let vars = Vars::from_serialize(json!({"packages": ["curl"]})).unwrap();
+        let result = render_string(
+            "{% set package = packages | first %}{{ package | default(value='none') }}",
+            vars,
+        )
+        .unwrap();
+        assert_eq!(result, "curl");
+    }
+}
@@ -0,0 +1,178 @@
+/// ANCHOR: filter
+/// ## from_csv
+///
+/// Parse a CSV string into a list of objects, keyed by the header row.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// delimiter:
+///   type: string
+///   description: Field delimiter, `TAB` is accepted for tabs. Defaults to `,`.
+/// skip_rows:
+///   type: integer
+///   description: Number of rows ignored at the beginning. Defaults to 0.
+/// fieldnames:
+///   type: list
+///   description: Field names to use as keys, for CSV data without header row.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     users: "name,uid\nfoo,1000\nboo,1001"
+///
+/// - assert:
+///     that:
+///       - "users | from_csv | length == 2"
+///       - "users | from_csv | last | get(key='uid') == '1001'"
+///       - "users | from_csv(skip_rows=1, fieldnames=['user', 'id']) | first | get(key='user') == 'foo'"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+
+use csv::{ReaderBuilder, StringRecord};
+use serde_json::{Map, Value};
+use tera::{try_get_value, Error, Result};
+
+fn get_delimiter(args: &HashMap<String, Value>) -> Result<u8> {
+    let delimiter = match args.get("delimiter") {
+        Some(v) => try_get_value!("from_csv", "delimiter", String, v),
+        None => return Ok(b','),
+    };
+    match delimiter.as_str() {
+        "TAB" | "\\t" | "\t" => Ok(b'\t'),
+        d if d.len() == 1 => Ok(d.as_bytes()[0]),
+        d => Err(Error::msg(format!(
+            "Filter `from_csv` delimiter `{}` must be a single character",
+            d
+        ))),
+    }
+}
+
+fn to_object(fieldnames: &[String], record: &StringRecord) -> Result<Value> {
+    if record.len() > fieldnames.len() {
+        return Err(Error::msg(format!(
+            "Filter `from_csv` found {} fields but there are only {} field names in row: {:?}",
+            record.len(),
+            fieldnames.len(),
+            record
+        )));
+    };
+    Ok(Value::Object(
+        fieldnames
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                (
+                    name.clone(),
+                    record.get(i).map_or(Value::Null, |s| json!(s)),
+                )
+            })
+            .collect::<Map<String, Value>>(),
+    ))
+}
+
+pub fn from_csv(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let s = try_get_value!("from_csv", "value", String, value);
+    let skip_rows = match args.get("skip_rows") {
+        Some(v) => try_get_value!("from_csv", "skip_rows", usize, v),
+        None => 0,
+    };
+    let fieldnames = match args.get("fieldnames") {
+        Some(v) => Some(try_get_value!("from_csv", "fieldnames", Vec<String>, v)),
+        None => None,
+    };
+
+    let mut reader = ReaderBuilder::new()
+        .delimiter(get_delimiter(args)?)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(s.as_bytes());
+    let mut records = reader.records().skip(skip_rows);
+
+    let fieldnames = match fieldnames {
+        Some(fieldnames) => fieldnames,
+        None => match records.next() {
+            Some(header) => header
+                .map_err(|e| Error::chain("Filter `from_csv` failed to parse header", e))?
+                .iter()
+                .map(String::from)
+                .collect(),
+            None => return Ok(Value::Array(Vec::new())),
+        },
+    };
+
+    records
+        .map(|record| {
+            let record = record.map_err(|e| Error::chain("Filter `from_csv` failed", e))?;
+            to_object(&fieldnames, &record)
+        })
+        .collect::<Result<Vec<Value>>>()
+        .map(Value::Array)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    const CSV: &str = "name,uid\nfoo,1000\nboo,1001\n";
+
+    #[test]
+    fn test_from_csv() {
+        let result = from_csv(&json!(CSV), &HashMap::new()).unwrap();
+        assert_eq!(
+            result,
+            json!([{"name": "foo", "uid": "1000"}, {"name": "boo", "uid": "1001"}])
+        );
+    }
+
+    #[test]
+    fn test_from_csv_no_header() {
+        let args = args!("fieldnames" => json!(["user", "id"]), "delimiter" => json!(";"));
+        let result = from_csv(&json!("foo;1000\nboo"), &args).unwrap();
+        assert_eq!(
+            result,
+            json!([{"user": "foo", "id": "1000"}, {"user": "boo", "id": null}])
+        );
+    }
+
+    #[test]
+    fn test_from_csv_skip_rows() {
+        let args = args!("skip_rows" => json!(1), "fieldnames" => json!(["user", "id"]));
+        let result = from_csv(&json!(CSV), &args).unwrap();
+        assert_eq!(
+            result,
+            json!([{"user": "foo", "id": "1000"}, {"user": "boo", "id": "1001"}])
+        );
+    }
+
+    #[test]
+    fn test_from_csv_empty() {
+        let result = from_csv(&json!(""), &HashMap::new()).unwrap();
+        assert_eq!(result, json!([]));
+    }
+
+    #[test]
+    fn test_from_csv_invalid() {
+        let _ = from_csv(&json!("a,b\n1,2,3"), &HashMap::new()).unwrap_err();
+        let args = args!("delimiter" => json!(";;"));
+        let _ = from_csv(&json!(CSV), &args).unwrap_err();
+    }
+
+    #[test]
+    fn test_from_csv_render() {
+        let mut vars = Vars::new();
+        vars.insert("users", CSV);
+        let result = render_string(
+            "{% for user in users | from_csv %}{{ user.name }}={{ user.uid }},{% endfor %}",
+            vars,
+        )
+        .unwrap();
+        assert_eq!(result, "foo=1000,boo=1001,");
+    }
+}
@@ -0,0 +1,97 @@
+/// ANCHOR: filter
+/// ## from_toml
+///
+/// Parse TOML text into an object. Datetimes are returned as strings.
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     config: "name = 'rash'"
+///
+/// - assert:
+///     that:
+///       - "config | from_toml | get(key='name') == 'rash'"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+use tera::{try_get_value, Error, Result};
+
+fn to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => json!(i),
+        toml::Value::Float(f) => json!(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(v) => Value::Array(v.into_iter().map(to_json).collect()),
+        toml::Value::Table(t) => Value::Object(
+            t.into_iter()
+                .map(|(k, v)| (k, to_json(v)))
+                .collect::<Map<String, Value>>(),
+        ),
+    }
+}
+
+pub fn from_toml(value: &Value, _: &HashMap<String, Value>) -> Result<Value> {
+    let s = try_get_value!("from_toml", "value", String, value);
+    s.parse::<toml::Value>()
+        .map(to_json)
+        .map_err(|e| Error::chain("Filter `from_toml` cannot parse value", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::filters::to_toml::to_toml;
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_from_toml() {
+        let toml = r#"
+        edition = 2018
+        date = 1979-05-27
+
+        [package]
+        name = "rash"
+        authors = ["foo"]
+        "#;
+        let result = from_toml(&json!(toml), &HashMap::new()).unwrap();
+        assert_eq!(
+            result,
+            json!({
+                "edition": 2018,
+                "date": "1979-05-27",
+                "package": {"name": "rash", "authors": ["foo"]},
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_toml_round_trip() {
+        let value = json!({"name": "rash", "package": {"version": "1.0.0", "ports": [80, 443]}});
+        let toml = to_toml(&value, &HashMap::new()).unwrap();
+        assert_eq!(from_toml(&toml, &HashMap::new()).unwrap(), value);
+    }
+
+    #[test]
+    fn test_from_toml_invalid() {
+        let _ = from_toml(&json!("foo ="), &HashMap::new()).unwrap_err();
+    }
+
+    #[test]
+    fn test_from_toml_render() {
+        let mut vars = Vars::new();
+        vars.insert("config", "[package]\nname = 'rash'");
+        let result = render_string(
+            "{% set config = config | from_toml %}{{ config.package.name }}",
+            vars,
+        )
+        .unwrap();
+        assert_eq!(result, "rash");
+    }
+}
@@ -0,0 +1,134 @@
+/// ANCHOR: filter
+/// ## groupby
+///
+/// Group a list of objects by an attribute, returning a dict from each attribute value to the
+/// list of objects with it. Non string values are used as strings, e.g. `80` as `"80"`.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// attribute:
+///   type: string
+///   required: true
+///   description: Dotted path of the object field to group by.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     servers: "name,env\nweb1,prod\nweb2,dev\nweb3,prod"
+///
+/// - assert:
+///     that:
+///       - "servers | from_csv | groupby(attribute='env') | get(key='prod') | length == 2"
+///       - "servers | from_csv | groupby(attribute='env') | get(key='dev') | first | get(key='name') == 'web2'"
+/// ```
+/// ANCHOR_END: filter
+use crate::utils::tera::filters::get_attribute;
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+use tera::{try_get_value, Error, Result};
+
+pub fn groupby(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let list = try_get_value!("groupby", "value", Vec<Value>, value);
+    let attribute = match args.get("attribute") {
+        Some(v) => try_get_value!("groupby", "attribute", String, v),
+        None => {
+            return Err(Error::msg(
+                "Filter `groupby` expected an arg called `attribute`",
+            ))
+        }
+    };
+
+    let mut groups = Map::new();
+    for element in list {
+        let key = match get_attribute(&element, &attribute) {
+            Some(Value::String(s)) => s.clone(),
+            Some(v) => v.to_string(),
+            None => {
+                return Err(Error::msg(format!(
+                    "attribute '{}' not found in {}",
+                    attribute, element
+                )))
+            }
+        };
+        match groups
+            .entry(key)
+            .or_insert_with(|| Value::Array(Vec::new()))
+        {
+            Value::Array(group) => group.push(element),
+            // every group is created as an array
+            _ => unreachable!(),
+        };
+    }
+    Ok(Value::Object(groups))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    fn get_args(attribute: &str) -> HashMap<String, Value> {
+        args!("attribute" => json!(attribute))
+    }
+
+    fn get_servers() -> Value {
+        json!([
+            {"name": "web1", "env": "prod", "port": 80},
+            {"name": "web2", "env": "dev", "port": 8080},
+            {"name": "web3", "env": "prod", "port": 80},
+        ])
+    }
+
+    #[test]
+    fn test_groupby() {
+        let result = groupby(&get_servers(), &get_args("env")).unwrap();
+        assert_eq!(
+            result,
+            json!({
+                "prod": [
+                    {"name": "web1", "env": "prod", "port": 80},
+                    {"name": "web3", "env": "prod", "port": 80},
+                ],
+                "dev": [{"name": "web2", "env": "dev", "port": 8080}],
+            })
+        );
+    }
+
+    #[test]
+    fn test_groupby_number() {
+        let result = groupby(&get_servers(), &get_args("port")).unwrap();
+        assert_eq!(result["80"].as_array().unwrap().len(), 2);
+        assert_eq!(result["8080"][0]["name"], json!("web2"));
+    }
+
+    #[test]
+    fn test_groupby_nested_attribute() {
+        let value = json!([{"meta": {"env": "dev"}}, {"meta": {"env": "dev"}}]);
+        let result = groupby(&value, &get_args("meta.env")).unwrap();
+        assert_eq!(result["dev"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_groupby_invalid() {
+        let _ = groupby(&get_servers(), &get_args("boo")).unwrap_err();
+        let _ = groupby(&get_servers(), &HashMap::new()).unwrap_err();
+    }
+
+    #[test]
+    fn test_groupby_render() {
+        let vars = Vars::from_serialize(json!({"servers": get_servers()})).unwrap();
+        let result = render_string(
+            "{% for env, servers in servers | groupby(attribute='env') %}{{ env }}={{ servers | map(attribute='name') | join(sep=',') }};{% endfor %}",
+            vars,
+        )
+        .unwrap();
+        assert_eq!(result, "dev=web2;prod=web1,web3;");
+    }
+}
@@ -0,0 +1,155 @@
+/// ANCHOR: filter
+/// ## indent
+///
+/// Indent each line of a string, as Ansible (Jinja2) `indent` filter does. By default, the
+/// first line and blank lines, including those with only whitespace, are not indented.
+/// It replaces Tera builtin `indent`, whose `prefix` param is still accepted.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// width:
+///   type: integer or string
+///   description: |
+///     Number of spaces to indent with, or string used as indentation.
+///     Defaults to 4.
+/// prefix:
+///   type: string
+///   description: String used as indentation, like Tera builtin. It cannot be used with `width`.
+/// first:
+///   type: bool
+///   description: Indent also the first line. Defaults to false.
+/// blank:
+///   type: bool
+///   description: Indent also blank lines. Defaults to false.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     script: |
+///       set -e
+///       echo done
+///
+/// - assert:
+///     that:
+///       - "script | indent(width=2) == 'set -e\n  echo done\n'"
+///       - "script | indent(width=2, first=true) == '  set -e\n  echo done\n'"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+pub fn indent(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let s = try_get_value!("indent", "value", String, value);
+    let indentation = match (args.get("width"), args.get("prefix")) {
+        (Some(_), Some(_)) => {
+            return Err(Error::msg(
+                "Filter `indent` accepts `width` or `prefix` args, but not both",
+            ))
+        }
+        (Some(Value::String(prefix)), None) => prefix.clone(),
+        (Some(v), None) => " ".repeat(try_get_value!("indent", "width", usize, v)),
+        (None, Some(v)) => try_get_value!("indent", "prefix", String, v),
+        (None, None) => " ".repeat(4),
+    };
+    let first = match args.get("first") {
+        Some(v) => try_get_value!("indent", "first", bool, v),
+        None => false,
+    };
+    let blank = match args.get("blank") {
+        Some(v) => try_get_value!("indent", "blank", bool, v),
+        None => false,
+    };
+
+    // same as Jinja2: add a newline to keep trailing line breaks after splitting lines
+    let s_with_newline = format!("{}\n", s);
+    let mut lines = s_with_newline.lines();
+    let first_line = lines
+        .next()
+        .ok_or_else(|| Error::msg("indent value has no lines"))?;
+    let indented = lines.fold(first_line.to_string(), |mut acc, line| {
+        acc.push('\n');
+        if blank || !line.trim_start().is_empty() {
+            acc.push_str(&indentation);
+        }
+        acc.push_str(line);
+        acc
+    });
+
+    if first {
+        Ok(Value::String(format!("{}{}", indentation, indented)))
+    } else {
+        Ok(Value::String(indented))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_indent() {
+        let result = indent(&json!("foo\nboo\n\nzoo"), &HashMap::new()).unwrap();
+        assert_eq!(result, json!("foo\n    boo\n\n    zoo"));
+    }
+
+    #[test]
+    fn test_indent_first() {
+        let args = args!("width" => json!(2), "first" => json!(true));
+        let result = indent(&json!("foo\nboo\n"), &args).unwrap();
+        assert_eq!(result, json!("  foo\n  boo\n"));
+    }
+
+    #[test]
+    fn test_indent_blank() {
+        let args = args!("width" => json!(2), "blank" => json!(true));
+        let result = indent(&json!("foo\n\nboo"), &args).unwrap();
+        assert_eq!(result, json!("foo\n  \n  boo"));
+    }
+
+    #[test]
+    fn test_indent_width_string() {
+        let args = args!("width" => json!("> "));
+        let result = indent(&json!("foo\nboo"), &args).unwrap();
+        assert_eq!(result, json!("foo\n> boo"));
+    }
+
+    #[test]
+    fn test_indent_whitespace_line() {
+        let result = indent(&json!("foo\n  \nboo"), &args!("width" => json!(2))).unwrap();
+        assert_eq!(result, json!("foo\n  \n  boo"));
+    }
+
+    #[test]
+    fn test_indent_prefix() {
+        let args = args!("prefix" => json!("  "), "first" => json!(true));
+        let result = indent(&json!("foo\nboo"), &args).unwrap();
+        assert_eq!(result, json!("  foo\n  boo"));
+
+        let args = args!("prefix" => json!("  "), "width" => json!(2));
+        let _ = indent(&json!("foo\nboo"), &args).unwrap_err();
+    }
+
+    #[test]
+    fn test_indent_prefix_render() {
+        let mut vars = Vars::new();
+        vars.insert("script", "set -e\necho done");
+        let result = render_string("run:\n  {{ script | indent(prefix='  ') }}", vars).unwrap();
+        assert_eq!(result, "run:\n  set -e\n  echo done");
+    }
+
+    #[test]
+    fn test_indent_render() {
+        let mut vars = Vars::new();
+        vars.insert("script", "set -e\necho done\n");
+        let result = render_string("run:\n  {{ script | indent(width=2) }}", vars).unwrap();
+        assert_eq!(result, "run:\n  set -e\n  echo done\n");
+    }
+}
@@ -0,0 +1,138 @@
+/// ANCHOR: filter
+/// ## join
+///
+/// Concatenate list elements with a separator. Extends Tera builtin `join` so lists of objects
+/// can be joined by one of their fields.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// sep:
+///   type: string
+///   description: Separator placed between elements. Defaults to empty string.
+/// attribute:
+///   type: string
+///   description: Dotted path of the object field to join.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     users:
+///       - name: alice
+///         uid: 1000
+///       - name: bob
+///         uid: 1001
+///
+/// - assert:
+///     that:
+///       - "users | join(sep=', ', attribute='name') == 'alice, bob'"
+///       - "users | map(attribute='uid') | join(sep=',') == '1000,1001'"
+/// ```
+/// ANCHOR_END: filter
+use crate::utils::tera::filters::get_attribute;
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+fn to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        v => v.to_string(),
+    }
+}
+
+pub fn join(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let list = try_get_value!("join", "value", Vec<Value>, value);
+    let sep = match args.get("sep") {
+        Some(v) => try_get_value!("join", "sep", String, v),
+        None => String::new(),
+    };
+
+    let elements = match args.get("attribute") {
+        Some(v) => {
+            let attribute = try_get_value!("join", "attribute", String, v);
+            list.iter()
+                .map(|element| {
+                    get_attribute(element, &attribute)
+                        .map(to_string)
+                        .ok_or_else(|| {
+                            Error::msg(format!(
+                                "attribute '{}' not found in {}",
+                                attribute, element
+                            ))
+                        })
+                })
+                .collect::<Result<Vec<String>>>()?
+        }
+        None => list.iter().map(to_string).collect(),
+    };
+    Ok(Value::String(elements.join(&sep)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::modules::MODULES;
+    use crate::utils::get_yaml;
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_join() {
+        let args = args!("sep" => json!(", "));
+        let result = join(&json!(["a", 1, true]), &args).unwrap();
+        assert_eq!(result, json!("a, 1, true"));
+    }
+
+    #[test]
+    fn test_join_attribute() {
+        let args = args!("sep" => json!(","), "attribute" => json!("user.name"));
+        let result = join(
+            &json!([{"user": {"name": "foo"}}, {"user": {"name": "boo"}}]),
+            &args,
+        )
+        .unwrap();
+        assert_eq!(result, json!("foo,boo"));
+    }
+
+    #[test]
+    fn test_join_attribute_not_found() {
+        let args = args!("attribute" => json!("name"));
+        let _ = join(&json!([{"id": 1}]), &args).unwrap_err();
+    }
+
+    #[test]
+    fn test_join_set_vars_objects() {
+        let params = get_yaml(
+            r#"
+        users:
+          - name: foo
+            uid: 1000
+          - name: boo
+            uid: 1001
+        "#,
+        )
+        .unwrap();
+        let (_, vars) = MODULES
+            .get("set_vars")
+            .unwrap()
+            .exec(params, Vars::new())
+            .unwrap();
+
+        let result = render_string(
+            "{{ users | map(attribute='name') | join(sep=', ') }}",
+            vars.clone(),
+        )
+        .unwrap();
+        assert_eq!(result, "foo, boo");
+
+        let result = render_string("{{ users | join(sep=', ', attribute='uid') }}", vars).unwrap();
+        assert_eq!(result, "1000, 1001");
+    }
+}
@@ -0,0 +1,73 @@
+/// ANCHOR: filter
+/// ## last
+///
+/// Return the last element of a list or the last character of a string. Overrides Tera
+/// builtin `last` so empty inputs return `default` arg, or `null` if it is not set, instead
+/// of an empty string, like `first` filter.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// default:
+///   type: any
+///   description: Value returned when input is empty. Defaults to null.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     packages:
+///       - curl
+///       - git
+///     empty: []
+///
+/// - assert:
+///     that:
+///       - "packages | last == 'git'"
+///       - "'rash' | last == 'h'"
+///       - "empty | last(default='none') == 'none'"
+/// ```
+/// ANCHOR_END: filter
+use crate::utils::tera::filters::first::get_edge;
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::Result;
+
+pub fn last(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    get_edge("last", value, args, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_last() {
+        let result = last(&json!([1, 2, 3]), &HashMap::new()).unwrap();
+        assert_eq!(result, json!(3));
+        let result = last(&json!("foo"), &HashMap::new()).unwrap();
+        assert_eq!(result, json!("o"));
+    }
+
+    #[test]
+    fn test_last_empty_default() {
+        assert_eq!(last(&json!([]), &HashMap::new()).unwrap(), json!(null));
+        let vars = Vars::from_serialize(json!({"packages": []})).unwrap();
+        let result = render_string(
+            "{% set package = packages | last %}{{ package | default(value='none') }}",
+            vars,
+        )
+        .unwrap();
+        assert_eq!(result, "none");
+
+        let vars = Vars::from_serialize(json!({"packages": []})).unwrap();
+        let result = render_string("{{ packages | last(default='none') }}", vars).unwrap();
+        assert_eq!(result, "none");
+    }
+}
@@ -0,0 +1,46 @@
+/// ANCHOR: filter
+/// ## lstrip
+///
+/// Remove leading characters from a string, as Python `lstrip`.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// chars:
+///   type: string
+///   description: Set of characters to remove. Defaults to whitespace.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "'  foo  ' | lstrip == 'foo  '"
+///       - "'0012' | lstrip(chars='0') == '12'"
+/// ```
+/// ANCHOR_END: filter
+use crate::utils::tera::filters::trim::{strip, Side};
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::Result;
+
+pub fn lstrip(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    strip("lstrip", value, args, Side::Start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lstrip() {
+        let result = lstrip(&json!("  foo  "), &HashMap::new()).unwrap();
+        assert_eq!(result, json!("foo  "));
+        let args = args!("chars" => json!("#/"));
+        let result = lstrip(&json!("#//foo/#"), &args).unwrap();
+        assert_eq!(result, json!("foo/#"));
+    }
+}
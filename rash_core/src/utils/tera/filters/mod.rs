@@ -0,0 +1,168 @@
+/// Build a filter or lookup args map from `key => value` pairs, in tests.
+#[cfg(test)]
+macro_rules! args {
+    ($($key:expr => $value:expr),* $(,)?) => {
+        IntoIterator::into_iter([$(($key.to_string(), $value)),*])
+            .collect::<std::collections::HashMap<String, serde_json::Value>>()
+    };
+}
+
+mod all;
+mod any;
+mod batch;
+mod bool;
+mod checksum;
+mod comment;
+mod expanduser;
+mod extract;
+mod first;
+mod from_csv;
+mod from_toml;
+mod groupby;
+mod indent;
+mod join;
+mod last;
+mod lstrip;
+mod path_join;
+mod realpath;
+mod regex_escape;
+mod regex_replace;
+mod regex_search;
+mod rejectattr;
+mod rstrip;
+mod selectattr;
+mod slice;
+mod split;
+mod to_nice_yaml;
+mod to_toml;
+mod trim;
+mod type_debug;
+mod unique;
+mod urlsplit;
+mod version_compare;
+
+use std::collections::HashMap;
+
+use regex::{Regex, RegexBuilder};
+use serde_json::Value;
+use tera::{try_get_value, Error, Result, Tera};
+
+/// Register all `rash` filters in [`Tera`], overwriting builtin ones with the same name.
+///
+/// [`Tera`]: ../../../../tera/struct.Tera.html
+pub fn add_filters(tera: &mut Tera) {
+    tera.register_filter("all", all::all);
+    tera.register_filter("any", any::any);
+    tera.register_filter("batch", batch::batch);
+    tera.register_filter("bool", bool::bool);
+    tera.register_filter("checksum", checksum::checksum);
+    tera.register_filter("comment", comment::comment);
+    tera.register_filter("expanduser", expanduser::expanduser);
+    tera.register_filter("extract", extract::extract);
+    tera.register_filter("first", first::first);
+    tera.register_filter("from_csv", from_csv::from_csv);
+    tera.register_filter("from_toml", from_toml::from_toml);
+    tera.register_filter("groupby", groupby::groupby);
+    tera.register_filter("indent", indent::indent);
+    tera.register_filter("join", join::join);
+    tera.register_filter("last", last::last);
+    tera.register_filter("lstrip", lstrip::lstrip);
+    tera.register_filter("path_join", path_join::path_join);
+    tera.register_filter("realpath", realpath::realpath);
+    tera.register_filter("regex_escape", regex_escape::regex_escape);
+    tera.register_filter("regex_replace", regex_replace::regex_replace);
+    tera.register_filter("regex_search", regex_search::regex_search);
+    tera.register_filter("rejectattr", rejectattr::rejectattr);
+    tera.register_filter("rstrip", rstrip::rstrip);
+    tera.register_filter("selectattr", selectattr::selectattr);
+    tera.register_filter("slice", slice::slice);
+    tera.register_filter("split", split::split);
+    tera.register_filter("to_nice_yaml", to_nice_yaml::to_nice_yaml);
+    tera.register_filter("to_toml", to_toml::to_toml);
+    tera.register_filter("trim", trim::trim);
+    tera.register_filter("type_debug", type_debug::type_debug);
+    tera.register_filter("unique", unique::unique);
+    tera.register_filter("urlsplit", urlsplit::urlsplit);
+    tera.register_filter("version_compare", version_compare::version_compare);
+}
+
+/// Get value following a dotted `attribute` path, e.g. `user.name` or `ports.0`.
+fn get_attribute<'a>(value: &'a Value, attribute: &str) -> Option<&'a Value> {
+    attribute
+        .split('.')
+        .try_fold(value, |current, key| match current {
+            Value::Object(map) => map.get(key),
+            Value::Array(list) => key.parse::<usize>().ok().and_then(|i| list.get(i)),
+            _ => None,
+        })
+}
+
+/// Return if `value` is considered true in a condition, following Tera rules.
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(list) => !list.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+/// Get bool `arg` of `filter` from `args`, false if it is not set.
+fn get_flag(filter: &str, arg: &str, args: &HashMap<String, Value>) -> Result<bool> {
+    match args.get(arg) {
+        Some(v) => Ok(try_get_value!(filter, arg, bool, v)),
+        None => Ok(false),
+    }
+}
+
+/// Build regex from `pattern` arg of `filter`, applying `ignorecase`, `multiline` and `dotall`
+/// flags from `args`.
+fn get_regex(filter: &str, args: &HashMap<String, Value>) -> Result<Regex> {
+    let pattern = match args.get("pattern") {
+        Some(v) => try_get_value!(filter, "pattern", String, v),
+        None => {
+            return Err(Error::msg(format!(
+                "Filter `{}` expected an arg called `pattern`",
+                filter
+            )))
+        }
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(get_flag(filter, "ignorecase", args)?)
+        .multi_line(get_flag(filter, "multiline", args)?)
+        .dot_matches_new_line(get_flag(filter, "dotall", args)?)
+        .build()
+        .map_err(|e| {
+            Error::chain(
+                format!("Filter `{}` invalid pattern '{}'", filter, pattern),
+                e,
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_attribute() {
+        let value = json!({"user": {"name": "foo", "ports": [80, 443]}});
+        assert_eq!(get_attribute(&value, "user.name"), Some(&json!("foo")));
+        assert_eq!(get_attribute(&value, "user.ports.1"), Some(&json!(443)));
+        assert_eq!(get_attribute(&value, "user.boo"), None);
+    }
+
+    #[test]
+    fn test_get_regex() {
+        let args = args!("pattern" => json!("^b.o"), "multiline" => json!(true));
+        let regex = get_regex("test", &args).unwrap();
+        assert!(regex.is_match("foo\nboo"));
+        assert!(!regex.is_match("foo\nBoo"));
+
+        let _ = get_regex("test", &HashMap::new()).unwrap_err();
+        let args = args!("pattern" => json!("(foo"));
+        let _ = get_regex("test", &args).unwrap_err();
+    }
+}
@@ -0,0 +1,74 @@
+/// ANCHOR: filter
+/// ## path_join
+///
+/// Join a list of path components. An absolute component discards the previous ones, so
+/// `['/etc', '/opt', 'app']` is joined as `/opt/app`. A string is returned unchanged.
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     conf_path: "{{ ['/etc', 'app', 'app.conf'] | path_join }}"
+///     app_path: "{{ ['/etc', '/opt', 'app'] | path_join }}"
+///
+/// - assert:
+///     that:
+///       - "conf_path == '/etc/app/app.conf'"
+///       - "app_path == '/opt/app'"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde_json::Value;
+use tera::{try_get_value, Result};
+
+pub fn path_join(value: &Value, _: &HashMap<String, Value>) -> Result<Value> {
+    if let Value::String(s) = value {
+        return Ok(Value::String(s.clone()));
+    };
+    let components = try_get_value!("path_join", "value", Vec<String>, value);
+    let path = components.iter().collect::<PathBuf>();
+    Ok(Value::String(path.to_string_lossy().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_path_join() {
+        let result = path_join(&json!(["/etc", "app", "app.conf"]), &HashMap::new()).unwrap();
+        assert_eq!(result, json!("/etc/app/app.conf"));
+        let result = path_join(&json!(["etc/", "app"]), &HashMap::new()).unwrap();
+        assert_eq!(result, json!("etc/app"));
+    }
+
+    #[test]
+    fn test_path_join_absolute_reset() {
+        let result = path_join(&json!(["/etc", "app", "/opt", "app"]), &HashMap::new()).unwrap();
+        assert_eq!(result, json!("/opt/app"));
+    }
+
+    #[test]
+    fn test_path_join_string() {
+        let result = path_join(&json!("/etc/app"), &HashMap::new()).unwrap();
+        assert_eq!(result, json!("/etc/app"));
+    }
+
+    #[test]
+    fn test_path_join_invalid() {
+        let _ = path_join(&json!([1, {"a": 1}]), &HashMap::new()).unwrap_err();
+    }
+
+    #[test]
+    fn test_path_join_render() {
+        let mut vars = Vars::new();
+        vars.insert("dir", "/etc/app");
+        let result = render_string("{{ [dir, 'conf.d', 'x.conf'] | path_join }}", vars).unwrap();
+        assert_eq!(result, "/etc/app/conf.d/x.conf");
+    }
+}
@@ -0,0 +1,90 @@
+/// ANCHOR: filter
+/// ## realpath
+///
+/// Return the canonical absolute path, resolving symlinks and `..` components.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// strict:
+///   type: bool
+///   description: |
+///     Fail if path does not exist. If false, nonexistent paths are returned unchanged.
+///     Defaults to true.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "'/etc/../etc/hostname' | realpath == '/etc/hostname'"
+///       - "'/this/does/not/exist' | realpath(strict=false) == '/this/does/not/exist'"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+use std::fs::canonicalize;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+pub fn realpath(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let path = try_get_value!("realpath", "value", String, value);
+    let strict = match args.get("strict") {
+        Some(v) => try_get_value!("realpath", "strict", bool, v),
+        None => true,
+    };
+
+    match canonicalize(&path) {
+        Ok(real_path) => Ok(Value::String(real_path.to_string_lossy().to_string())),
+        Err(_) if !strict => Ok(Value::String(path)),
+        Err(e) => Err(Error::msg(format!("realpath `{}` failed: {}", path, e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    use std::fs::{create_dir, File};
+    use std::os::unix::fs::symlink;
+
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_realpath_symlink() {
+        let dir = tempdir().unwrap();
+        let real_dir = canonicalize(dir.path()).unwrap();
+        create_dir(real_dir.join("target")).unwrap();
+        File::create(real_dir.join("target/file.txt")).unwrap();
+        symlink(real_dir.join("target"), real_dir.join("link")).unwrap();
+
+        let path = dir.path().join("link/../link/file.txt");
+        let result = realpath(&json!(path.to_str().unwrap()), &HashMap::new()).unwrap();
+        assert_eq!(
+            result,
+            json!(real_dir.join("target/file.txt").to_str().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_realpath_not_found() {
+        let _ = realpath(&json!("/this/does/not/exist"), &HashMap::new()).unwrap_err();
+    }
+
+    #[test]
+    fn test_realpath_not_strict() {
+        let args = args!("strict" => json!(false));
+        let result = realpath(&json!("/this/does/not/exist"), &args).unwrap();
+        assert_eq!(result, json!("/this/does/not/exist"));
+    }
+
+    #[test]
+    fn test_realpath_render() {
+        let result = render_string("{{ '/etc/../etc' | realpath }}", Vars::new()).unwrap();
+        assert_eq!(result, "/etc");
+    }
+}
@@ -0,0 +1,100 @@
+/// ANCHOR: filter
+/// ## regex_escape
+///
+/// Escape regular expression special characters in a string, so it can be safely embedded in a
+/// pattern.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// re_type:
+///   type: string
+///   description: |
+///     Escaping rules to apply: `python` or `posix_basic`.
+///     Defaults to `python`.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "'file.txt' | regex_escape == 'file\\.txt'"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+/// Same characters escaped by Python `re.escape`.
+const PYTHON_SPECIAL_CHARS: &str = "()[]{}?*+-|^$\\.&~# \t\n\r\x0b\x0c";
+/// Special characters of POSIX basic regular expressions.
+const POSIX_BASIC_SPECIAL_CHARS: &str = "].[^$*\\";
+
+fn escape(s: &str, special_chars: &str) -> String {
+    s.chars()
+        .fold(String::with_capacity(s.len()), |mut acc, c| {
+            if special_chars.contains(c) {
+                acc.push('\\');
+            }
+            acc.push(c);
+            acc
+        })
+}
+
+pub fn regex_escape(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let s = try_get_value!("regex_escape", "value", String, value);
+    let re_type = match args.get("re_type") {
+        Some(v) => try_get_value!("regex_escape", "re_type", String, v),
+        None => "python".to_string(),
+    };
+
+    match re_type.as_str() {
+        "python" => Ok(Value::String(escape(&s, PYTHON_SPECIAL_CHARS))),
+        "posix_basic" => Ok(Value::String(escape(&s, POSIX_BASIC_SPECIAL_CHARS))),
+        _ => Err(Error::msg(format!(
+            "regex_escape re_type '{}' not supported",
+            re_type
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_regex_escape() {
+        let result = regex_escape(&json!("a.b*c(d)[e]"), &HashMap::new()).unwrap();
+        assert_eq!(result, json!(r"a\.b\*c\(d\)\[e\]"));
+    }
+
+    #[test]
+    fn test_regex_escape_no_special_chars() {
+        let result = regex_escape(&json!("abc_123"), &HashMap::new()).unwrap();
+        assert_eq!(result, json!("abc_123"));
+    }
+
+    #[test]
+    fn test_regex_escape_posix_basic() {
+        let args = args!("re_type" => json!("posix_basic"));
+        let result = regex_escape(&json!("^a.b*(c)[d]$"), &args).unwrap();
+        assert_eq!(result, json!(r"\^a\.b\*(c)\[d\]\$"));
+    }
+
+    #[test]
+    fn test_regex_escape_invalid_re_type() {
+        let args = args!("re_type" => json!("posix_extended"));
+        let _ = regex_escape(&json!("a.b"), &args).unwrap_err();
+    }
+
+    #[test]
+    fn test_regex_escape_render() {
+        let result = render_string("{{ '1.0.0' | regex_escape }}", Vars::new()).unwrap();
+        assert_eq!(result, r"1\.0\.0");
+    }
+}
@@ -0,0 +1,189 @@
+/// ANCHOR: filter
+/// ## regex_replace
+///
+/// Replace every match of a regular expression in a string. Backreferences in `replacement`
+/// use Python syntax, as in Ansible: `\1` for numbered groups and `\g` followed by the name
+/// between angle brackets for named ones.
+/// When the value is a list, each element is replaced, the same as Ansible
+/// `map('regex_replace', ...)`.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// pattern:
+///   type: string
+///   required: true
+///   description: Regular expression to search.
+/// replacement:
+///   type: string
+///   description: Text replacing each match. Defaults to empty string.
+/// ignorecase:
+///   type: bool
+///   description: Match case insensitively. Defaults to false.
+/// multiline:
+///   type: bool
+///   description: Make line start and end anchors match at every line. Defaults to false.
+/// dotall:
+///   type: bool
+///   description: Make `.` match newlines too. Defaults to false.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     hosts: "web1.example.com"
+///     config: "# begin\nfoo\n# end\nboo"
+///     packages:
+///       - python3-foo
+///       - python3-boo
+///
+/// - assert:
+///     that:
+///       - "hosts | regex_replace(pattern='(\\w+)\\..*', replacement='\\1') == 'web1'"
+///       - "config | regex_replace(pattern='# begin.*# end\\n', dotall=true) == 'boo'"
+///       - "packages | regex_replace(pattern='python3-', replacement='') | join(sep=',') == 'foo,boo'"
+/// ```
+/// ANCHOR_END: filter
+use crate::utils::tera::filters::get_regex;
+use crate::utils::to_regex_replacement;
+
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde_json::Value;
+use tera::{try_get_value, Result};
+
+fn replace(value: &Value, regex: &Regex, replacement: &str) -> Result<Value> {
+    let s = try_get_value!("regex_replace", "value", String, value);
+    Ok(Value::String(
+        regex.replace_all(&s, replacement).to_string(),
+    ))
+}
+
+pub fn regex_replace(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let regex = get_regex("regex_replace", args)?;
+    let replacement = match args.get("replacement") {
+        Some(v) => to_regex_replacement(&try_get_value!("regex_replace", "replacement", String, v)),
+        None => String::new(),
+    };
+    match value {
+        Value::Array(list) => list
+            .iter()
+            .map(|v| replace(v, &regex, &replacement))
+            .collect::<Result<Vec<Value>>>()
+            .map(Value::Array),
+        _ => replace(value, &regex, &replacement),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    fn get_args(
+        pattern: &str,
+        replacement: &str,
+        extra: Vec<(&str, bool)>,
+    ) -> HashMap<String, Value> {
+        vec![
+            ("pattern".to_string(), json!(pattern)),
+            ("replacement".to_string(), json!(replacement)),
+        ]
+        .into_iter()
+        .chain(extra.into_iter().map(|(k, v)| (k.to_string(), json!(v))))
+        .collect()
+    }
+
+    #[test]
+    fn test_regex_replace() {
+        let result = regex_replace(
+            &json!("web1.example.com"),
+            &get_args(r"^(?P<host>\w+)\.(.*)", r"\2:\g<host>", vec![]),
+        )
+        .unwrap();
+        assert_eq!(result, json!("example.com:web1"));
+
+        let result = regex_replace(&json!("a1b22"), &get_args(r"\d", "$", vec![])).unwrap();
+        assert_eq!(result, json!("a$b$$"));
+    }
+
+    #[test]
+    fn test_regex_replace_dotall() {
+        let value = json!("keep\n# begin\nfoo\nboo\n# end\nlast");
+        let args = |dotall| get_args(r"# begin.*# end\n", "", vec![("dotall", dotall)]);
+        assert_eq!(regex_replace(&value, &args(false)).unwrap(), value);
+        assert_eq!(
+            regex_replace(&value, &args(true)).unwrap(),
+            json!("keep\nlast")
+        );
+    }
+
+    #[test]
+    fn test_regex_replace_multiline() {
+        let value = json!("foo=1\n#boo=2\nzoo=3");
+        let args = |multiline| get_args(r"^(\w+)=", r"\1: ", vec![("multiline", multiline)]);
+        assert_eq!(
+            regex_replace(&value, &args(false)).unwrap(),
+            json!("foo: 1\n#boo=2\nzoo=3")
+        );
+        assert_eq!(
+            regex_replace(&value, &args(true)).unwrap(),
+            json!("foo: 1\n#boo=2\nzoo: 3")
+        );
+    }
+
+    #[test]
+    fn test_regex_replace_ignorecase() {
+        let result = regex_replace(
+            &json!("Foo foo"),
+            &get_args("foo", "boo", vec![("ignorecase", true)]),
+        )
+        .unwrap();
+        assert_eq!(result, json!("boo boo"));
+    }
+
+    #[test]
+    fn test_regex_replace_invalid() {
+        let _ = regex_replace(&json!("foo"), &get_args("(foo", "", vec![])).unwrap_err();
+        let _ = regex_replace(&json!("foo"), &HashMap::new()).unwrap_err();
+        let _ = regex_replace(&json!(1), &get_args("1", "", vec![])).unwrap_err();
+    }
+
+    #[test]
+    fn test_regex_replace_list() {
+        let result = regex_replace(
+            &json!(["prefix_foo", "prefix_boo", "zoo"]),
+            &get_args("^prefix_", "", vec![]),
+        )
+        .unwrap();
+        assert_eq!(result, json!(["foo", "boo", "zoo"]));
+        let _ = regex_replace(&json!(["foo", 1]), &get_args("foo", "", vec![])).unwrap_err();
+    }
+
+    #[test]
+    fn test_regex_replace_list_render() {
+        let vars = Vars::from_serialize(json!({"names": ["prefix_foo", "prefix_boo"]})).unwrap();
+        let result = render_string(
+            "{{ names | regex_replace(pattern='^prefix_', replacement='') | join(sep=',') }}",
+            vars,
+        )
+        .unwrap();
+        assert_eq!(result, "foo,boo");
+    }
+
+    #[test]
+    fn test_regex_replace_render() {
+        let mut vars = Vars::new();
+        vars.insert("version", "v1.2.3");
+        let result = render_string(
+            r#"{{ version | regex_replace(pattern='^v(\d+)\..*', replacement='\1') }}"#,
+            vars,
+        )
+        .unwrap();
+        assert_eq!(result, "1");
+    }
+}
@@ -0,0 +1,100 @@
+/// ANCHOR: filter
+/// ## regex_search
+///
+/// Return the first match of a regular expression in a string, or `null` if there is no match.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// pattern:
+///   type: string
+///   required: true
+///   description: Regular expression to search.
+/// ignorecase:
+///   type: bool
+///   description: Match case insensitively. Defaults to false.
+/// multiline:
+///   type: bool
+///   description: Make line start and end anchors match at every line. Defaults to false.
+/// dotall:
+///   type: bool
+///   description: Make `.` match newlines too. Defaults to false.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     config: "name=rash\nversion=1.2.3"
+///
+/// - assert:
+///     that:
+///       - "config | regex_search(pattern='version=.*') == 'version=1.2.3'"
+///       - "config | regex_search(pattern='NAME', ignorecase=true) == 'name'"
+/// ```
+/// ANCHOR_END: filter
+use crate::utils::tera::filters::get_regex;
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Result};
+
+pub fn regex_search(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let s = try_get_value!("regex_search", "value", String, value);
+    let regex = get_regex("regex_search", args)?;
+    Ok(regex
+        .find(&s)
+        .map_or(Value::Null, |m| Value::String(m.as_str().to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    fn get_args(pattern: &str, extra: Vec<(&str, bool)>) -> HashMap<String, Value> {
+        vec![("pattern".to_string(), json!(pattern))]
+            .into_iter()
+            .chain(extra.into_iter().map(|(k, v)| (k.to_string(), json!(v))))
+            .collect()
+    }
+
+    #[test]
+    fn test_regex_search() {
+        let result = regex_search(&json!("foo=1 boo=2"), &get_args(r"boo=\d", vec![])).unwrap();
+        assert_eq!(result, json!("boo=2"));
+        let result = regex_search(&json!("foo=1"), &get_args("zoo", vec![])).unwrap();
+        assert_eq!(result, json!(null));
+    }
+
+    #[test]
+    fn test_regex_search_multiline() {
+        let value = json!("foo=1\nboo=2");
+        let args = |multiline| get_args(r"^boo=\d$", vec![("multiline", multiline)]);
+        assert_eq!(regex_search(&value, &args(false)).unwrap(), json!(null));
+        assert_eq!(regex_search(&value, &args(true)).unwrap(), json!("boo=2"));
+    }
+
+    #[test]
+    fn test_regex_search_dotall() {
+        let value = json!("<a>\nfoo\n</a>");
+        let args = |dotall| get_args("<a>.*</a>", vec![("dotall", dotall)]);
+        assert_eq!(regex_search(&value, &args(false)).unwrap(), json!(null));
+        assert_eq!(regex_search(&value, &args(true)).unwrap(), value);
+    }
+
+    #[test]
+    fn test_regex_search_render() {
+        let mut vars = Vars::new();
+        vars.insert("config", "name=rash\nversion=1.2.3");
+        let result = render_string(
+            r#"{% if config | regex_search(pattern='^version=', multiline=true) %}found{% endif %}"#,
+            vars,
+        )
+        .unwrap();
+        assert_eq!(result, "found");
+    }
+}
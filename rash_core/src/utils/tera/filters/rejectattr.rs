@@ -0,0 +1,49 @@
+/// ANCHOR: filter
+/// ## rejectattr
+///
+/// Filter a list of objects, removing those whose attribute passes a test, like Ansible
+/// `rejectattr`. It accepts the same parameters as `selectattr`.
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     passwd: |
+///       alice:x:1000:1000::/home/alice:/bin/bash
+///       daemon:x:1:1::/usr/sbin:/usr/sbin/nologin
+///
+/// - assert:
+///     that:
+///       - "passwd | from_csv(delimiter=':', fieldnames=['name', 'password', 'uid', 'gid', 'gecos', 'home', 'shell']) | rejectattr(attribute='shell', test='search', value='nologin') | map(attribute='name') | join == 'alice'"
+/// ```
+/// ANCHOR_END: filter
+use crate::utils::tera::filters::selectattr::filter_by_attribute;
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::Result;
+
+pub fn rejectattr(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    filter_by_attribute("rejectattr", value, args, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejectattr() {
+        let users = json!([
+            {"name": "alice", "uid": 1000},
+            {"name": "daemon", "uid": 1},
+            {"name": "nobody"},
+        ]);
+        let args =
+            args!("attribute" => json!("uid"), "test" => json!("lessthan"), "value" => json!(1000));
+        assert_eq!(
+            rejectattr(&users, &args).unwrap(),
+            json!([{"name": "alice", "uid": 1000}, {"name": "nobody"}])
+        );
+    }
+}
@@ -0,0 +1,46 @@
+/// ANCHOR: filter
+/// ## rstrip
+///
+/// Remove trailing characters from a string, as Python `rstrip`.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// chars:
+///   type: string
+///   description: Set of characters to remove. Defaults to whitespace.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "'  foo  ' | rstrip == '  foo'"
+///       - "'/etc/rash/' | rstrip(chars='/') == '/etc/rash'"
+/// ```
+/// ANCHOR_END: filter
+use crate::utils::tera::filters::trim::{strip, Side};
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::Result;
+
+pub fn rstrip(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    strip("rstrip", value, args, Side::End)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rstrip() {
+        let result = rstrip(&json!("  foo  "), &HashMap::new()).unwrap();
+        assert_eq!(result, json!("  foo"));
+        let args = args!("chars" => json!("#/"));
+        let result = rstrip(&json!("#//foo/#"), &args).unwrap();
+        assert_eq!(result, json!("#//foo"));
+    }
+}
@@ -0,0 +1,284 @@
+/// ANCHOR: filter
+/// ## selectattr
+///
+/// Filter a list of objects, keeping those whose attribute passes a test, like Ansible
+/// `selectattr`. Without `test`, objects whose attribute is truthy are kept.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// attribute:
+///   type: string
+///   required: true
+///   description: Dotted path of the object field to test.
+/// test:
+///   type: string
+///   choices:
+///     - defined
+///     - undefined
+///     - equalto
+///     - eq
+///     - ne
+///     - greaterthan
+///     - gt
+///     - ge
+///     - lessthan
+///     - lt
+///     - le
+///     - in
+///     - match
+///     - search
+///   description: |
+///     Test applied to the attribute. `match` checks the regex at the beginning of the
+///     string and `search` anywhere in it.
+/// value:
+///   type: any
+///   description: Value the attribute is tested against. Required by all tests but `defined` and `undefined`.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     inventory: |
+///       [[servers]]
+///       name = "web1"
+///       port = 80
+///       [[servers]]
+///       name = "db1"
+///       port = 5432
+///       backup = true
+///
+/// - assert:
+///     that:
+///       - "inventory | from_toml | get(key='servers') | selectattr(attribute='port', test='greaterthan', value=1024) | map(attribute='name') | join == 'db1'"
+///       - "inventory | from_toml | get(key='servers') | selectattr(attribute='name', test='match', value='web') | length == 1"
+///       - "inventory | from_toml | get(key='servers') | selectattr(attribute='backup') | length == 1"
+/// ```
+/// ANCHOR_END: filter
+use crate::utils::tera::filters::{get_attribute, is_truthy};
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+fn compare(filter: &str, attribute: &Value, value: &Value) -> Result<Ordering> {
+    let ordering = match (attribute, value) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+    ordering.ok_or_else(|| {
+        Error::msg(format!(
+            "Filter `{}` cannot compare {} with {}",
+            filter, attribute, value
+        ))
+    })
+}
+
+fn is_match(filter: &str, attribute: &Value, pattern: &str) -> Result<bool> {
+    let s = try_get_value!(filter, "attribute", String, attribute);
+    let regex = Regex::new(pattern).map_err(|e| {
+        Error::chain(
+            format!("Filter `{}` invalid pattern '{}'", filter, pattern),
+            e,
+        )
+    })?;
+    Ok(regex.is_match(&s))
+}
+
+/// Return true if `attribute`, `None` when undefined, passes `test` against `value`.
+fn apply_test(
+    filter: &str,
+    test: &str,
+    attribute: Option<&Value>,
+    value: Option<&Value>,
+) -> Result<bool> {
+    let attribute = match (test, attribute) {
+        ("defined", attribute) => return Ok(attribute.is_some()),
+        ("undefined", attribute) => return Ok(attribute.is_none()),
+        (_, None) => return Ok(false),
+        (_, Some(attribute)) => attribute,
+    };
+    let value = value.ok_or_else(|| {
+        Error::msg(format!(
+            "Filter `{}` test `{}` expected an arg called `value`",
+            filter, test
+        ))
+    })?;
+    match test {
+        "equalto" | "eq" => Ok(attribute == value),
+        "ne" => Ok(attribute != value),
+        "greaterthan" | "gt" => Ok(compare(filter, attribute, value)? == Ordering::Greater),
+        "ge" => Ok(compare(filter, attribute, value)? != Ordering::Less),
+        "lessthan" | "lt" => Ok(compare(filter, attribute, value)? == Ordering::Less),
+        "le" => Ok(compare(filter, attribute, value)? != Ordering::Greater),
+        "in" => match value {
+            Value::Array(list) => Ok(list.contains(attribute)),
+            Value::String(s) => Ok(attribute.as_str().is_some_and(|a| s.contains(a))),
+            Value::Object(map) => Ok(attribute.as_str().is_some_and(|a| map.contains_key(a))),
+            _ => Err(Error::msg(format!(
+                "Filter `{}` test `in` expected a list, string or object value, found: {}",
+                filter, value
+            ))),
+        },
+        "match" => {
+            let pattern = try_get_value!(filter, "value", String, value);
+            is_match(filter, attribute, &format!("^(?:{})", pattern))
+        }
+        "search" => {
+            let pattern = try_get_value!(filter, "value", String, value);
+            is_match(filter, attribute, &pattern)
+        }
+        _ => Err(Error::msg(format!(
+            "Filter `{}` unknown test `{}`",
+            filter, test
+        ))),
+    }
+}
+
+/// Keep elements of `value` list whose attribute passes the test in `args` if `select` is true,
+/// or those which do not pass it otherwise.
+pub fn filter_by_attribute(
+    filter: &str,
+    value: &Value,
+    args: &HashMap<String, Value>,
+    select: bool,
+) -> Result<Value> {
+    let list = try_get_value!(filter, "value", Vec<Value>, value);
+    let attribute = match args.get("attribute") {
+        Some(v) => try_get_value!(filter, "attribute", String, v),
+        None => {
+            return Err(Error::msg(format!(
+                "Filter `{}` expected an arg called `attribute`",
+                filter
+            )))
+        }
+    };
+    let test = match args.get("test") {
+        Some(v) => Some(try_get_value!(filter, "test", String, v)),
+        None => None,
+    };
+
+    let mut filtered = Vec::new();
+    for element in list {
+        let element_attribute = get_attribute(&element, &attribute);
+        let passed = match &test {
+            Some(test) => apply_test(filter, test, element_attribute, args.get("value"))?,
+            None => element_attribute.is_some_and(is_truthy),
+        };
+        if passed == select {
+            filtered.push(element);
+        };
+    }
+    Ok(Value::Array(filtered))
+}
+
+pub fn selectattr(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    filter_by_attribute("selectattr", value, args, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    fn get_args(attribute: &str, test: &str, value: Value) -> HashMap<String, Value> {
+        args!("attribute" => json!(attribute), "test" => json!(test), "value" => value)
+    }
+
+    fn get_hosts() -> Value {
+        json!([
+            {"name": "web1", "port": 80, "tags": {"env": "prod"}},
+            {"name": "web2", "port": 8080, "tags": {"env": "dev"}},
+            {"name": "db1", "port": 5432, "backup": true},
+        ])
+    }
+
+    fn get_names(value: Value) -> Vec<String> {
+        value
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|host| host["name"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_selectattr_equalto() {
+        let result = selectattr(
+            &get_hosts(),
+            &get_args("tags.env", "equalto", json!("prod")),
+        );
+        assert_eq!(get_names(result.unwrap()), vec!["web1"]);
+        let result = selectattr(&get_hosts(), &get_args("port", "ne", json!(80)));
+        assert_eq!(get_names(result.unwrap()), vec!["web2", "db1"]);
+    }
+
+    #[test]
+    fn test_selectattr_greaterthan() {
+        let result = selectattr(&get_hosts(), &get_args("port", "greaterthan", json!(1024)));
+        assert_eq!(get_names(result.unwrap()), vec!["web2", "db1"]);
+        let result = selectattr(&get_hosts(), &get_args("port", "le", json!(5432)));
+        assert_eq!(get_names(result.unwrap()), vec!["web1", "db1"]);
+        let _ = selectattr(&get_hosts(), &get_args("port", "gt", json!("80"))).unwrap_err();
+    }
+
+    #[test]
+    fn test_selectattr_match() {
+        let result = selectattr(&get_hosts(), &get_args("name", "match", json!("web")));
+        assert_eq!(get_names(result.unwrap()), vec!["web1", "web2"]);
+        let result = selectattr(&get_hosts(), &get_args("name", "match", json!("1")));
+        assert_eq!(get_names(result.unwrap()), Vec::<String>::new());
+        let result = selectattr(&get_hosts(), &get_args("name", "search", json!("1")));
+        assert_eq!(get_names(result.unwrap()), vec!["web1", "db1"]);
+    }
+
+    #[test]
+    fn test_selectattr_in() {
+        let result = selectattr(&get_hosts(), &get_args("port", "in", json!([80, 5432])));
+        assert_eq!(get_names(result.unwrap()), vec!["web1", "db1"]);
+    }
+
+    #[test]
+    fn test_selectattr_defined() {
+        let result = selectattr(&get_hosts(), &get_args("tags", "defined", Value::Null));
+        assert_eq!(get_names(result.unwrap()), vec!["web1", "web2"]);
+        let result = selectattr(
+            &get_hosts(),
+            &get_args("tags.env", "undefined", Value::Null),
+        );
+        assert_eq!(get_names(result.unwrap()), vec!["db1"]);
+    }
+
+    #[test]
+    fn test_selectattr_truthy() {
+        let args = args!("attribute" => json!("backup"));
+        let result = selectattr(&get_hosts(), &args);
+        assert_eq!(get_names(result.unwrap()), vec!["db1"]);
+    }
+
+    #[test]
+    fn test_selectattr_invalid() {
+        let _ = selectattr(&get_hosts(), &get_args("port", "boo", json!(1))).unwrap_err();
+        let _ = selectattr(&get_hosts(), &HashMap::new()).unwrap_err();
+        let args = args!("attribute" => json!("port"), "test" => json!("equalto"));
+        let _ = selectattr(&get_hosts(), &args).unwrap_err();
+    }
+
+    #[test]
+    fn test_selectattr_render() {
+        let vars = Vars::from_serialize(json!({ "hosts": get_hosts() })).unwrap();
+        let result = render_string(
+            "{{ hosts | selectattr(attribute='port', test='greaterthan', value=1024) | map(attribute='name') | join(sep=',') }}",
+            vars,
+        )
+        .unwrap();
+        assert_eq!(result, "web2,db1");
+    }
+}
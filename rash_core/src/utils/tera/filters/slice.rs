@@ -0,0 +1,180 @@
+/// ANCHOR: filter
+/// ## slice
+///
+/// Distribute a list into `slices` lists, as Jinja2 `slice`, e.g. to lay out items in columns.
+/// First lists get the extra elements and, if `fill_with` is set, the rest are filled up to the
+/// same length. Extends Tera builtin `slice`, which is used when `slices` is not passed.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// slices:
+///   type: integer
+///   description: Number of lists to return.
+/// fill_with:
+///   type: any
+///   description: Value used to fill up shorter lists.
+/// start:
+///   type: integer
+///   description: Tera builtin start index, negative values count from the end.
+/// end:
+///   type: integer
+///   description: Tera builtin end index, negative values count from the end.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     hosts:
+///       - web1
+///       - web2
+///       - web3
+///
+/// - assert:
+///     that:
+///       - "hosts | slice(slices=2) | first | length == 2"
+///       - "hosts | slice(slices=2) | last | first == 'web3'"
+///       - "hosts | slice(start=1) | first == 'web2'"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+/// Index as Tera builtin `slice` does: negative values count from the end.
+fn get_index(i: f64, list: &[Value]) -> usize {
+    if i >= 0.0 {
+        i as usize
+    } else {
+        (list.len() as f64 + i) as usize
+    }
+}
+
+fn slice_range(list: &[Value], args: &HashMap<String, Value>) -> Result<Value> {
+    let start = match args.get("start") {
+        Some(v) => get_index(try_get_value!("slice", "start", f64, v), list),
+        None => 0,
+    };
+    let end = match args.get("end") {
+        Some(v) => get_index(try_get_value!("slice", "end", f64, v), list),
+        None => list.len(),
+    }
+    .min(list.len());
+    if start >= end {
+        return Ok(Value::Array(Vec::new()));
+    };
+    Ok(Value::Array(list[start..end].to_vec()))
+}
+
+fn distribute(list: &[Value], slices: usize, fill_with: Option<&Value>) -> Value {
+    let items_per_slice = list.len() / slices;
+    let slices_with_extra = list.len() % slices;
+    let mut start = 0;
+    Value::Array(
+        (0..slices)
+            .map(|slice_number| {
+                let length = items_per_slice + usize::from(slice_number < slices_with_extra);
+                let mut slice = list[start..start + length].to_vec();
+                start += length;
+                if let (Some(fill_with), true) = (fill_with, slices_with_extra > 0) {
+                    slice.resize(items_per_slice + 1, fill_with.clone());
+                };
+                Value::Array(slice)
+            })
+            .collect(),
+    )
+}
+
+pub fn slice(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let list = try_get_value!("slice", "value", Vec<Value>, value);
+    match args.get("slices") {
+        Some(v) => {
+            if args.contains_key("start") || args.contains_key("end") {
+                return Err(Error::msg(
+                    "Filter `slice` slices arg cannot be combined with start or end",
+                ));
+            };
+            let slices = try_get_value!("slice", "slices", usize, v);
+            if slices == 0 {
+                return Err(Error::msg("Filter `slice` slices must be greater than 0"));
+            };
+            Ok(distribute(&list, slices, args.get("fill_with")))
+        }
+        None => slice_range(&list, args),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_slice_slices() {
+        let value = json!([1, 2, 3, 4, 5, 6, 7]);
+        let result = slice(&value, &args!("slices" => json!(3))).unwrap();
+        assert_eq!(result, json!([[1, 2, 3], [4, 5], [6, 7]]));
+
+        let result = slice(&json!([1, 2, 3, 4]), &args!("slices" => json!(2))).unwrap();
+        assert_eq!(result, json!([[1, 2], [3, 4]]));
+    }
+
+    #[test]
+    fn test_slice_slices_fill_with() {
+        let value = json!([1, 2, 3, 4, 5, 6, 7]);
+        let result = slice(
+            &value,
+            &args!("slices" => json!(3), "fill_with" => json!(0)),
+        )
+        .unwrap();
+        assert_eq!(result, json!([[1, 2, 3], [4, 5, 0], [6, 7, 0]]));
+
+        let result = slice(
+            &json!([1, 2, 3, 4]),
+            &args!("slices" => json!(2), "fill_with" => json!(0)),
+        )
+        .unwrap();
+        assert_eq!(result, json!([[1, 2], [3, 4]]));
+    }
+
+    #[test]
+    fn test_slice_slices_more_than_items() {
+        let result = slice(&json!([1, 2]), &args!("slices" => json!(3))).unwrap();
+        assert_eq!(result, json!([[1], [2], []]));
+    }
+
+    #[test]
+    fn test_slice_range() {
+        let value = json!([1, 2, 3, 4, 5]);
+        let result = slice(&value, &args!("start" => json!(1), "end" => json!(3))).unwrap();
+        assert_eq!(result, json!([2, 3]));
+        let result = slice(&value, &args!("start" => json!(-2))).unwrap();
+        assert_eq!(result, json!([4, 5]));
+        let result = slice(&value, &args!("start" => json!(4), "end" => json!(1))).unwrap();
+        assert_eq!(result, json!([]));
+        let result = slice(&value, &args!("end" => json!(10))).unwrap();
+        assert_eq!(result, value);
+    }
+
+    #[test]
+    fn test_slice_invalid() {
+        let value = json!([1, 2]);
+        let _ = slice(&value, &args!("slices" => json!(0))).unwrap_err();
+        let _ = slice(&value, &args!("slices" => json!(2), "start" => json!(1))).unwrap_err();
+    }
+
+    #[test]
+    fn test_slice_render() {
+        let vars = Vars::from_serialize(json!({"items": ["a", "b", "c"]})).unwrap();
+        let result = render_string(
+            "{% for column in items | slice(slices=2) %}{{ column | join(sep=' ') }};{% endfor %}",
+            vars,
+        )
+        .unwrap();
+        assert_eq!(result, "a b;c;");
+    }
+}
@@ -0,0 +1,138 @@
+/// ANCHOR: filter
+/// ## split
+///
+/// Split a string into a list, Python style. Extends Tera builtin `split` so `pat` is optional,
+/// splitting on runs of whitespace and discarding empty strings, and the number of splits
+/// can be limited.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// pat:
+///   type: string
+///   description: Separator. Defaults to any whitespace.
+/// maxsplit:
+///   type: integer
+///   description: |
+///     Maximum number of splits; the remainder is returned as the last element.
+///     Defaults to no limit.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "'a:b:c' | split(pat=':', maxsplit=1) | last == 'b:c'"
+///       - "' foo  boo ' | split | length == 2"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+fn split_whitespace(s: &str, maxsplit: Option<usize>) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut rest = s.trim_start();
+    while !rest.is_empty() {
+        if maxsplit == Some(result.len()) {
+            result.push(rest.to_string());
+            break;
+        };
+        match rest.find(char::is_whitespace) {
+            Some(i) => {
+                result.push(rest[..i].to_string());
+                rest = rest[i..].trim_start();
+            }
+            None => {
+                result.push(rest.to_string());
+                break;
+            }
+        }
+    }
+    result
+}
+
+pub fn split(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let s = try_get_value!("split", "value", String, value);
+    let maxsplit = match args.get("maxsplit") {
+        Some(v) => match try_get_value!("split", "maxsplit", i64, v) {
+            x if x < 0 => None,
+            x => Some(x as usize),
+        },
+        None => None,
+    };
+
+    let elements = match args.get("pat") {
+        Some(v) => {
+            let pat = try_get_value!("split", "pat", String, v);
+            if pat.is_empty() {
+                return Err(Error::msg("Filter `split` received an empty `pat`"));
+            };
+            match maxsplit {
+                Some(n) => s.splitn(n + 1, pat.as_str()).map(String::from).collect(),
+                None => s.split(pat.as_str()).map(String::from).collect(),
+            }
+        }
+        None => split_whitespace(&s, maxsplit),
+    };
+    Ok(json!(elements))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_split_whitespace() {
+        let result = split(&json!("  foo boo\t\nzoo  "), &HashMap::new()).unwrap();
+        assert_eq!(result, json!(["foo", "boo", "zoo"]));
+        let result = split(&json!("   "), &HashMap::new()).unwrap();
+        assert_eq!(result, json!([]));
+    }
+
+    #[test]
+    fn test_split_whitespace_maxsplit() {
+        let result = split(&json!(" foo  boo zoo "), &args!("maxsplit" => json!(1))).unwrap();
+        assert_eq!(result, json!(["foo", "boo zoo "]));
+    }
+
+    #[test]
+    fn test_split_pat() {
+        let result = split(&json!("a:b::c"), &args!("pat" => json!(":"))).unwrap();
+        assert_eq!(result, json!(["a", "b", "", "c"]));
+    }
+
+    #[test]
+    fn test_split_pat_maxsplit() {
+        let args = |maxsplit| args!("pat" => json!(":"), "maxsplit" => json!(maxsplit));
+        assert_eq!(
+            split(&json!("a:b:c"), &args(1)).unwrap(),
+            json!(["a", "b:c"])
+        );
+        assert_eq!(split(&json!("a:b:c"), &args(0)).unwrap(), json!(["a:b:c"]));
+        assert_eq!(
+            split(&json!("a:b:c"), &args(-1)).unwrap(),
+            json!(["a", "b", "c"])
+        );
+    }
+
+    #[test]
+    fn test_split_empty_pat() {
+        let _ = split(&json!("abc"), &args!("pat" => json!(""))).unwrap_err();
+    }
+
+    #[test]
+    fn test_split_render() {
+        let result = render_string(
+            "{{ 'a:b:c' | split(pat=':', maxsplit=1) | join(sep=',') }}",
+            Vars::new(),
+        )
+        .unwrap();
+        assert_eq!(result, "a,b:c");
+    }
+}
@@ -0,0 +1,228 @@
+/// ANCHOR: filter
+/// ## to_nice_yaml
+///
+/// Serialize a value to human readable block style YAML, without flow collections nor document
+/// start marker.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// indent:
+///   type: integer
+///   description: Number of spaces used for each nesting level, at least 2. Defaults to 2.
+/// sort_keys:
+///   type: bool
+///   description: Emit mapping keys in alphabetical order. Defaults to true.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     config:
+///       name: rash
+///       package:
+///         version: "1.0.0"
+///
+/// - assert:
+///     that:
+///       - "config | to_nice_yaml == 'name: rash\npackage:\n  version: 1.0.0\n'"
+///       - "config | to_nice_yaml(indent=4) == 'name: rash\npackage:\n    version: 1.0.0\n'"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+fn is_block(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => !map.is_empty(),
+        Value::Array(list) => !list.is_empty(),
+        _ => false,
+    }
+}
+
+fn to_scalar(value: &Value) -> Result<String> {
+    let s = serde_yaml::to_string(value).map_err(|e| {
+        Error::chain(
+            format!("Filter `to_nice_yaml` cannot serialize {}", value),
+            e,
+        )
+    })?;
+    let s = s.trim_end_matches('\n');
+    Ok(s.strip_prefix("---")
+        .map_or(s, |s| s.trim_start_matches([' ', '\n']))
+        .to_string())
+}
+
+/// Return `value` YAML lines, without indentation of the current level.
+fn to_lines(value: &Value, indent: usize, sort_keys: bool) -> Result<Vec<String>> {
+    let padding = " ".repeat(indent);
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            let mut items = map.iter().collect::<Vec<_>>();
+            if sort_keys {
+                items.sort_by_key(|(key, _)| *key);
+            };
+            let mut lines = Vec::new();
+            for (key, v) in items {
+                let key = to_scalar(&Value::String(key.clone()))?;
+                if is_block(v) {
+                    lines.push(format!("{}:", key));
+                    lines.extend(
+                        to_lines(v, indent, sort_keys)?
+                            .into_iter()
+                            .map(|line| format!("{}{}", padding, line)),
+                    );
+                } else {
+                    lines.push(format!("{}: {}", key, to_scalar(v)?));
+                }
+            }
+            Ok(lines)
+        }
+        Value::Array(list) if !list.is_empty() => {
+            let mut lines = Vec::new();
+            for v in list {
+                if is_block(v) {
+                    let item_lines = to_lines(v, indent, sort_keys)?;
+                    lines.extend(item_lines.into_iter().enumerate().map(|(i, line)| {
+                        if i == 0 {
+                            format!("-{}{}", &padding[1..], line)
+                        } else {
+                            format!("{}{}", padding, line)
+                        }
+                    }));
+                } else {
+                    lines.push(format!("- {}", to_scalar(v)?));
+                }
+            }
+            Ok(lines)
+        }
+        _ => Ok(vec![to_scalar(value)?]),
+    }
+}
+
+pub fn to_nice_yaml(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let indent = match args.get("indent") {
+        Some(v) => try_get_value!("to_nice_yaml", "indent", usize, v),
+        None => 2,
+    };
+    if indent < 2 {
+        return Err(Error::msg(format!(
+            "Filter `to_nice_yaml` indent must be at least 2, found: {}",
+            indent
+        )));
+    };
+    let sort_keys = match args.get("sort_keys") {
+        Some(v) => try_get_value!("to_nice_yaml", "sort_keys", bool, v),
+        None => true,
+    };
+    let lines = to_lines(value, indent, sort_keys)?;
+    Ok(Value::String(format!("{}\n", lines.join("\n"))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    fn get_args(indent: usize) -> HashMap<String, Value> {
+        args!("indent" => json!(indent))
+    }
+
+    fn get_config() -> Value {
+        json!({
+            "name": "rash",
+            "package": {"version": "1.0.0", "authors": ["foo", "boo"]},
+            "servers": [{"host": "web1", "port": 80}, {"host": "web2", "port": 8080}],
+            "empty": {},
+        })
+    }
+
+    #[test]
+    fn test_to_nice_yaml() {
+        let result = to_nice_yaml(&get_config(), &HashMap::new()).unwrap();
+        assert_eq!(
+            result,
+            json!(
+                r#"empty: {}
+name: rash
+package:
+  authors:
+    - foo
+    - boo
+  version: 1.0.0
+servers:
+  - host: web1
+    port: 80
+  - host: web2
+    port: 8080
+"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_to_nice_yaml_indent() {
+        let result = to_nice_yaml(&get_config(), &get_args(4)).unwrap();
+        assert_eq!(
+            result,
+            json!(
+                r#"empty: {}
+name: rash
+package:
+    authors:
+        - foo
+        - boo
+    version: 1.0.0
+servers:
+    -   host: web1
+        port: 80
+    -   host: web2
+        port: 8080
+"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_to_nice_yaml_sort_keys() {
+        let value = json!({"zoo": 1, "boo": {"foo": 2, "abc": 3}});
+        let result = to_nice_yaml(&value, &HashMap::new()).unwrap();
+        assert_eq!(result, json!("boo:\n  abc: 3\n  foo: 2\nzoo: 1\n"));
+    }
+
+    #[test]
+    fn test_to_nice_yaml_scalars() {
+        let value = json!({"a": "yes", "b": "1", "c": null, "d": true, "e": 1.5, "f": []});
+        let result = to_nice_yaml(&value, &HashMap::new()).unwrap();
+        assert_eq!(
+            result,
+            json!("a: \"yes\"\nb: \"1\"\nc: ~\nd: true\ne: 1.5\nf: []\n")
+        );
+        let result = to_nice_yaml(&json!("foo"), &HashMap::new()).unwrap();
+        assert_eq!(result, json!("foo\n"));
+    }
+
+    #[test]
+    fn test_to_nice_yaml_round_trip() {
+        let result = to_nice_yaml(&get_config(), &get_args(3)).unwrap();
+        let parsed: Value = serde_yaml::from_str(result.as_str().unwrap()).unwrap();
+        assert_eq!(parsed, get_config());
+    }
+
+    #[test]
+    fn test_to_nice_yaml_invalid_indent() {
+        let _ = to_nice_yaml(&get_config(), &get_args(1)).unwrap_err();
+    }
+
+    #[test]
+    fn test_to_nice_yaml_render() {
+        let vars = Vars::from_serialize(json!({"config": {"name": "rash"}})).unwrap();
+        let result = render_string("{{ config | to_nice_yaml }}", vars).unwrap();
+        assert_eq!(result, "name: rash\n");
+    }
+}
@@ -0,0 +1,88 @@
+/// ANCHOR: filter
+/// ## to_toml
+///
+/// Serialize an object to TOML text. Values which TOML cannot represent, like null, fail.
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     config:
+///       name: rash
+///       package:
+///         version: "1.0.0"
+///
+/// - assert:
+///     that:
+///       - "config | to_toml | from_toml | get(key='name') == 'rash'"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{Error, Result};
+
+pub fn to_toml(value: &Value, _: &HashMap<String, Value>) -> Result<Value> {
+    if !value.is_object() {
+        return Err(Error::msg(format!(
+            "Filter `to_toml` requires an object, found: {}",
+            value
+        )));
+    };
+    let toml_value = toml::Value::try_from(value)
+        .map_err(|e| Error::chain(format!("Filter `to_toml` cannot serialize {}", value), e))?;
+    toml::to_string(&toml_value)
+        .map(Value::String)
+        .map_err(|e| Error::chain(format!("Filter `to_toml` cannot serialize {}", value), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_to_toml() {
+        let value = json!({
+            "package": {"name": "rash", "authors": ["foo"]},
+            "dependencies": {"tera": {"version": "1", "default-features": false}},
+            "edition": 2018,
+        });
+        let result = to_toml(&value, &HashMap::new()).unwrap();
+        assert_eq!(
+            result,
+            json!(
+                r#"edition = 2018
+[dependencies.tera]
+default-features = false
+version = "1"
+
+[package]
+authors = ["foo"]
+name = "rash"
+"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_to_toml_null() {
+        let error = to_toml(&json!({"foo": null}), &HashMap::new()).unwrap_err();
+        assert!(error.to_string().contains("cannot serialize"));
+    }
+
+    #[test]
+    fn test_to_toml_not_object() {
+        let _ = to_toml(&json!([1, 2]), &HashMap::new()).unwrap_err();
+    }
+
+    #[test]
+    fn test_to_toml_render() {
+        let mut vars = Vars::new();
+        vars.insert("config", &json!({"name": "rash"}));
+        let result = render_string("{{ config | to_toml }}", vars).unwrap();
+        assert_eq!(result, "name = \"rash\"\n");
+    }
+}
@@ -0,0 +1,121 @@
+/// ANCHOR: filter
+/// ## trim
+///
+/// Remove leading and trailing characters from a string, as Python `strip`. Extends Tera builtin
+/// `trim` so any set of characters can be removed, not just whitespace.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// chars:
+///   type: string
+///   description: Set of characters to remove. Defaults to whitespace.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "'  foo  ' | trim == 'foo'"
+///       - "'##foo#' | trim(chars='#') == 'foo'"
+///       - "'xyfooyx' | trim(chars='xy') == 'foo'"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Result};
+
+/// Which ends of the string are stripped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    Both,
+    Start,
+    End,
+}
+
+/// Strip characters in `chars` arg, or whitespace if unset, from `side` of `value`.
+pub fn strip(
+    filter: &str,
+    value: &Value,
+    args: &HashMap<String, Value>,
+    side: Side,
+) -> Result<Value> {
+    let s = try_get_value!(filter, "value", String, value);
+    let chars = match args.get("chars") {
+        Some(v) => Some(try_get_value!(filter, "chars", String, v)),
+        None => None,
+    };
+    let is_stripped = |c: char| match &chars {
+        Some(chars) => chars.contains(c),
+        None => c.is_whitespace(),
+    };
+    let stripped = match side {
+        Side::Both => s.trim_matches(is_stripped),
+        Side::Start => s.trim_start_matches(is_stripped),
+        Side::End => s.trim_end_matches(is_stripped),
+    };
+    Ok(Value::String(stripped.to_string()))
+}
+
+pub fn trim(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    strip("trim", value, args, Side::Both)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    fn get_args(chars: &str) -> HashMap<String, Value> {
+        args!("chars" => json!(chars))
+    }
+
+    #[test]
+    fn test_trim() {
+        let result = trim(&json!(" \t foo boo\n "), &HashMap::new()).unwrap();
+        assert_eq!(result, json!("foo boo"));
+    }
+
+    #[test]
+    fn test_trim_chars() {
+        let result = trim(&json!("##foo#"), &get_args("#")).unwrap();
+        assert_eq!(result, json!("foo"));
+        let result = trim(&json!("xyfoo yx"), &get_args("yx")).unwrap();
+        assert_eq!(result, json!("foo "));
+        let result = trim(&json!("  foo  "), &get_args("#")).unwrap();
+        assert_eq!(result, json!("  foo  "));
+    }
+
+    #[test]
+    fn test_strip_side() {
+        let value = json!("--foo--");
+        let args = get_args("-");
+        assert_eq!(
+            strip("test", &value, &args, Side::Start).unwrap(),
+            json!("foo--")
+        );
+        assert_eq!(
+            strip("test", &value, &args, Side::End).unwrap(),
+            json!("--foo")
+        );
+    }
+
+    #[test]
+    fn test_trim_invalid() {
+        let _ = trim(&json!(1), &HashMap::new()).unwrap_err();
+        let args = args!("chars" => json!(1));
+        let _ = trim(&json!("foo"), &args).unwrap_err();
+    }
+
+    #[test]
+    fn test_trim_render() {
+        let mut vars = Vars::new();
+        vars.insert("title", "== Title ==");
+        let result = render_string("{{ title | trim(chars='= ') }}", vars).unwrap();
+        assert_eq!(result, "Title");
+    }
+}
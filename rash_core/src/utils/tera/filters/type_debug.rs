@@ -0,0 +1,65 @@
+/// ANCHOR: filter
+/// ## type_debug
+///
+/// Return the type name of a value: `string`, `int`, `float`, `bool`, `list`, `dict` or `none`.
+/// Useful to debug why a condition is not matching.
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "rash.args | type_debug == 'list'"
+///       - "rash.user.uid | type_debug == 'int'"
+/// ```
+/// ANCHOR_END: filter
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::Result;
+
+pub fn type_debug(value: &Value, _: &HashMap<String, Value>) -> Result<Value> {
+    let type_name = match value {
+        Value::Null => "none",
+        Value::Bool(_) => "bool",
+        Value::Number(n) if n.is_f64() => "float",
+        Value::Number(_) => "int",
+        Value::String(_) => "string",
+        Value::Array(_) => "list",
+        Value::Object(_) => "dict",
+    };
+    Ok(Value::String(type_name.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_type_debug() {
+        let args = HashMap::new();
+        assert_eq!(type_debug(&json!(null), &args).unwrap(), json!("none"));
+        assert_eq!(type_debug(&json!(true), &args).unwrap(), json!("bool"));
+        assert_eq!(type_debug(&json!(1), &args).unwrap(), json!("int"));
+        assert_eq!(type_debug(&json!(-1), &args).unwrap(), json!("int"));
+        assert_eq!(type_debug(&json!(1.5), &args).unwrap(), json!("float"));
+        assert_eq!(type_debug(&json!("boo"), &args).unwrap(), json!("string"));
+        assert_eq!(type_debug(&json!([1]), &args).unwrap(), json!("list"));
+        assert_eq!(type_debug(&json!({"a": 1}), &args).unwrap(), json!("dict"));
+    }
+
+    #[test]
+    fn test_type_debug_render() {
+        assert_eq!(
+            render_string("{{ 1 | type_debug }}", Vars::new()).unwrap(),
+            "int"
+        );
+        assert_eq!(
+            render_string("{{ [] | type_debug }}", Vars::new()).unwrap(),
+            "list"
+        );
+    }
+}
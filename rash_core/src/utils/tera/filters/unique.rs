@@ -0,0 +1,143 @@
+/// ANCHOR: filter
+/// ## unique
+///
+/// Remove duplicated elements from a list, preserving first-seen order.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// case_sensitive:
+///   type: bool
+///   description: Compare strings case sensitively. Defaults to true.
+/// attribute:
+///   type: string
+///   description: Dotted path of the object field used to compare elements.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     packages:
+///       - curl
+///       - Curl
+///       - git
+///       - curl
+///
+/// - assert:
+///     that:
+///       - "packages | unique | length == 3"
+///       - "packages | unique(case_sensitive=false) | length == 2"
+/// ```
+/// ANCHOR_END: filter
+use crate::utils::tera::filters::get_attribute;
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+pub fn unique(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let list = try_get_value!("unique", "value", Vec<Value>, value);
+
+    let case_sensitive = match args.get("case_sensitive") {
+        Some(v) => try_get_value!("unique", "case_sensitive", bool, v),
+        None => true,
+    };
+    let attribute = match args.get("attribute") {
+        Some(v) => Some(try_get_value!("unique", "attribute", String, v)),
+        None => None,
+    };
+
+    let mut seen = HashSet::new();
+    list.into_iter()
+        .filter_map(|element| {
+            let key = match &attribute {
+                Some(attr) => match get_attribute(&element, attr) {
+                    Some(v) => v.clone(),
+                    None => {
+                        return Some(Err(Error::msg(format!(
+                            "attribute '{}' not found in {}",
+                            attr, element
+                        ))))
+                    }
+                },
+                None => element.clone(),
+            };
+            let key_string = match key {
+                Value::String(s) if !case_sensitive => s.to_lowercase(),
+                _ => key.to_string(),
+            };
+            if seen.insert(key_string) {
+                Some(Ok(element))
+            } else {
+                None
+            }
+        })
+        .collect::<Result<Vec<Value>>>()
+        .map(Value::Array)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_unique() {
+        let result = unique(&json!([3, 1, 3, 2, 1]), &HashMap::new()).unwrap();
+        assert_eq!(result, json!([3, 1, 2]));
+    }
+
+    #[test]
+    fn test_unique_case_sensitive_default() {
+        let result = unique(&json!(["a", "A", "b", "a"]), &HashMap::new()).unwrap();
+        assert_eq!(result, json!(["a", "A", "b"]));
+    }
+
+    #[test]
+    fn test_unique_case_insensitive() {
+        let args = args!("case_sensitive" => json!(false));
+        let result = unique(&json!(["a", "A", "b", "B", "c"]), &args).unwrap();
+        assert_eq!(result, json!(["a", "b", "c"]));
+    }
+
+    #[test]
+    fn test_unique_attribute() {
+        let args = args!("attribute" => json!("user.name"));
+        let result = unique(
+            &json!([
+                {"id": 1, "user": {"name": "foo"}},
+                {"id": 2, "user": {"name": "boo"}},
+                {"id": 3, "user": {"name": "foo"}},
+            ]),
+            &args,
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            json!([
+                {"id": 1, "user": {"name": "foo"}},
+                {"id": 2, "user": {"name": "boo"}},
+            ])
+        );
+    }
+
+    #[test]
+    fn test_unique_attribute_not_found() {
+        let args = args!("attribute" => json!("name"));
+        let _ = unique(&json!([{"id": 1}]), &args).unwrap_err();
+    }
+
+    #[test]
+    fn test_unique_render() {
+        let result = render_string(
+            "{{ ['a', 'A', 'b'] | unique(case_sensitive=false) | join(sep=',') }}",
+            Vars::new(),
+        )
+        .unwrap();
+        assert_eq!(result, "a,b");
+    }
+}
@@ -0,0 +1,220 @@
+/// ANCHOR: filter
+/// ## version_compare
+///
+/// Compare a version with another one, returning a boolean. By default versions are compared
+/// loosely, splitting them in numeric and alphabetic components, so `1.10` is greater than
+/// `1.9` and `1.2.0rc1` is compared too.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// version:
+///   type: string
+///   required: true
+///   description: Version to compare with.
+/// operator:
+///   type: string
+///   description: |
+///     Comparison operator: lt, le, eq, ne, ge and gt, or their symbolic forms.
+///     Defaults to eq.
+/// strict:
+///   type: bool
+///   description: Parse both versions as SemVer, failing if they are not valid. Defaults to false.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     installed: "1.10.2"
+///
+/// - assert:
+///     that:
+///       - "installed | version_compare(version='1.9', operator='ge')"
+///       - "installed | version_compare(version='1.10.2')"
+///       - "installed | version_compare(version='2.0.0-rc.1', operator='lt', strict=true)"
+/// ```
+/// ANCHOR_END: filter
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+/// Component of a loose version.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Component {
+    Number(u64),
+    Text(String),
+}
+
+fn parse_loose(version: &str) -> Vec<Component> {
+    let mut components = Vec::new();
+    let mut chars = version.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() {
+            let mut number = String::new();
+            while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                number.push(d);
+                chars.next();
+            }
+            // digits only, it only fails on overflow
+            components.push(match number.parse::<u64>() {
+                Ok(n) => Component::Number(n),
+                Err(_) => Component::Text(number),
+            });
+        } else if c.is_alphabetic() {
+            let mut text = String::new();
+            while let Some(&d) = chars.peek().filter(|d| d.is_alphabetic()) {
+                text.push(d);
+                chars.next();
+            }
+            components.push(Component::Text(text.to_lowercase()));
+        } else {
+            chars.next();
+        }
+    }
+    components
+}
+
+fn compare_strict(version: &str, other: &str) -> Result<Ordering> {
+    let parse = |s: &str| {
+        semver::Version::parse(s).map_err(|e| {
+            Error::msg(format!(
+                "Filter `version_compare` received an invalid SemVer version '{}': {}",
+                s, e
+            ))
+        })
+    };
+    Ok(parse(version)?.cmp(&parse(other)?))
+}
+
+pub fn version_compare(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let version = try_get_value!("version_compare", "value", String, value);
+    let other = match args.get("version") {
+        Some(v) => try_get_value!("version_compare", "version", String, v),
+        None => {
+            return Err(Error::msg(
+                "Filter `version_compare` expected an arg called `version`",
+            ))
+        }
+    };
+    let operator = match args.get("operator") {
+        Some(v) => try_get_value!("version_compare", "operator", String, v),
+        None => "eq".to_string(),
+    };
+    let strict = match args.get("strict") {
+        Some(v) => try_get_value!("version_compare", "strict", bool, v),
+        None => false,
+    };
+
+    let ordering = if strict {
+        compare_strict(&version, &other)?
+    } else {
+        parse_loose(&version).cmp(&parse_loose(&other))
+    };
+
+    let result = match operator.as_str() {
+        "lt" | "<" => ordering == Ordering::Less,
+        "le" | "<=" => ordering != Ordering::Greater,
+        "eq" | "==" | "=" => ordering == Ordering::Equal,
+        "ne" | "!=" | "<>" => ordering != Ordering::Equal,
+        "ge" | ">=" => ordering != Ordering::Less,
+        "gt" | ">" => ordering == Ordering::Greater,
+        _ => {
+            return Err(Error::msg(format!(
+                "Filter `version_compare` received an invalid operator: {}",
+                operator
+            )))
+        }
+    };
+    Ok(Value::Bool(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    fn compare(version: &str, other: &str, operator: &str, strict: bool) -> Result<Value> {
+        let args = args!(
+            "version" => json!(other),
+            "operator" => json!(operator),
+            "strict" => json!(strict),
+        );
+        version_compare(&json!(version), &args)
+    }
+
+    #[test]
+    fn test_version_compare_operators() {
+        vec![
+            ("1.2.0", "1.2.0", "==", true),
+            ("1.2.0", "1.2.0", "!=", false),
+            ("1.10.0", "1.9.0", ">", true),
+            ("1.10.0", "1.9.0", "<", false),
+            ("1.2.0", "1.2.0", ">=", true),
+            ("1.2.0", "1.2.1", "<=", true),
+            ("1.2.1", "1.2.0", "le", false),
+            ("2.0", "1.99", "gt", true),
+            ("1.0", "1.0", "ne", false),
+        ]
+        .into_iter()
+        .for_each(|(version, other, operator, expected)| {
+            assert_eq!(
+                compare(version, other, operator, false).unwrap(),
+                json!(expected),
+                "{} {} {}",
+                version,
+                operator,
+                other
+            )
+        });
+    }
+
+    #[test]
+    fn test_version_compare_loose() {
+        assert_eq!(compare("1.2", "1.2.0", "lt", false).unwrap(), json!(true));
+        assert_eq!(
+            compare("1.2.0rc1", "1.2.0", "gt", false).unwrap(),
+            json!(true)
+        );
+        assert_eq!(
+            compare("2:1.2-3", "2:1.2-10", "lt", false).unwrap(),
+            json!(true)
+        );
+        assert_eq!(
+            compare("v1.2.0", "V1.2.0", "eq", false).unwrap(),
+            json!(true)
+        );
+    }
+
+    #[test]
+    fn test_version_compare_strict() {
+        assert_eq!(
+            compare("1.2.0-rc.1", "1.2.0", "lt", true).unwrap(),
+            json!(true)
+        );
+        assert_eq!(compare("1.10.0", "1.9.0", "gt", true).unwrap(), json!(true));
+        let _ = compare("1.2", "1.2.0", "eq", true).unwrap_err();
+    }
+
+    #[test]
+    fn test_version_compare_invalid() {
+        let _ = compare("1.2", "1.2", "~=", false).unwrap_err();
+        let _ = version_compare(&json!("1.2"), &HashMap::new()).unwrap_err();
+    }
+
+    #[test]
+    fn test_version_compare_render() {
+        let mut vars = Vars::new();
+        vars.insert("installed", "1.10.2");
+        let result = render_string(
+            "{{ installed | version_compare(version='1.9', operator='>=') }}",
+            vars,
+        )
+        .unwrap();
+        assert_eq!(result, "true");
+    }
+}
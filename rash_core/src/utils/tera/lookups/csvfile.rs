@@ -0,0 +1,219 @@
+/// ANCHOR: lookup
+/// ## csvfile
+///
+/// Look up a key in the first column of a CSV file and return the value of another column of the
+/// same row.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// key:
+///   type: string
+///   required: true
+///   description: Value searched in the first column.
+/// file:
+///   type: string
+///   required: true
+///   description: Path of the CSV file.
+/// col:
+///   type: integer or string
+///   description: |
+///     Column to return, by index (starting in 0) or by name when `header` is true.
+///     Defaults to 1.
+/// delimiter:
+///   type: string
+///   description: Field delimiter, `TAB` is accepted for tabs. Defaults to `,`.
+/// header:
+///   type: bool
+///   description: Skip the first row, using it as column names. Defaults to false.
+/// default:
+///   type: string
+///   description: Value returned when key is not found.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "csvfile(key='bob', file='/etc/users.csv', col=2) == 'bob@example.com'"
+///       - "csvfile(key='alice', file='/etc/users.csv', default='none') == 'none'"
+/// ```
+/// ANCHOR_END: lookup
+use std::collections::HashMap;
+
+use csv::ReaderBuilder;
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+fn get_delimiter(args: &HashMap<String, Value>) -> Result<u8> {
+    let delimiter = match args.get("delimiter") {
+        Some(v) => try_get_value!("csvfile", "delimiter", String, v),
+        None => return Ok(b','),
+    };
+    match delimiter.as_str() {
+        "TAB" | "\\t" | "\t" => Ok(b'\t'),
+        d if d.len() == 1 => Ok(d.as_bytes()[0]),
+        d => Err(Error::msg(format!(
+            "csvfile delimiter `{}` must be a single character",
+            d
+        ))),
+    }
+}
+
+pub fn csvfile(args: &HashMap<String, Value>) -> Result<Value> {
+    let key = match args.get("key") {
+        Some(v) => try_get_value!("csvfile", "key", String, v),
+        None => {
+            return Err(Error::msg(
+                "Function `csvfile` expected an arg called `key`",
+            ))
+        }
+    };
+    let file = match args.get("file") {
+        Some(v) => try_get_value!("csvfile", "file", String, v),
+        None => {
+            return Err(Error::msg(
+                "Function `csvfile` expected an arg called `file`",
+            ))
+        }
+    };
+    let header = match args.get("header") {
+        Some(v) => try_get_value!("csvfile", "header", bool, v),
+        None => false,
+    };
+
+    let mut reader = ReaderBuilder::new()
+        .delimiter(get_delimiter(args)?)
+        .has_headers(header)
+        .flexible(true)
+        .from_path(&file)
+        .map_err(|e| Error::msg(format!("csvfile file `{}` failed: {}", file, e)))?;
+
+    let col = match args.get("col") {
+        Some(Value::String(name)) if header => reader
+            .headers()
+            .map_err(|e| Error::msg(e.to_string()))?
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| Error::msg(format!("csvfile col `{}` not found in header", name)))?,
+        Some(v) => try_get_value!("csvfile", "col", usize, v),
+        None => 1,
+    };
+
+    for record in reader.records() {
+        let record = record.map_err(|e| Error::msg(e.to_string()))?;
+        if record.get(0) == Some(key.as_str()) {
+            return record
+                .get(col)
+                .map(|value| Value::String(value.to_string()))
+                .ok_or_else(|| {
+                    Error::msg(format!("csvfile col `{}` not found for key `{}`", col, key))
+                });
+        }
+    }
+
+    Ok(args.get("default").cloned().unwrap_or(Value::Null))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::Path;
+
+    use tempfile::tempdir;
+
+    fn get_args(file: &Path, extra: Vec<(&str, Value)>) -> HashMap<String, Value> {
+        vec![("file".to_string(), json!(file.to_str().unwrap()))]
+            .into_iter()
+            .chain(extra.into_iter().map(|(k, v)| (k.to_string(), v)))
+            .collect()
+    }
+
+    #[test]
+    fn test_csvfile() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("users.csv");
+        let mut file = File::create(file_path.clone()).unwrap();
+        writeln!(
+            file,
+            "alice,1000,alice@example.com\nbob,1001,bob@example.com"
+        )
+        .unwrap();
+
+        let result = csvfile(&get_args(&file_path, vec![("key", json!("bob"))])).unwrap();
+        assert_eq!(result, json!("1001"));
+
+        let result = csvfile(&get_args(
+            &file_path,
+            vec![("key", json!("bob")), ("col", json!(2))],
+        ))
+        .unwrap();
+        assert_eq!(result, json!("bob@example.com"));
+    }
+
+    #[test]
+    fn test_csvfile_default() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("users.csv");
+        let mut file = File::create(file_path.clone()).unwrap();
+        writeln!(file, "alice,1000").unwrap();
+
+        let result = csvfile(&get_args(
+            &file_path,
+            vec![("key", json!("bob")), ("default", json!("nobody"))],
+        ))
+        .unwrap();
+        assert_eq!(result, json!("nobody"));
+
+        let result = csvfile(&get_args(&file_path, vec![("key", json!("bob"))])).unwrap();
+        assert_eq!(result, Value::Null);
+    }
+
+    #[test]
+    fn test_csvfile_header_and_delimiter() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("users.tsv");
+        let mut file = File::create(file_path.clone()).unwrap();
+        writeln!(
+            file,
+            "name\tuid\tmail\nname2\t1\tfoo\nbob\t1001\tbob@example.com"
+        )
+        .unwrap();
+
+        let result = csvfile(&get_args(
+            &file_path,
+            vec![
+                ("key", json!("bob")),
+                ("delimiter", json!("TAB")),
+                ("header", json!(true)),
+                ("col", json!("mail")),
+            ],
+        ))
+        .unwrap();
+        assert_eq!(result, json!("bob@example.com"));
+
+        let result = csvfile(&get_args(
+            &file_path,
+            vec![
+                ("key", json!("name")),
+                ("delimiter", json!("TAB")),
+                ("header", json!(true)),
+            ],
+        ))
+        .unwrap();
+        assert_eq!(result, Value::Null);
+    }
+
+    #[test]
+    fn test_csvfile_not_found() {
+        let _ = csvfile(&get_args(
+            Path::new("/tmp/not_found/users.csv"),
+            vec![("key", json!("bob"))],
+        ))
+        .unwrap_err();
+    }
+}
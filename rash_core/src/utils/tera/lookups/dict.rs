@@ -0,0 +1,55 @@
+/// ANCHOR: lookup
+/// ## dict
+///
+/// Build a dict from keyword arguments. Useful to construct params inline or as `combine`
+/// input.
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "dict(name='web1', port=80) | get(key='port') == 80"
+///       - "dict() | length == 0"
+/// ```
+/// ANCHOR_END: lookup
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+use tera::Result;
+
+pub fn dict(args: &HashMap<String, Value>) -> Result<Value> {
+    Ok(Value::Object(
+        args.iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect::<Map<String, Value>>(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_dict() {
+        let args = vec![("name", json!("x")), ("port", json!(80))]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect::<HashMap<String, Value>>();
+        assert_eq!(dict(&args).unwrap(), json!({"name": "x", "port": 80}));
+        assert_eq!(dict(&HashMap::new()).unwrap(), json!({}));
+    }
+
+    #[test]
+    fn test_dict_render() {
+        let result = render_string(
+            "{{ dict(name='x', port=80) | json_encode() | safe }}",
+            Vars::new(),
+        )
+        .unwrap();
+        assert_eq!(result, r#"{"name":"x","port":80}"#);
+    }
+}
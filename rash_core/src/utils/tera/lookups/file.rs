@@ -0,0 +1,137 @@
+/// ANCHOR: lookup
+/// ## file
+///
+/// Return the content of a file.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// path:
+///   type: string
+///   required: true
+///   description: Path of the file to read.
+/// split_lines:
+///   type: bool
+///   description: Return a list with file lines instead of a string. Defaults to false.
+/// rstrip:
+///   type: bool
+///   description: Remove trailing whitespace and newlines. Defaults to true.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "file(path='/etc/hostname') | length > 0"
+///       - "file(path='/etc/passwd', split_lines=true) | length > 1"
+/// ```
+/// ANCHOR_END: lookup
+use std::collections::HashMap;
+use std::fs;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+pub fn file(args: &HashMap<String, Value>) -> Result<Value> {
+    let path = match args.get("path") {
+        Some(v) => try_get_value!("file", "path", String, v),
+        None => return Err(Error::msg("Function `file` expected an arg called `path`")),
+    };
+    let split_lines = match args.get("split_lines") {
+        Some(v) => try_get_value!("file", "split_lines", bool, v),
+        None => false,
+    };
+    let rstrip = match args.get("rstrip") {
+        Some(v) => try_get_value!("file", "rstrip", bool, v),
+        None => true,
+    };
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| Error::msg(format!("file `{}` failed: {}", path, e)))?;
+    let content = if rstrip { content.trim_end() } else { &content };
+
+    if split_lines {
+        Ok(Value::Array(
+            content
+                .lines()
+                .map(|line| Value::String(line.to_string()))
+                .collect(),
+        ))
+    } else {
+        Ok(Value::String(content.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+
+    fn get_args(tmp_file: &NamedTempFile, extra: Vec<(&str, Value)>) -> HashMap<String, Value> {
+        vec![("path", json!(tmp_file.path().to_str().unwrap()))]
+            .into_iter()
+            .chain(extra)
+            .map(|(k, v)| (k.to_string(), v))
+            .collect()
+    }
+
+    fn create_file() -> NamedTempFile {
+        let mut tmp_file = NamedTempFile::new().unwrap();
+        write!(tmp_file, "foo\nboo\n\n").unwrap();
+        tmp_file
+    }
+
+    #[test]
+    fn test_file() {
+        let tmp_file = create_file();
+        let result = file(&get_args(&tmp_file, vec![])).unwrap();
+        assert_eq!(result, json!("foo\nboo"));
+    }
+
+    #[test]
+    fn test_file_split_lines() {
+        let tmp_file = create_file();
+        let result = file(&get_args(&tmp_file, vec![("split_lines", json!(true))])).unwrap();
+        assert_eq!(result, json!(["foo", "boo"]));
+    }
+
+    #[test]
+    fn test_file_no_rstrip() {
+        let tmp_file = create_file();
+        let result = file(&get_args(&tmp_file, vec![("rstrip", json!(false))])).unwrap();
+        assert_eq!(result, json!("foo\nboo\n\n"));
+
+        let result = file(&get_args(
+            &tmp_file,
+            vec![("rstrip", json!(false)), ("split_lines", json!(true))],
+        ))
+        .unwrap();
+        assert_eq!(result, json!(["foo", "boo", ""]));
+    }
+
+    #[test]
+    fn test_file_not_found() {
+        let args = args!("path" => json!("/tmp/this/file/does/not/exist"));
+        let _ = file(&args).unwrap_err();
+    }
+
+    #[test]
+    fn test_file_render() {
+        let tmp_file = create_file();
+        let mut vars = Vars::new();
+        vars.insert("path", tmp_file.path().to_str().unwrap());
+        let result = render_string(
+            "{% for line in file(path=path, split_lines=true) %}{{ line }},{% endfor %}",
+            vars,
+        )
+        .unwrap();
+        assert_eq!(result, "foo,boo,");
+    }
+}
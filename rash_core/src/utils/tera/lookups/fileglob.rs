@@ -0,0 +1,89 @@
+/// ANCHOR: lookup
+/// ## fileglob
+///
+/// Return the list of files matching a shell glob pattern, sorted alphabetically.
+/// Directories are excluded and an empty list is returned when nothing matches.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// pattern:
+///   type: string
+///   required: true
+///   description: Shell glob pattern, e.g. `/etc/*.conf`.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "fileglob(pattern='/etc/*.conf') | length == 3"
+/// ```
+/// ANCHOR_END: lookup
+use std::collections::HashMap;
+
+use glob::glob;
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+pub fn fileglob(args: &HashMap<String, Value>) -> Result<Value> {
+    let pattern = match args.get("pattern") {
+        Some(v) => try_get_value!("fileglob", "pattern", String, v),
+        None => {
+            return Err(Error::msg(
+                "Function `fileglob` expected an arg called `pattern`",
+            ))
+        }
+    };
+
+    let paths = glob(&pattern)
+        .map_err(|e| Error::msg(format!("fileglob pattern `{}` invalid: {}", pattern, e)))?
+        .filter_map(|entry| entry.ok())
+        .filter(|path| path.is_file())
+        .map(|path| Value::String(path.to_string_lossy().to_string()))
+        .collect::<Vec<Value>>();
+    Ok(Value::Array(paths))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::{create_dir, File};
+
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_fileglob() {
+        let dir = tempdir().unwrap();
+        File::create(dir.path().join("b.conf")).unwrap();
+        File::create(dir.path().join("a.conf")).unwrap();
+        File::create(dir.path().join("c.txt")).unwrap();
+        create_dir(dir.path().join("d.conf")).unwrap();
+
+        let args = args!("pattern" => json!(format!("{}/*.conf", dir.path().to_str().unwrap())));
+        let result = fileglob(&args).unwrap();
+        assert_eq!(
+            result,
+            json!([
+                dir.path().join("a.conf").to_str().unwrap(),
+                dir.path().join("b.conf").to_str().unwrap(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_fileglob_no_match() {
+        let dir = tempdir().unwrap();
+        let args = args!("pattern" => json!(format!("{}/*.conf", dir.path().to_str().unwrap())));
+        let result = fileglob(&args).unwrap();
+        assert_eq!(result, json!([]));
+    }
+
+    #[test]
+    fn test_fileglob_invalid_pattern() {
+        let args = args!("pattern" => json!("/tmp/[a"));
+        let _ = fileglob(&args).unwrap_err();
+    }
+}
@@ -0,0 +1,129 @@
+/// ANCHOR: lookup
+/// ## lines
+///
+/// Return the lines of a file, or of a command output, as a list of strings.
+/// Trailing newlines are stripped.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// file:
+///   type: string
+///   description: Path of the file to read.
+/// cmd:
+///   type: string
+///   description: |
+///     Command executed by `/bin/sh`, its stdout is read.
+///     Only `file` or `cmd` can be provided, not both.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "lines(file='/etc/hostname') | length == 1"
+///       - "lines(cmd='echo foo') | first == 'foo'"
+/// ```
+/// ANCHOR_END: lookup
+use std::collections::HashMap;
+use std::fs;
+use std::process::Command;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+fn read_cmd_output(cmd: &str) -> Result<String> {
+    let output = Command::new("/bin/sh")
+        .args(vec!["-c", cmd])
+        .output()
+        .map_err(|e| Error::msg(format!("lines cmd `{}` failed: {}", cmd, e)))?;
+    if !output.status.success() {
+        return Err(Error::msg(format!(
+            "lines cmd `{}` failed: {}",
+            cmd,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    String::from_utf8(output.stdout).map_err(|e| Error::msg(e.to_string()))
+}
+
+pub fn lines(args: &HashMap<String, Value>) -> Result<Value> {
+    let content = match (args.get("file"), args.get("cmd")) {
+        (Some(file), None) => {
+            let path = try_get_value!("lines", "file", String, file);
+            fs::read_to_string(&path)
+                .map_err(|e| Error::msg(format!("lines file `{}` failed: {}", path, e)))?
+        }
+        (None, Some(cmd)) => read_cmd_output(&try_get_value!("lines", "cmd", String, cmd))?,
+        _ => {
+            return Err(Error::msg(
+                "Function `lines` expected an arg called `file` or `cmd`, not both",
+            ))
+        }
+    };
+
+    Ok(Value::Array(
+        content
+            .lines()
+            .map(|line| Value::String(line.to_string()))
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    use std::fs::File;
+    use std::io::Write;
+
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_lines_file() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("hosts");
+        let mut file = File::create(file_path.clone()).unwrap();
+        writeln!(file, "127.0.0.1 localhost\n::1 localhost\n10.0.0.1 foo").unwrap();
+
+        let args = args!("file" => json!(file_path.to_str().unwrap()));
+        let result = lines(&args).unwrap();
+        assert_eq!(
+            result,
+            json!(["127.0.0.1 localhost", "::1 localhost", "10.0.0.1 foo"])
+        );
+
+        let rendered = render_string(
+            &format!(
+                "{{{{ lines(file='{}') | length }}}}",
+                file_path.to_str().unwrap()
+            ),
+            Vars::new(),
+        )
+        .unwrap();
+        assert_eq!(rendered, "3");
+    }
+
+    #[test]
+    fn test_lines_file_not_found() {
+        let args = args!("file" => json!("/tmp/not_found/boo"));
+        let _ = lines(&args).unwrap_err();
+    }
+
+    #[test]
+    fn test_lines_cmd() {
+        let args = args!("cmd" => json!("printf 'foo\\nboo\\n'"));
+        let result = lines(&args).unwrap();
+        assert_eq!(result, json!(["foo", "boo"]));
+    }
+
+    #[test]
+    fn test_lines_file_and_cmd() {
+        let args = args!("file" => json!("/etc/hosts"), "cmd" => json!("ls"));
+        let _ = lines(&args).unwrap_err();
+    }
+}
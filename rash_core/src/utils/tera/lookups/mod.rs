@@ -0,0 +1,130 @@
+mod csvfile;
+mod dict;
+mod file;
+mod fileglob;
+mod lines;
+mod nested;
+mod omit;
+mod random_choice;
+mod sequence;
+mod together;
+mod url;
+mod vars;
+mod vault;
+
+use crate::utils::vault::Decryptor;
+use crate::vars::Vars;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result, Tera};
+
+/// Register all `rash` lookups in [`Tera`] as functions, with `vault` using `decryptor`.
+///
+/// [`Tera`]: ../../../../tera/struct.Tera.html
+pub fn add_lookup_functions(tera: &mut Tera, decryptor: Arc<dyn Decryptor + Sync + Send>) {
+    tera.register_function("csvfile", with_errors("csvfile", csvfile::csvfile));
+    tera.register_function("dict", dict::dict);
+    tera.register_function("file", with_errors("file", file::file));
+    tera.register_function("fileglob", with_errors("fileglob", fileglob::fileglob));
+    tera.register_function("lines", with_errors("lines", lines::lines));
+    tera.register_function("nested", with_errors("nested", nested::nested));
+    tera.register_function("omit", omit::omit);
+    tera.register_tester("omitted", omit::omitted);
+    tera.register_function(
+        "random_choice",
+        with_errors("random_choice", random_choice::random_choice),
+    );
+    tera.register_function("sequence", with_errors("sequence", sequence::sequence));
+    tera.register_function("together", with_errors("together", together::together));
+    tera.register_function("url", with_errors("url", url::url));
+    tera.register_function("vault", with_errors("vault", vault::vault(decryptor)));
+}
+
+/// Register lookups which need access to current render `vars`.
+///
+/// [`Tera`]: ../../../../tera/struct.Tera.html
+pub fn add_context_lookup_functions(tera: &mut Tera, vars: &Vars) {
+    tera.register_function("vars", with_errors("vars", vars::vars(vars)));
+}
+
+/// Wrap `lookup` to handle its failures as the `errors` argument says: `strict` returns the
+/// error, `warn` logs it and returns `null`, and `ignore` silently returns `null`.
+/// Defaults to `strict`.
+fn with_errors<F>(
+    name: &'static str,
+    lookup: F,
+) -> impl Fn(&HashMap<String, Value>) -> Result<Value> + Sync + Send
+where
+    F: Fn(&HashMap<String, Value>) -> Result<Value> + Sync + Send,
+{
+    move |args: &HashMap<String, Value>| -> Result<Value> {
+        let errors = match args.get("errors") {
+            Some(v) => try_get_value!(name, "errors", String, v),
+            None => return lookup(args),
+        };
+        let mut args = args.clone();
+        args.remove("errors");
+        match (lookup(&args), errors.as_str()) {
+            (Ok(value), "strict" | "warn" | "ignore") => Ok(value),
+            (Err(e), "strict") => Err(e),
+            (Err(e), "warn") => {
+                warn!("lookup `{}` failed: {}", name, e);
+                Ok(Value::Null)
+            }
+            (Err(_), "ignore") => Ok(Value::Null),
+            (_, errors) => Err(Error::msg(format!(
+                "Function `{}` got an invalid `errors` value `{}`, expected one of: strict, warn, ignore",
+                name, errors
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+
+    #[test]
+    fn test_with_errors_ignore() {
+        let result = render_string(
+            "{% set content = file(path='/tmp/this/file/does/not/exist', errors='ignore') %}{{ content | default(value='missing') }}",
+            Vars::new(),
+        )
+        .unwrap();
+        assert_eq!(result, "missing");
+    }
+
+    #[test]
+    fn test_with_errors_warn() {
+        let result = render_string(
+            "{{ file(path='/tmp/this/file/does/not/exist', errors='warn') }}",
+            Vars::new(),
+        )
+        .unwrap();
+        assert_eq!(result, "");
+    }
+
+    #[test]
+    fn test_with_errors_strict() {
+        let _ = render_string(
+            "{{ file(path='/tmp/this/file/does/not/exist', errors='strict') }}",
+            Vars::new(),
+        )
+        .unwrap_err();
+        let _ = render_string(
+            "{{ file(path='/tmp/this/file/does/not/exist') }}",
+            Vars::new(),
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn test_with_errors_invalid() {
+        let _ = render_string("{{ sequence(end=3, errors='boo') }}", Vars::new()).unwrap_err();
+    }
+}
@@ -0,0 +1,102 @@
+/// ANCHOR: lookup
+/// ## nested
+///
+/// Return the cartesian product of several lists as a list of lists, as Ansible `with_nested`
+/// does.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// lists:
+///   type: list
+///   required: true
+///   description: Lists to combine, the last one varies fastest.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     users:
+///       - alice
+///       - bob
+///     databases:
+///       - clients
+///       - employees
+///
+/// - assert:
+///     that:
+///       - "nested(lists=[users, databases]) | length == 4"
+///       - "nested(lists=[users, databases]) | last | join(sep='@') == 'bob@employees'"
+/// ```
+/// ANCHOR_END: lookup
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+pub fn nested(args: &HashMap<String, Value>) -> Result<Value> {
+    let lists = match args.get("lists") {
+        Some(v) => try_get_value!("nested", "lists", Vec<Vec<Value>>, v),
+        None => {
+            return Err(Error::msg(
+                "Function `nested` expected an arg called `lists`",
+            ))
+        }
+    };
+    if lists.is_empty() {
+        return Ok(Value::Array(vec![]));
+    }
+
+    let product = lists.iter().fold(vec![vec![]], |combinations, list| {
+        combinations
+            .iter()
+            .flat_map(|combination| {
+                list.iter().map(move |element| {
+                    let mut new_combination: Vec<Value> = combination.clone();
+                    new_combination.push(element.clone());
+                    new_combination
+                })
+            })
+            .collect::<Vec<Vec<Value>>>()
+    });
+    Ok(Value::Array(
+        product.into_iter().map(Value::Array).collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_two_lists() {
+        let args = args!("lists" => json!([["a", "b"], [1, 2, 3]]));
+        let result = nested(&args).unwrap();
+        assert_eq!(
+            result,
+            json!([["a", 1], ["a", 2], ["a", 3], ["b", 1], ["b", 2], ["b", 3]])
+        );
+    }
+
+    #[test]
+    fn test_nested_three_lists() {
+        let args = args!("lists" => json!([["a", "b"], [1, 2], ["x", "y"]]));
+        let result = nested(&args).unwrap();
+        let product = result.as_array().unwrap();
+        assert_eq!(product.len(), 8);
+        assert_eq!(product[0], json!(["a", 1, "x"]));
+        assert_eq!(product[1], json!(["a", 1, "y"]));
+        assert_eq!(product[2], json!(["a", 2, "x"]));
+        assert_eq!(product[7], json!(["b", 2, "y"]));
+    }
+
+    #[test]
+    fn test_nested_empty() {
+        let args = args!("lists" => json!([["a", "b"], []]));
+        assert_eq!(nested(&args).unwrap(), json!([]));
+
+        let args = args!("lists" => json!([]));
+        assert_eq!(nested(&args).unwrap(), json!([]));
+    }
+}
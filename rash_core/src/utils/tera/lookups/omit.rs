@@ -0,0 +1,57 @@
+/// ANCHOR: lookup
+/// ## omit
+///
+/// Return a placeholder which removes the param where it is rendered, usually with `default`
+/// filter to only set the param when a variable is defined, like Ansible `omit`. It works in
+/// nested dicts and lists of params too.
+/// The `omitted` test checks if a value is this placeholder.
+///
+/// ### Example
+///
+/// ```yaml
+/// - copy:
+///     content: "foo"
+///     dest: /tmp/foo
+///     mode: "{{ file_mode | default(value=omit()) }}"
+/// ```
+/// ANCHOR_END: lookup
+use crate::utils::tera::OMIT_PLACEHOLDER;
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::Result;
+
+pub fn omit(_: &HashMap<String, Value>) -> Result<Value> {
+    Ok(Value::String(OMIT_PLACEHOLDER.to_string()))
+}
+
+pub fn omitted(value: Option<&Value>, _: &[Value]) -> Result<bool> {
+    Ok(value.and_then(Value::as_str) == Some(OMIT_PLACEHOLDER))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_omitted() {
+        let placeholder = omit(&HashMap::new()).unwrap();
+        assert!(omitted(Some(&placeholder), &[]).unwrap());
+        assert!(!omitted(Some(&json!("foo")), &[]).unwrap());
+        assert!(!omitted(None, &[]).unwrap());
+    }
+
+    #[test]
+    fn test_omit_render() {
+        let result = render_string(
+            "{% set mode = x | default(value=omit()) %}{% if mode is omitted %}omitted{% endif %}",
+            Vars::new(),
+        )
+        .unwrap();
+        assert_eq!(result, "omitted");
+    }
+}
@@ -0,0 +1,85 @@
+/// ANCHOR: lookup
+/// ## random_choice
+///
+/// Return a random element from a list of items.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// items:
+///   type: list
+///   required: true
+///   description: Elements to choose from.
+/// seed:
+///   type: integer
+///   description: Seed of the random generator, for reproducible choices.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "random_choice(items=['a', 'b', 'c']) in 'abc'"
+/// ```
+/// ANCHOR_END: lookup
+use std::collections::HashMap;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{thread_rng, SeedableRng};
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+pub fn random_choice(args: &HashMap<String, Value>) -> Result<Value> {
+    let items = match args.get("items") {
+        Some(v) => try_get_value!("random_choice", "items", Vec<Value>, v),
+        None => {
+            return Err(Error::msg(
+                "Function `random_choice` expected an arg called `items`",
+            ))
+        }
+    };
+
+    let choice = match args.get("seed") {
+        Some(v) => {
+            let seed = try_get_value!("random_choice", "seed", u64, v);
+            items.choose(&mut StdRng::seed_from_u64(seed))
+        }
+        None => items.choose(&mut thread_rng()),
+    };
+    choice
+        .cloned()
+        .ok_or_else(|| Error::msg("Function `random_choice` received an empty list of `items`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_args(seed: Option<u64>) -> HashMap<String, Value> {
+        vec![("items".to_string(), json!(["a", "b", "c", "d", "e"]))]
+            .into_iter()
+            .chain(seed.map(|s| ("seed".to_string(), json!(s))))
+            .collect()
+    }
+
+    #[test]
+    fn test_random_choice() {
+        let items = json!(["a", "b", "c", "d", "e"]);
+        let result = random_choice(&get_args(None)).unwrap();
+        assert!(items.as_array().unwrap().contains(&result));
+    }
+
+    #[test]
+    fn test_random_choice_seed() {
+        let result = random_choice(&get_args(Some(42))).unwrap();
+        (0..10).for_each(|_| assert_eq!(random_choice(&get_args(Some(42))).unwrap(), result));
+    }
+
+    #[test]
+    fn test_random_choice_empty() {
+        let args = args!("items" => json!([]));
+        let _ = random_choice(&args).unwrap_err();
+    }
+}
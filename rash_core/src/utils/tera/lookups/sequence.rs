@@ -0,0 +1,219 @@
+/// ANCHOR: lookup
+/// ## sequence
+///
+/// Generate a list of numbers as strings, as Ansible `with_sequence` does.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// start:
+///   type: integer
+///   description: First number of the sequence. Defaults to 1.
+/// end:
+///   type: integer
+///   description: Last number of the sequence, included.
+/// count:
+///   type: integer
+///   description: Number of elements. Only `end` or `count` can be provided, not both.
+/// stride:
+///   type: integer
+///   description: Increment between numbers, negative to count backwards. Defaults to 1.
+/// format:
+///   type: string
+///   description: |
+///     printf-style format applied to each number, e.g. `host%02d`.
+///     Supported conversions are `d`, `i`, `o`, `x`, `X` and `s`. Defaults to `%d`.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "sequence(end=3) | join(sep=',') == '1,2,3'"
+///       - "sequence(start=0, end=10, stride=5) | join(sep=',') == '0,5,10'"
+///       - "sequence(count=2, format='host%02d') | join(sep=',') == 'host01,host02'"
+/// ```
+/// ANCHOR_END: lookup
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+fn get_int_arg(args: &HashMap<String, Value>, key: &str) -> Result<Option<i64>> {
+    match args.get(key) {
+        Some(v) => Ok(Some(try_get_value!("sequence", key, i64, v))),
+        None => Ok(None),
+    }
+}
+
+fn pad(s: String, width: usize, left_align: bool, zero_pad: bool) -> String {
+    if s.len() >= width {
+        s
+    } else if left_align {
+        format!("{:<width$}", s, width = width)
+    } else if zero_pad {
+        let (sign, digits) = match s.strip_prefix('-') {
+            Some(digits) => ("-", digits.to_string()),
+            None => ("", s),
+        };
+        format!("{}{:0>width$}", sign, digits, width = width - sign.len())
+    } else {
+        format!("{:>width$}", s, width = width)
+    }
+}
+
+/// Format `n` with a printf-style `format` string.
+fn printf(format: &str, n: i64) -> Result<String> {
+    let mut result = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+        let mut left_align = false;
+        let mut zero_pad = false;
+        while let Some(&flag) = chars.peek() {
+            match flag {
+                '-' => left_align = true,
+                '0' => zero_pad = true,
+                _ => break,
+            };
+            chars.next();
+        }
+        let mut width = 0;
+        while let Some(digit) = chars.peek().and_then(|d| d.to_digit(10)) {
+            width = width * 10 + digit as usize;
+            chars.next();
+        }
+        let converted = match chars.next() {
+            Some('%') => "%".to_string(),
+            Some('d') | Some('i') | Some('s') => n.to_string(),
+            Some('o') => format!("{:o}", n),
+            Some('x') => format!("{:x}", n),
+            Some('X') => format!("{:X}", n),
+            _ => {
+                return Err(Error::msg(format!(
+                    "sequence format `{}` is not valid",
+                    format
+                )))
+            }
+        };
+        result.push_str(&pad(converted, width, left_align, zero_pad));
+    }
+    Ok(result)
+}
+
+pub fn sequence(args: &HashMap<String, Value>) -> Result<Value> {
+    let start = get_int_arg(args, "start")?.unwrap_or(1);
+    let stride = get_int_arg(args, "stride")?.unwrap_or(1);
+    let format = match args.get("format") {
+        Some(v) => try_get_value!("sequence", "format", String, v),
+        None => "%d".to_string(),
+    };
+
+    let end = match (get_int_arg(args, "end")?, get_int_arg(args, "count")?) {
+        (Some(end), None) => end,
+        (None, Some(count)) if count <= 0 => return Ok(Value::Array(vec![])),
+        (None, Some(count)) => start + (count - 1) * stride,
+        (Some(_), Some(_)) => {
+            return Err(Error::msg(
+                "Function `sequence` can't have both `end` and `count` args",
+            ))
+        }
+        (None, None) => {
+            return Err(Error::msg(
+                "Function `sequence` expected an arg called `end` or `count`",
+            ))
+        }
+    };
+
+    if stride == 0 {
+        return Ok(Value::Array(vec![]));
+    }
+    if (stride > 0 && end < start) || (stride < 0 && end > start) {
+        return Err(Error::msg(format!(
+            "sequence from {} to {} can't be reached with stride {}",
+            start, end, stride
+        )));
+    }
+
+    let length = (end - start) / stride + 1;
+    (0..length)
+        .map(|i| printf(&format, start + i * stride).map(Value::String))
+        .collect::<Result<Vec<Value>>>()
+        .map(Value::Array)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sequence() {
+        let result = sequence(&args!("end" => json!(3))).unwrap();
+        assert_eq!(result, json!(["1", "2", "3"]));
+
+        let result = sequence(&args!(
+            "start" => json!(10),
+            "end" => json!(0),
+            "stride" => json!(-5),
+        ))
+        .unwrap();
+        assert_eq!(result, json!(["10", "5", "0"]));
+    }
+
+    #[test]
+    fn test_sequence_count() {
+        let result = sequence(&args!(
+            "start" => json!(4),
+            "count" => json!(3),
+            "stride" => json!(2),
+        ))
+        .unwrap();
+        assert_eq!(result, json!(["4", "6", "8"]));
+    }
+
+    #[test]
+    fn test_sequence_format() {
+        let result = sequence(&args!(
+            "start" => json!(1),
+            "end" => json!(3),
+            "format" => json!("host%02d"),
+        ))
+        .unwrap();
+        assert_eq!(result, json!(["host01", "host02", "host03"]));
+
+        let result = sequence(&args!(
+            "start" => json!(9),
+            "end" => json!(11),
+            "format" => json!("0x%-3X|"),
+        ))
+        .unwrap();
+        assert_eq!(result, json!(["0x9  |", "0xA  |", "0xB  |"]));
+    }
+
+    #[test]
+    fn test_sequence_end_and_count() {
+        let _ = sequence(&args!("end" => json!(3), "count" => json!(3))).unwrap_err();
+    }
+
+    #[test]
+    fn test_sequence_unreachable_end() {
+        let _ = sequence(&args!("start" => json!(5), "end" => json!(1))).unwrap_err();
+    }
+
+    #[test]
+    fn test_sequence_invalid_format() {
+        let _ = sequence(&args!("end" => json!(1), "format" => json!("%f"))).unwrap_err();
+    }
+
+    #[test]
+    fn test_printf() {
+        assert_eq!(printf("%d", 7).unwrap(), "7");
+        assert_eq!(printf("%05d", -7).unwrap(), "-0007");
+        assert_eq!(printf("%3d%%", 7).unwrap(), "  7%");
+        assert_eq!(printf("%o", 8).unwrap(), "10");
+    }
+}
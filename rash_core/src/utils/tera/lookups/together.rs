@@ -0,0 +1,98 @@
+/// ANCHOR: lookup
+/// ## together
+///
+/// Zip several lists element-wise into a list of lists, as Ansible `with_together` does.
+/// Shorter lists are padded with `null` up to the length of the longest one.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// lists:
+///   type: list
+///   required: true
+///   description: Lists to zip.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     users:
+///       - alice
+///       - bob
+///     uids:
+///       - 1000
+///       - 1001
+///
+/// - assert:
+///     that:
+///       - "together(lists=[users, uids]) | first | join(sep=':') == 'alice:1000'"
+/// ```
+/// ANCHOR_END: lookup
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+pub fn together(args: &HashMap<String, Value>) -> Result<Value> {
+    let lists = match args.get("lists") {
+        Some(v) => try_get_value!("together", "lists", Vec<Vec<Value>>, v),
+        None => {
+            return Err(Error::msg(
+                "Function `together` expected an arg called `lists`",
+            ))
+        }
+    };
+
+    let length = lists.iter().map(Vec::len).max().unwrap_or(0);
+    Ok(Value::Array(
+        (0..length)
+            .map(|i| {
+                Value::Array(
+                    lists
+                        .iter()
+                        .map(|list| list.get(i).cloned().unwrap_or(Value::Null))
+                        .collect(),
+                )
+            })
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+    use crate::vars::Vars;
+
+    #[test]
+    fn test_together() {
+        let args = args!("lists" => json!([["a", "b"], [1, 2]]));
+        let result = together(&args).unwrap();
+        assert_eq!(result, json!([["a", 1], ["b", 2]]));
+    }
+
+    #[test]
+    fn test_together_unequal_length() {
+        let args = args!("lists" => json!([["a", "b", "c"], [1], []]));
+        let result = together(&args).unwrap();
+        assert_eq!(
+            result,
+            json!([["a", 1, null], ["b", null, null], ["c", null, null]])
+        );
+    }
+
+    #[test]
+    fn test_together_render() {
+        let mut vars = Vars::new();
+        vars.insert("users", &json!(["alice", "bob"]));
+        vars.insert("uids", &json!([1000, 1001]));
+        let result = render_string(
+            "{% for pair in together(lists=[users, uids]) %}{{ pair | join(sep=':') }} {% endfor %}",
+            vars,
+        )
+        .unwrap();
+        assert_eq!(result, "alice:1000 bob:1001 ");
+    }
+}
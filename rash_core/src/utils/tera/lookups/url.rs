@@ -0,0 +1,431 @@
+/// ANCHOR: lookup
+/// ## url
+///
+/// Return the body of an HTTP GET request. Responses with a non-2xx status fail.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// url:
+///   type: string
+///   required: true
+///   description: URL to request.
+/// split_lines:
+///   type: bool
+///   description: Return the body as a list of lines. Defaults to false.
+/// headers:
+///   type: dict
+///   description: HTTP headers added to the request.
+/// validate_certs:
+///   type: bool
+///   description: |
+///     If false, TLS certificates are not validated. Use it only with trusted
+///     self-signed endpoints. Defaults to true.
+/// client_cert:
+///   type: string
+///   description: |
+///     Path of a PEM client certificate chain for mutual TLS. Requires `client_key`.
+/// client_key:
+///   type: string
+///   description: Path of the PEM private key of `client_cert`.
+/// timeout:
+///   type: integer
+///   description: Seconds to wait for the whole request before failing.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "url(url='https://example.com/version') == '1.0.0'"
+///       - "url(url='https://example.com/versions', split_lines=true) | length == 2"
+///       - "url(url='https://self-signed.local/', validate_certs=false) | length > 0"
+/// ```
+/// ANCHOR_END: lookup
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+/// Verifier accepting any server certificate, used when `validate_certs` is false.
+/// Handshake signatures are still verified.
+#[derive(Debug)]
+struct NoCertVerifier(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoCertVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+fn get_client_auth(
+    client_cert: &str,
+    client_key: &str,
+) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)> {
+    let certs = CertificateDer::pem_file_iter(client_cert)
+        .and_then(|certs| certs.collect::<std::result::Result<Vec<_>, _>>())
+        .map_err(|e| {
+            Error::msg(format!(
+                "url client_cert `{}` is invalid: {}",
+                client_cert, e
+            ))
+        })?;
+    let key = PrivateKeyDer::from_pem_file(client_key)
+        .map_err(|e| Error::msg(format!("url client_key `{}` is invalid: {}", client_key, e)))?;
+    Ok((certs, key))
+}
+
+fn get_tls_config(
+    validate_certs: bool,
+    client_auth: Option<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)>,
+) -> Result<ClientConfig> {
+    let provider = Arc::new(ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| Error::chain("url TLS config failed", e))?;
+    let builder = if validate_certs {
+        let root_store = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        builder.with_root_certificates(root_store)
+    } else {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoCertVerifier(provider)))
+    };
+    match client_auth {
+        Some((certs, key)) => builder
+            .with_client_auth_cert(certs, key)
+            .map_err(|e| Error::chain("url client certificate failed", e)),
+        None => Ok(builder.with_no_client_auth()),
+    }
+}
+
+fn get_agent(args: &HashMap<String, Value>) -> Result<ureq::Agent> {
+    let validate_certs = match args.get("validate_certs") {
+        Some(v) => try_get_value!("url", "validate_certs", bool, v),
+        None => true,
+    };
+    let client_auth = match (args.get("client_cert"), args.get("client_key")) {
+        (Some(cert), Some(key)) => Some(get_client_auth(
+            &try_get_value!("url", "client_cert", String, cert),
+            &try_get_value!("url", "client_key", String, key),
+        )?),
+        (None, None) => None,
+        _ => {
+            return Err(Error::msg(
+                "Function `url` requires both `client_cert` and `client_key` args",
+            ))
+        }
+    };
+    let builder = match args.get("timeout") {
+        Some(v) => ureq::AgentBuilder::new().timeout(Duration::from_secs(try_get_value!(
+            "url", "timeout", u64, v
+        ))),
+        None => ureq::AgentBuilder::new(),
+    };
+    if validate_certs && client_auth.is_none() {
+        return Ok(builder.build());
+    };
+    Ok(builder
+        .tls_config(Arc::new(get_tls_config(validate_certs, client_auth)?))
+        .build())
+}
+
+pub fn url(args: &HashMap<String, Value>) -> Result<Value> {
+    let url = match args.get("url") {
+        Some(v) => try_get_value!("url", "url", String, v),
+        None => return Err(Error::msg("Function `url` expected an arg called `url`")),
+    };
+    let split_lines = match args.get("split_lines") {
+        Some(v) => try_get_value!("url", "split_lines", bool, v),
+        None => false,
+    };
+    let headers = match args.get("headers") {
+        Some(v) => try_get_value!("url", "headers", HashMap<String, String>, v),
+        None => HashMap::new(),
+    };
+
+    let agent = get_agent(args)?;
+
+    let request = headers
+        .iter()
+        .fold(agent.get(&url), |request, (k, v)| request.set(k, v));
+    let body = request
+        .call()
+        .map_err(|e| Error::msg(format!("url `{}` failed: {}", url, e)))?
+        .into_string()
+        .map_err(|e| Error::msg(format!("url `{}` failed: {}", url, e)))?;
+
+    if split_lines {
+        Ok(Value::Array(
+            body.lines()
+                .map(|line| Value::String(line.to_string()))
+                .collect(),
+        ))
+    } else {
+        Ok(Value::String(body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use rustls::server::WebPkiClientVerifier;
+    use rustls::{ServerConfig, ServerConnection, StreamOwned};
+    use tempfile::NamedTempFile;
+
+    /// Serve one HTTP request with `status` and `body`, returning the server URL and a handle
+    /// which joins with the raw request headers received.
+    fn serve_once(
+        status: &'static str,
+        body: &'static str,
+    ) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let request = BufReader::new(stream.try_clone().unwrap())
+                .lines()
+                .map(|line| line.unwrap())
+                .take_while(|line| !line.is_empty())
+                .collect::<Vec<String>>()
+                .join("\n");
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+            request
+        });
+        (address, handle)
+    }
+
+    /// Serve one HTTPS request with a self-signed certificate, requiring a client certificate
+    /// signed by `client_ca` if defined. Handle joins with true if the response was sent.
+    fn serve_tls_once(
+        body: &'static str,
+        client_ca: Option<CertificateDer<'static>>,
+    ) -> (String, thread::JoinHandle<bool>) {
+        let cert = rcgen::generate_simple_self_signed(vec!["127.0.0.1".to_string()]).unwrap();
+        let key = PrivateKeyDer::Pkcs8(cert.key_pair.serialize_der().into());
+        let provider = Arc::new(ring::default_provider());
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .unwrap();
+        let builder = match client_ca {
+            Some(ca) => {
+                let mut roots = RootCertStore::empty();
+                roots.add(ca).unwrap();
+                builder.with_client_cert_verifier(
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                        .build()
+                        .unwrap(),
+                )
+            }
+            None => builder.with_no_client_auth(),
+        };
+        let config = Arc::new(
+            builder
+                .with_single_cert(vec![cert.cert.der().clone()], key)
+                .unwrap(),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("https://{}", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut tls = StreamOwned::new(ServerConnection::new(config).unwrap(), stream);
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                match tls.read(&mut buf) {
+                    Ok(n) if n > 0 => request.extend_from_slice(&buf[..n]),
+                    _ => return false,
+                }
+            }
+            write!(
+                tls,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+            tls.conn.send_close_notify();
+            tls.flush().unwrap();
+            true
+        });
+        (address, handle)
+    }
+
+    fn get_tls_args(address: &str, extra: Vec<(&str, Value)>) -> HashMap<String, Value> {
+        vec![("url", json!(address))]
+            .into_iter()
+            .chain(extra)
+            .map(|(k, v)| (k.to_string(), v))
+            .collect()
+    }
+
+    #[test]
+    fn test_url_self_signed_validate_certs() {
+        let (address, handle) = serve_tls_once("foo", None);
+        let error = url(&get_tls_args(&address, vec![])).unwrap_err();
+        assert!(error.to_string().contains("failed"));
+        assert!(!handle.join().unwrap());
+    }
+
+    #[test]
+    fn test_url_self_signed_no_validate_certs() {
+        let (address, handle) = serve_tls_once("foo", None);
+        let result = url(&get_tls_args(
+            &address,
+            vec![("validate_certs", json!(false))],
+        ))
+        .unwrap();
+        assert_eq!(result, json!("foo"));
+        assert!(handle.join().unwrap());
+    }
+
+    #[test]
+    fn test_url_client_cert() {
+        let client = rcgen::generate_simple_self_signed(vec!["client".to_string()]).unwrap();
+        let mut cert_file = NamedTempFile::new().unwrap();
+        write!(cert_file, "{}", client.cert.pem()).unwrap();
+        let mut key_file = NamedTempFile::new().unwrap();
+        write!(key_file, "{}", client.key_pair.serialize_pem()).unwrap();
+
+        let (address, handle) = serve_tls_once("foo", Some(client.cert.der().clone()));
+        let result = url(&get_tls_args(
+            &address,
+            vec![
+                ("validate_certs", json!(false)),
+                ("client_cert", json!(cert_file.path().to_str().unwrap())),
+                ("client_key", json!(key_file.path().to_str().unwrap())),
+            ],
+        ))
+        .unwrap();
+        assert_eq!(result, json!("foo"));
+        assert!(handle.join().unwrap());
+    }
+
+    #[test]
+    fn test_url_client_cert_without_key() {
+        let args = get_tls_args(
+            "https://127.0.0.1",
+            vec![("client_cert", json!("/tmp/cert.pem"))],
+        );
+        let error = url(&args).unwrap_err();
+        assert!(error.to_string().contains("client_key"));
+    }
+
+    #[test]
+    fn test_url_client_cert_invalid() {
+        let args = get_tls_args(
+            "https://127.0.0.1",
+            vec![
+                ("client_cert", json!("/tmp/this/does/not/exist.pem")),
+                ("client_key", json!("/tmp/this/does/not/exist.key")),
+            ],
+        );
+        let error = url(&args).unwrap_err();
+        assert!(error.to_string().contains("client_cert"));
+    }
+
+    #[test]
+    fn test_url_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let args = args!("url" => json!(address), "timeout" => json!(1));
+        let error = url(&args).unwrap_err();
+        assert!(error.to_string().to_lowercase().contains("timed out"));
+        drop(listener);
+    }
+
+    #[test]
+    fn test_url() {
+        let (address, handle) = serve_once("200 OK", "1.0.0\n");
+        let args = args!(
+            "url" => json!(format!("{}/version", address)),
+            "headers" => json!({"X-Token": "boo"}),
+        );
+        let result = url(&args).unwrap();
+        assert_eq!(result, json!("1.0.0\n"));
+
+        let request = handle.join().unwrap();
+        assert!(request.starts_with("GET /version HTTP/1.1"));
+        assert!(request.to_lowercase().contains("x-token: boo"));
+    }
+
+    #[test]
+    fn test_url_split_lines() {
+        let (address, handle) = serve_once("200 OK", "foo\nboo\n");
+        let args = args!("url" => json!(address), "split_lines" => json!(true));
+        let result = url(&args).unwrap();
+        assert_eq!(result, json!(["foo", "boo"]));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_url_not_found() {
+        let (address, handle) = serve_once("404 Not Found", "not found");
+        let args = args!("url" => json!(address));
+        let error = url(&args).unwrap_err();
+        assert!(error.to_string().contains("404"));
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,98 @@
+/// ANCHOR: lookup
+/// ## vars
+///
+/// Return the value of a variable whose name is computed in the template.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// name:
+///   type: string
+///   required: true
+///   description: Name of the variable.
+/// default:
+///   type: any
+///   description: Value returned if the variable does not exist, instead of failing.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - set_vars:
+///     version_curl: "7.79"
+///     package: curl
+///
+/// - assert:
+///     that:
+///       - "vars(name='version_' ~ package) == '7.79'"
+///       - "vars(name='version_git', default='latest') == 'latest'"
+/// ```
+/// ANCHOR_END: lookup
+use crate::vars::Vars;
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+/// Return `vars` lookup with access to current `context` variables.
+pub fn vars(context: &Vars) -> impl Fn(&HashMap<String, Value>) -> Result<Value> + Sync + Send {
+    let context = context.clone().into_json();
+    move |args| {
+        let name = match args.get("name") {
+            Some(v) => try_get_value!("vars", "name", String, v),
+            None => return Err(Error::msg("Function `vars` expected an arg called `name`")),
+        };
+
+        match (context.get(&name), args.get("default")) {
+            (Some(value), _) => Ok(value.clone()),
+            (None, Some(default)) => Ok(default.clone()),
+            (None, None) => Err(Error::msg(format!(
+                "Function `vars` could not find variable `{}`",
+                name
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tera::render_string;
+
+    #[test]
+    fn test_vars() {
+        let mut context = Vars::new();
+        context.insert("version_curl", "7.79");
+        let args = args!("name" => json!("version_curl"));
+        let result = vars(&context)(&args).unwrap();
+        assert_eq!(result, json!("7.79"));
+    }
+
+    #[test]
+    fn test_vars_default() {
+        let args = args!("name" => json!("version_git"), "default" => json!("latest"));
+        let result = vars(&Vars::new())(&args).unwrap();
+        assert_eq!(result, json!("latest"));
+    }
+
+    #[test]
+    fn test_vars_not_found() {
+        let args = args!("name" => json!("version_git"));
+        let _ = vars(&Vars::new())(&args).unwrap_err();
+    }
+
+    #[test]
+    fn test_vars_render() {
+        let mut context = Vars::new();
+        context.insert("version_curl", "7.79");
+        context.insert("package", "curl");
+        let result = render_string(
+            "{{ vars(name='version_' ~ package) }} {{ vars(name='version_git', default='latest') }}",
+            context,
+        )
+        .unwrap();
+        assert_eq!(result, "7.79 latest");
+    }
+}
@@ -0,0 +1,122 @@
+/// ANCHOR: lookup
+/// ## vault
+///
+/// Decrypt a vault value, encrypted with AES-256-GCM using a key derived from the password with
+/// PBKDF2-HMAC-SHA256. Password is read from the file passed in `--vault-password-file`, or
+/// from `RASH_VAULT_PASSWORD` environment variable or the file in `RASH_VAULT_PASSWORD_FILE`.
+/// Values are encrypted with `rash --vault-encrypt`, reading the plaintext from stdin.
+///
+/// ### Parameters
+///
+/// ```yaml
+/// value:
+///   type: string
+///   required: true
+///   description: Encrypted value, starting with `rash-vault:v1:`.
+/// ```
+///
+/// ### Example
+///
+/// ```yaml
+/// - assert:
+///     that:
+///       - "vault(value=env.MYAPP_DB_PASSWORD) | length > 0"
+/// ```
+/// ANCHOR_END: lookup
+use crate::utils::vault::Decryptor;
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tera::{try_get_value, Error, Result};
+
+/// Return `vault` lookup which decrypts values with `decryptor`.
+pub fn vault<D>(decryptor: D) -> impl Fn(&HashMap<String, Value>) -> Result<Value> + Sync + Send
+where
+    D: Decryptor + Sync + Send,
+{
+    move |args: &HashMap<String, Value>| -> Result<Value> {
+        let value = match args.get("value") {
+            Some(v) => try_get_value!("vault", "value", String, v),
+            None => {
+                return Err(Error::msg(
+                    "Function `vault` expected an arg called `value`",
+                ))
+            }
+        };
+        decryptor
+            .decrypt(&value)
+            .map(Value::String)
+            // never include the value in errors
+            .map_err(|e| Error::msg(format!("Function `vault` failed: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::error::{Error as RashError, ErrorKind, Result as RashResult};
+    use crate::utils::tera::new_tera;
+    use crate::utils::vault::{encrypt, PasswordDecryptor};
+    use crate::vars::Vars;
+
+    /// Decrypt `secret:<plaintext>` values, failing with anything else.
+    struct FakeDecryptor;
+
+    impl Decryptor for FakeDecryptor {
+        fn decrypt(&self, ciphertext: &str) -> RashResult<String> {
+            ciphertext
+                .strip_prefix("secret:")
+                .map(String::from)
+                .ok_or_else(|| RashError::new(ErrorKind::InvalidData, "bad value"))
+        }
+    }
+
+    #[test]
+    fn test_vault() {
+        let args = args!("value" => json!("secret:supersecret"));
+        let lookup = vault(FakeDecryptor);
+        assert_eq!(lookup(&args).unwrap(), json!("supersecret"));
+    }
+
+    #[test]
+    fn test_vault_error() {
+        let args = args!("value" => json!("boo:supersecret"));
+        let lookup = vault(FakeDecryptor);
+        let error = lookup(&args).unwrap_err();
+        assert!(!error.to_string().contains("supersecret"));
+    }
+
+    #[test]
+    fn test_vault_missing_value() {
+        let lookup = vault(FakeDecryptor);
+        let _ = lookup(&HashMap::new()).unwrap_err();
+    }
+
+    #[test]
+    fn test_vault_render() {
+        let mut tera = new_tera();
+        tera.register_function("vault", vault(FakeDecryptor));
+        let mut vars = Vars::new();
+        vars.insert("db_password", "secret:supersecret");
+        let result = tera
+            .render_str("password={{ vault(value=db_password) }}", &vars)
+            .unwrap();
+        assert_eq!(result, "password=supersecret");
+    }
+
+    #[test]
+    fn test_vault_encrypt_round_trip() {
+        let ciphertext = encrypt("supersecret", "password").unwrap();
+        let mut tera = new_tera();
+        tera.register_function("vault", vault(PasswordDecryptor::new("password")));
+        let mut vars = Vars::new();
+        vars.insert("db_password", &ciphertext);
+        let result = tera
+            .render_str("password={{ vault(value=db_password) }}", &vars)
+            .unwrap();
+        assert_eq!(result, "password=supersecret");
+        assert!(!result.contains(&ciphertext));
+    }
+}
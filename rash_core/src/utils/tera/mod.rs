@@ -0,0 +1,539 @@
+#[macro_use]
+mod filters;
+mod lookups;
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::utils::vault::{Decryptor, EnvDecryptor};
+use crate::vars::Vars;
+
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tera::ast::{Expr, ExprVal, Node};
+use tera::{Template, Tera};
+
+lazy_static! {
+    static ref TERA: Tera = init_tera();
+}
+
+static VAULT_DECRYPTOR: OnceLock<Arc<dyn Decryptor + Sync + Send>> = OnceLock::new();
+
+/// Value returned by `omit` lookup. Params rendered to it are removed.
+pub const OMIT_PLACEHOLDER: &str = "__rash_omit_placeholder__";
+
+/// How undefined variables are rendered in templates. It is read from `rash.undefined` builtin
+/// of the rendered [`Vars`], so every render of a run uses the same one.
+///
+/// [`Vars`]: ../../vars/type.Vars.html
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UndefinedBehavior {
+    /// Undefined variables fail, except with `default` filter or `is defined` test.
+    Strict,
+    /// Undefined variables are rendered as empty strings; their attributes fail.
+    Lenient,
+    /// Undefined variables and their attributes are rendered as empty strings.
+    Chainable,
+}
+
+impl FromStr for UndefinedBehavior {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "strict" => Ok(UndefinedBehavior::Strict),
+            "lenient" => Ok(UndefinedBehavior::Lenient),
+            "chainable" => Ok(UndefinedBehavior::Chainable),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "undefined behavior '{}' is not valid, use strict, lenient or chainable",
+                    s
+                ),
+            )),
+        }
+    }
+}
+
+/// Return [`UndefinedBehavior`] set in `rash.undefined` of `vars`, or `Strict` if missing.
+///
+/// [`UndefinedBehavior`]: enum.UndefinedBehavior.html
+fn get_undefined_behavior(vars: &Vars) -> UndefinedBehavior {
+    vars.get("rash")
+        .and_then(|rash| rash.get("undefined"))
+        .and_then(Value::as_str)
+        .and_then(|behavior| UndefinedBehavior::from_str(behavior).ok())
+        .unwrap_or(UndefinedBehavior::Strict)
+}
+
+/// Set the [`Decryptor`] used by `vault` lookup. It must be called once, before any render,
+/// and it defaults to [`EnvDecryptor`] if not called.
+///
+/// [`Decryptor`]: ../vault/trait.Decryptor.html
+/// [`EnvDecryptor`]: ../vault/struct.EnvDecryptor.html
+pub fn set_vault_decryptor<D>(decryptor: D) -> Result<()>
+where
+    D: Decryptor + Sync + Send + 'static,
+{
+    VAULT_DECRYPTOR.set(Arc::new(decryptor)).map_err(|_| {
+        Error::new(
+            ErrorKind::Other,
+            "vault decryptor must be set once, before rendering any template",
+        )
+    })
+}
+
+/// Create a [`Tera`] instance with all `rash` filters and lookups registered.
+///
+/// [`Tera`]: ../../../tera/struct.Tera.html
+fn init_tera() -> Tera {
+    let mut tera = Tera::default();
+    filters::add_filters(&mut tera);
+    let decryptor = VAULT_DECRYPTOR.get_or_init(|| Arc::new(EnvDecryptor));
+    lookups::add_lookup_functions(&mut tera, decryptor.clone());
+    tera
+}
+
+/// Return a copy of the shared [`Tera`] instance, ready to add templates and render.
+///
+/// [`Tera`]: ../../../tera/struct.Tera.html
+#[inline(always)]
+pub fn new_tera() -> Tera {
+    TERA.clone()
+}
+
+/// Return a copy of the shared [`Tera`] instance with lookups which need `vars` registered.
+///
+/// [`Tera`]: ../../../tera/struct.Tera.html
+fn new_tera_with_vars(vars: &Vars) -> Tera {
+    let mut tera = new_tera();
+    lookups::add_context_lookup_functions(&mut tera, vars);
+    tera
+}
+
+#[inline(always)]
+pub fn render_string(s: &str, vars: Vars) -> Result<String> {
+    let behavior = get_undefined_behavior(&vars);
+    render_string_with_behavior(s, vars, behavior)
+}
+
+/// Variable paths referenced in template `ast`. Variables checked with tests, like
+/// `is defined`, or with `default` filter are kept apart as `checked`, because Tera already
+/// handles them being undefined.
+#[derive(Default)]
+struct VarRefs {
+    used: Vec<String>,
+    checked: HashSet<String>,
+}
+
+impl VarRefs {
+    fn from_ast(ast: &[Node]) -> Self {
+        let mut refs = VarRefs::default();
+        refs.add_nodes(ast);
+        refs
+    }
+
+    fn add_nodes(&mut self, nodes: &[Node]) {
+        nodes.iter().for_each(|node| match node {
+            Node::VariableBlock(_, expr) => self.add_expr(expr),
+            Node::Set(_, set) => self.add_expr(&set.value),
+            Node::MacroDefinition(_, definition, _) => self.add_nodes(&definition.body),
+            Node::FilterSection(_, section, _) => {
+                section.filter.args.values().for_each(|e| self.add_expr(e));
+                self.add_nodes(&section.body);
+            }
+            Node::Block(_, block, _) => self.add_nodes(&block.body),
+            Node::Forloop(_, forloop, _) => {
+                self.add_expr(&forloop.container);
+                self.add_nodes(&forloop.body);
+                if let Some(empty_body) = &forloop.empty_body {
+                    self.add_nodes(empty_body);
+                };
+            }
+            Node::If(if_node, _) => {
+                if_node.conditions.iter().for_each(|(_, expr, body)| {
+                    self.add_expr(expr);
+                    self.add_nodes(body);
+                });
+                if let Some((_, body)) = &if_node.otherwise {
+                    self.add_nodes(body);
+                };
+            }
+            _ => (),
+        })
+    }
+
+    fn add_expr(&mut self, expr: &Expr) {
+        match &expr.val {
+            ExprVal::Ident(ident) if expr.has_default_filter() => {
+                self.checked.insert(ident.clone());
+            }
+            val => self.add_expr_val(val),
+        };
+        expr.filters
+            .iter()
+            .flat_map(|filter| filter.args.values())
+            .for_each(|e| self.add_expr(e));
+    }
+
+    fn add_expr_val(&mut self, val: &ExprVal) {
+        match val {
+            ExprVal::Ident(ident) => self.used.push(ident.clone()),
+            ExprVal::Math(math) => {
+                self.add_expr(&math.lhs);
+                self.add_expr(&math.rhs);
+            }
+            ExprVal::Logic(logic) => {
+                self.add_expr(&logic.lhs);
+                self.add_expr(&logic.rhs);
+            }
+            ExprVal::In(in_expr) => {
+                self.add_expr(&in_expr.lhs);
+                self.add_expr(&in_expr.rhs);
+            }
+            ExprVal::Test(test) => {
+                self.checked.insert(test.ident.clone());
+                test.args.iter().for_each(|e| self.add_expr(e));
+            }
+            ExprVal::MacroCall(call) => call.args.values().for_each(|e| self.add_expr(e)),
+            ExprVal::FunctionCall(call) => call.args.values().for_each(|e| self.add_expr(e)),
+            ExprVal::Array(values) => values.iter().for_each(|e| self.add_expr(e)),
+            ExprVal::StringConcat(concat) => {
+                concat.values.iter().for_each(|v| self.add_expr_val(v))
+            }
+            ExprVal::String(_) | ExprVal::Int(_) | ExprVal::Float(_) | ExprVal::Bool(_) => (),
+        }
+    }
+}
+
+/// Define undefined `path` as an empty string in `vars`. It is only possible when every
+/// missing element is the last one or `behavior` is `Chainable`, otherwise rendering fails.
+fn define_undefined_var(vars: &mut Vars, path: &str, behavior: UndefinedBehavior) {
+    let mut keys = path.split('.');
+    // safe unwrap: split returns one element at least
+    let name = keys.next().unwrap();
+    let attrs = keys.collect::<Vec<&str>>();
+
+    fn define(value: Option<&Value>, attrs: &[&str], behavior: UndefinedBehavior) -> Option<Value> {
+        match (value, attrs.split_first()) {
+            (None, None) => Some(Value::String(String::new())),
+            (Some(_), None) => None,
+            (Some(Value::Object(map)), Some((attr, rest))) => {
+                let mut map = map.clone();
+                let new_value = define(map.get(*attr), rest, behavior)?;
+                map.insert(attr.to_string(), new_value);
+                Some(Value::Object(map))
+            }
+            (None, Some((attr, rest))) if behavior == UndefinedBehavior::Chainable => {
+                let mut map = Map::new();
+                map.insert(attr.to_string(), define(None, rest, behavior)?);
+                Some(Value::Object(map))
+            }
+            _ => None,
+        }
+    }
+
+    if let Some(value) = define(vars.get(name), &attrs, behavior) {
+        trace!("undefined {} rendered as empty", path);
+        vars.insert(name, &value);
+    };
+}
+
+/// Define as empty strings undefined variables used in template `ast` and not checked,
+/// according to `behavior`.
+fn define_undefined_vars(ast: &[Node], vars: &mut Vars, behavior: UndefinedBehavior) {
+    if behavior == UndefinedBehavior::Strict {
+        return;
+    };
+    let refs = VarRefs::from_ast(ast);
+    refs.used
+        .iter()
+        // subscripts like `foo[bar]` are not supported
+        .filter(|path| !refs.checked.contains(*path) && !path.contains('['))
+        .for_each(|path| define_undefined_var(vars, path, behavior));
+}
+
+fn render_string_with_behavior(s: &str, vars: Vars, behavior: UndefinedBehavior) -> Result<String> {
+    let mut vars = vars;
+    if behavior != UndefinedBehavior::Strict {
+        let template = Template::new("__rash_undefined", None, s)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        define_undefined_vars(&template.ast, &mut vars, behavior);
+    };
+    let mut tera = new_tera_with_vars(&vars);
+    trace!("rendering {:?}", &s);
+    tera.render_str(s, &vars)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Render template file in `path` directly to `writer`, without keeping the whole output in
+/// memory.
+pub fn render_to_writer<W: Write>(path: &Path, vars: &Vars, writer: W) -> Result<()> {
+    let mut tera = new_tera_with_vars(vars);
+    let name = path.to_string_lossy();
+    tera.add_template_file(path, Some(&name))
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let behavior = get_undefined_behavior(vars);
+    let mut vars = vars.clone();
+    if behavior != UndefinedBehavior::Strict {
+        // safe unwrap: template was just added
+        define_undefined_vars(&tera.get_template(&name).unwrap().ast, &mut vars, behavior);
+    };
+    trace!("rendering {:?} to writer", &name);
+    tera.render_to(&name, &vars, writer)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+#[inline(always)]
+pub fn render_as_json(s: &str, vars: Vars) -> Result<String> {
+    render_string(&s.replace("}}", "| json_encode() | safe }}"), vars)
+}
+
+/// Evaluate `s` as a boolean expression. Undefined variables and render failures are
+/// returned as errors instead of being evaluated as `false`.
+///
+/// Boolean-like strings are interpreted directly, Ansible style: `yes`, `on`, `true` and `1` are
+/// `true`; `no`, `off`, `false` and `0` are `false`.
+#[inline(always)]
+pub fn is_render_string(s: &str, vars: Vars) -> Result<bool> {
+    match s.trim().to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => return Ok(true),
+        "false" | "no" | "off" | "0" => return Ok(false),
+        _ => (),
+    };
+    match render_string(
+        &format!(
+            "{{% set __rash_condition = {} %}}{{% if __rash_condition %}}true{{% else %}}false{{% endif %}}",
+            s
+        ),
+        vars,
+    )
+    .map_err(|e| e.context(format!("condition '{}'", s)))?
+    .as_str()
+    {
+        "false" => Ok(false),
+        "true" => Ok(true),
+        output => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("condition '{}' rendered to unexpected {:?}", s, output),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::File;
+    use std::io;
+
+    use tempfile::tempdir;
+
+    /// Writer which only counts bytes and lines, so output is never stored.
+    #[derive(Default)]
+    struct CountWriter {
+        bytes: usize,
+        lines: usize,
+    }
+
+    impl Write for CountWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.bytes += buf.len();
+            self.lines += buf.iter().filter(|&&b| b == b'\n').count();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_render_string_undefined_strict() {
+        let error =
+            render_string_with_behavior("{{ missing }}", Vars::new(), UndefinedBehavior::Strict)
+                .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        let result = render_string_with_behavior(
+            "{{ missing.attr | default(value='x') }}",
+            Vars::new(),
+            UndefinedBehavior::Strict,
+        )
+        .unwrap();
+        assert_eq!(result, "x");
+    }
+
+    #[test]
+    fn test_render_string_undefined_lenient() {
+        let vars = Vars::from_serialize(json!({"foo": {"boo": 1}})).unwrap();
+        let render = |s| render_string_with_behavior(s, vars.clone(), UndefinedBehavior::Lenient);
+        assert_eq!(render("a{{ missing }}b").unwrap(), "ab");
+        assert_eq!(render("{{ missing }}{{ other | upper }}").unwrap(), "");
+        assert_eq!(render("{{ foo.boo }}{{ foo.missing }}").unwrap(), "1");
+        let error = render("{{ missing.attr }}").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_render_string_undefined_chainable() {
+        let vars = Vars::from_serialize(json!({"foo": {"boo": 1}, "zoo": 1})).unwrap();
+        let render = |s| render_string_with_behavior(s, vars.clone(), UndefinedBehavior::Chainable);
+        assert_eq!(render("{{ missing }}").unwrap(), "");
+        assert_eq!(render("{{ missing.attr }}").unwrap(), "");
+        assert_eq!(render("{{ foo.missing.attr }}").unwrap(), "");
+        assert_eq!(
+            render("{{ missing.attr | default(value='x') }}").unwrap(),
+            "x"
+        );
+        let error = render("{{ zoo.attr }}").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_render_string_undefined_checked() {
+        let vars = Vars::from_serialize(json!({"items": ["a", "b"]})).unwrap();
+        let render = |s| render_string_with_behavior(s, vars.clone(), UndefinedBehavior::Lenient);
+        assert_eq!(
+            render("{% if missing is defined %}{{ missing }}{% else %}none{% endif %}").unwrap(),
+            "none"
+        );
+        assert_eq!(
+            render("{{ missing | default(value='x') }}{{ other }}").unwrap(),
+            "x"
+        );
+        assert_eq!(
+            render("{% for i in items %}{{ i }}{{ missing }}{% endfor %}").unwrap(),
+            "ab"
+        );
+        assert_eq!(render("{{ 'a' ~ missing ~ 'b' }}").unwrap(), "ab");
+    }
+
+    #[test]
+    fn test_render_string_undefined_behavior_from_vars() {
+        let vars = Vars::from_serialize(json!({"rash": {"undefined": "lenient"}})).unwrap();
+        assert_eq!(render_string("a{{ missing }}b", vars).unwrap(), "ab");
+        let vars = Vars::from_serialize(json!({"rash": {"undefined": "strict"}})).unwrap();
+        let _ = render_string("a{{ missing }}b", vars).unwrap_err();
+        let _ = render_string("a{{ missing }}b", Vars::new()).unwrap_err();
+    }
+
+    #[test]
+    fn test_undefined_behavior_from_str() {
+        assert_eq!(
+            UndefinedBehavior::from_str("Lenient").unwrap(),
+            UndefinedBehavior::Lenient
+        );
+        assert_eq!(
+            UndefinedBehavior::from_str("chainable").unwrap(),
+            UndefinedBehavior::Chainable
+        );
+        let error = UndefinedBehavior::from_str("foo").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_is_render_string() {
+        let vars = Vars::from_serialize(json!({"foo": 1, "boo": [1, 2]})).unwrap();
+        assert!(is_render_string("foo == 1", vars.clone()).unwrap());
+        assert!(!is_render_string("foo == 2", vars.clone()).unwrap());
+        assert!(is_render_string("boo | length == 2", vars.clone()).unwrap());
+        assert!(!is_render_string("not foo", vars).unwrap());
+    }
+
+    #[test]
+    fn test_is_render_string_bool_like() {
+        ["true", "True", "yes", "YES", "on", "1", " yes "]
+            .iter()
+            .for_each(|s| assert!(is_render_string(s, Vars::new()).unwrap(), "{}", s));
+        ["false", "False", "no", "NO", "off", "0"]
+            .iter()
+            .for_each(|s| assert!(!is_render_string(s, Vars::new()).unwrap(), "{}", s));
+        assert!(is_render_string("2", Vars::new()).unwrap());
+    }
+
+    #[test]
+    fn test_is_render_string_undefined() {
+        let error = is_render_string("undefined_var == 1", Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(format!("{}", error).starts_with("condition 'undefined_var == 1': "));
+    }
+
+    #[test]
+    fn test_is_render_string_defined() {
+        let vars = Vars::from_serialize(json!({"foo": {"boo": 1}, "empty": null})).unwrap();
+        for (s, expected) in [
+            ("foo is defined", true),
+            ("empty is defined", true),
+            ("foo.boo is defined", true),
+            ("foo.yea is defined", false),
+            ("missing is defined", false),
+            ("missing.yea is defined", false),
+            ("missing is undefined", true),
+            ("foo is undefined", false),
+            ("missing is not defined", true),
+            ("not missing is defined", true),
+            ("missing is defined and missing == 1", false),
+            ("foo is defined and foo.boo == 1", true),
+        ] {
+            assert_eq!(
+                is_render_string(s, vars.clone()).unwrap(),
+                expected,
+                "{}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_render_string_unexpected_output() {
+        let error = is_render_string("true %}foo{% if true", Vars::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_render_to_writer() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("large.j2");
+        let mut file = File::create(&file_path).unwrap();
+        write!(
+            file,
+            "{{% for i in range(end=200000) %}}line {{{{ i % 10 }}}}\n{{% endfor %}}"
+        )
+        .unwrap();
+
+        let mut writer = CountWriter::default();
+        render_to_writer(&file_path, &Vars::new(), &mut writer).unwrap();
+        assert_eq!(writer.lines, 200000);
+        assert_eq!(writer.bytes, 200000 * "line 0\n".len());
+    }
+
+    #[test]
+    fn test_render_to_writer_undefined_chainable() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("chainable.j2");
+        let mut file = File::create(&file_path).unwrap();
+        write!(file, "a{{{{ undefined_var.attr }}}}b").unwrap();
+        let vars = Vars::from_serialize(json!({"rash": {"undefined": "chainable"}})).unwrap();
+
+        let mut output = Vec::new();
+        render_to_writer(&file_path, &vars, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "ab");
+    }
+
+    #[test]
+    fn test_render_to_writer_error() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("error.j2");
+        let mut file = File::create(&file_path).unwrap();
+        write!(file, "{{{{ undefined_var }}}}").unwrap();
+
+        let mut output = Vec::new();
+        let error = render_to_writer(&file_path, &Vars::new(), &mut output).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::error::{Error, ErrorKind, Result};
+
+use std::ffi::CString;
+use std::mem::MaybeUninit;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use libc::{gmtime_r, localtime_r, strftime, time_t, tm};
+
+const MAX_FORMATTED_LEN: usize = 4096;
+
+/// Return current time as seconds since epoch.
+pub fn now() -> Result<i64> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .map_err(|e| Error::new(ErrorKind::Other, e))
+}
+
+/// Format `timestamp` in local time with strftime `format`, e.g.: `%Y-%m-%d %H:%M:%S`.
+pub fn format_local_time(format: &str, timestamp: i64) -> Result<String> {
+    format_time(format, timestamp, false)
+}
+
+/// Format `timestamp` in UTC with strftime `format`, e.g.: `%Y-%m-%dT%H:%M:%SZ`.
+pub fn format_utc_time(format: &str, timestamp: i64) -> Result<String> {
+    format_time(format, timestamp, true)
+}
+
+fn format_time(format: &str, timestamp: i64, utc: bool) -> Result<String> {
+    if format.is_empty() {
+        return Ok(String::new());
+    };
+    let c_format = CString::new(format).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let mut buf = vec![0u8; MAX_FORMATTED_LEN];
+    let len = unsafe {
+        let mut broken_down = MaybeUninit::<tm>::uninit();
+        let converted = if utc {
+            gmtime_r(&(timestamp as time_t), broken_down.as_mut_ptr())
+        } else {
+            localtime_r(&(timestamp as time_t), broken_down.as_mut_ptr())
+        };
+        if converted.is_null() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{} cannot be converted to date", timestamp),
+            ));
+        };
+        strftime(
+            buf.as_mut_ptr() as *mut libc::c_char,
+            buf.len(),
+            c_format.as_ptr(),
+            broken_down.as_ptr(),
+        )
+    };
+    if len == 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("{} cannot be formatted as time", format),
+        ));
+    };
+    buf.truncate(len);
+    String::from_utf8(buf).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2020-07-01T12:00:00Z, same day and year in any timezone
+    const TIMESTAMP: i64 = 1_593_604_800;
+
+    #[test]
+    fn test_format_local_time() {
+        assert_eq!(format_local_time("%Y-%m", TIMESTAMP).unwrap(), "2020-07");
+        assert_eq!(
+            format_local_time("Managed on %Y", TIMESTAMP).unwrap(),
+            "Managed on 2020"
+        );
+        assert_eq!(
+            format_local_time("no format", TIMESTAMP).unwrap(),
+            "no format"
+        );
+        assert_eq!(format_local_time("", TIMESTAMP).unwrap(), "");
+    }
+
+    #[test]
+    fn test_format_utc_time() {
+        assert_eq!(
+            format_utc_time("%Y-%m-%dT%H:%M:%SZ", TIMESTAMP).unwrap(),
+            "2020-07-01T12:00:00Z"
+        );
+    }
+
+    #[test]
+    fn test_now() {
+        assert!(now().unwrap() > TIMESTAMP);
+    }
+}
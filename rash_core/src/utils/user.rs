@@ -0,0 +1,111 @@
+use crate::error::{Error, ErrorKind, Result};
+
+use std::ffi::{CStr, CString};
+
+use libc::{getgrnam, getpwnam};
+
+/// Get uid from a user name or a numeric uid string.
+pub fn get_uid(owner: &str) -> Result<u32> {
+    resolve_id(owner, "user", |name| unsafe {
+        let passwd = getpwnam(name.as_ptr());
+        if passwd.is_null() {
+            None
+        } else {
+            Some((*passwd).pw_uid)
+        }
+    })
+}
+
+/// Get gid from a group name or a numeric gid string.
+pub fn get_gid(group: &str) -> Result<u32> {
+    resolve_id(group, "group", |name| unsafe {
+        let group = getgrnam(name.as_ptr());
+        if group.is_null() {
+            None
+        } else {
+            Some((*group).gr_gid)
+        }
+    })
+}
+
+/// Get home directory of `user` from passwd database.
+pub fn get_home_dir(user: &str) -> Result<String> {
+    let c_name = CString::new(user).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let home_dir = unsafe {
+        let passwd = getpwnam(c_name.as_ptr());
+        if passwd.is_null() {
+            None
+        } else {
+            Some(
+                CStr::from_ptr((*passwd).pw_dir)
+                    .to_string_lossy()
+                    .to_string(),
+            )
+        }
+    };
+    home_dir.ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("user {} does not exist", user),
+        )
+    })
+}
+
+fn resolve_id<F>(name: &str, kind: &str, lookup: F) -> Result<u32>
+where
+    F: Fn(&CString) -> Option<u32>,
+{
+    if let Ok(id) = name.parse::<u32>() {
+        return Ok(id);
+    };
+    let c_name = CString::new(name).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    lookup(&c_name).ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("{} {} does not exist", kind, name),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_lookup(name: &CString) -> Option<u32> {
+        match name.to_str().unwrap() {
+            "foo" => Some(1000),
+            "boo" => Some(1001),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_id() {
+        assert_eq!(resolve_id("foo", "user", mock_lookup).unwrap(), 1000);
+        assert_eq!(resolve_id("boo", "user", mock_lookup).unwrap(), 1001);
+    }
+
+    #[test]
+    fn test_resolve_id_numeric() {
+        assert_eq!(resolve_id("1234", "user", mock_lookup).unwrap(), 1234);
+    }
+
+    #[test]
+    fn test_resolve_id_not_found() {
+        let error = resolve_id("zoo", "group", mock_lookup).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_get_home_dir() {
+        assert_eq!(get_home_dir("root").unwrap(), "/root");
+        let error = get_home_dir("this_user_does_not_exist").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_get_uid_and_gid_root() {
+        assert_eq!(get_uid("root").unwrap(), 0);
+        assert_eq!(get_gid("root").unwrap(), 0);
+    }
+}
@@ -0,0 +1,246 @@
+//! Encrypted values, decrypted just before rendering with `vault` lookup.
+//!
+//! Values are encrypted with AES-256-GCM, using a key derived from a password with
+//! PBKDF2-HMAC-SHA256 and a random salt. They are stored as `rash-vault:v1:`, followed by the
+//! PBKDF2 iterations, `:` and the hex encoded salt, nonce and ciphertext.
+//! By default, password is read from `RASH_VAULT_PASSWORD` environment variable or from the
+//! file in `RASH_VAULT_PASSWORD_FILE`.
+use crate::error::{Error, ErrorKind, Result};
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use pbkdf2::pbkdf2_hmac_array;
+use sha2::Sha256;
+
+pub const VAULT_PREFIX: &str = "rash-vault:v1:";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+/// PBKDF2-HMAC-SHA256 iterations of new values, as recommended by OWASP.
+const ITERATIONS: u32 = 600_000;
+
+/// Decrypt vault values. Implement it to use other encryption schemes or key sources.
+pub trait Decryptor {
+    fn decrypt(&self, ciphertext: &str) -> Result<String>;
+}
+
+impl<D: Decryptor + ?Sized> Decryptor for Arc<D> {
+    fn decrypt(&self, ciphertext: &str) -> Result<String> {
+        (**self).decrypt(ciphertext)
+    }
+}
+
+/// Decryptor with a fixed password.
+pub struct PasswordDecryptor {
+    password: String,
+}
+
+impl PasswordDecryptor {
+    pub fn new(password: &str) -> Self {
+        PasswordDecryptor {
+            password: password.to_string(),
+        }
+    }
+}
+
+impl Decryptor for PasswordDecryptor {
+    fn decrypt(&self, ciphertext: &str) -> Result<String> {
+        decrypt(ciphertext, &self.password)
+    }
+}
+
+/// Decryptor reading password from `RASH_VAULT_PASSWORD` or `RASH_VAULT_PASSWORD_FILE`
+/// environment variables each time a value is decrypted.
+pub struct EnvDecryptor;
+
+impl Decryptor for EnvDecryptor {
+    fn decrypt(&self, ciphertext: &str) -> Result<String> {
+        decrypt(ciphertext, &get_env_password()?)
+    }
+}
+
+/// Read vault password from `path`, without trailing new lines.
+pub fn read_password_file(path: &Path) -> Result<String> {
+    Ok(fs::read_to_string(path)?.trim_end().to_string())
+}
+
+/// Return vault password from `RASH_VAULT_PASSWORD` or from the file in
+/// `RASH_VAULT_PASSWORD_FILE` environment variables.
+pub fn get_env_password() -> Result<String> {
+    if let Ok(password) = env::var("RASH_VAULT_PASSWORD") {
+        return Ok(password);
+    };
+    match env::var("RASH_VAULT_PASSWORD_FILE") {
+        Ok(path) => read_password_file(Path::new(&path)),
+        Err(_) => Err(Error::new(
+            ErrorKind::NotFound,
+            "vault password not found: set RASH_VAULT_PASSWORD or RASH_VAULT_PASSWORD_FILE",
+        )),
+    }
+}
+
+fn get_cipher(password: &str, salt: &[u8], iterations: u32) -> Aes256Gcm {
+    let key = pbkdf2_hmac_array::<Sha256, 32>(password.as_bytes(), salt, iterations);
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Result<Vec<u8>> {
+    s.as_bytes()
+        .chunks(2)
+        .map(|chunk| {
+            std::str::from_utf8(chunk)
+                .ok()
+                .filter(|byte| byte.len() == 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "vault value is not valid hex"))
+        })
+        .collect()
+}
+
+/// Encrypt `plaintext` with `password`, returning a vault value.
+pub fn encrypt(plaintext: &str, password: &str) -> Result<String> {
+    encrypt_with_iterations(plaintext, password, ITERATIONS)
+}
+
+fn encrypt_with_iterations(plaintext: &str, password: &str, iterations: u32) -> Result<String> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = get_cipher(password, &salt, iterations)
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|_| Error::new(ErrorKind::Other, "vault encryption failed"))?;
+    Ok(format!(
+        "{}{}:{}{}{}",
+        VAULT_PREFIX,
+        iterations,
+        to_hex(&salt),
+        to_hex(&nonce),
+        to_hex(&ciphertext)
+    ))
+}
+
+/// Decrypt vault value `ciphertext` with `password`.
+pub fn decrypt(ciphertext: &str, password: &str) -> Result<String> {
+    let (iterations, data) = ciphertext
+        .trim()
+        .strip_prefix(VAULT_PREFIX)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("vault value must start with {}", VAULT_PREFIX),
+            )
+        })?
+        .split_once(':')
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "vault value has no iterations"))?;
+    let iterations = iterations
+        .parse::<u32>()
+        .ok()
+        .filter(|iterations| *iterations > 0)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "vault iterations are not valid"))?;
+    let data = from_hex(data)?;
+    if data.len() < SALT_LEN + NONCE_LEN {
+        return Err(Error::new(ErrorKind::InvalidData, "vault value too short"));
+    };
+    let (salt, data) = data.split_at(SALT_LEN);
+    let (nonce, encrypted) = data.split_at(NONCE_LEN);
+    let plaintext = get_cipher(password, salt, iterations)
+        .decrypt(Nonce::from_slice(nonce), encrypted)
+        .map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidData,
+                "vault decryption failed, password is wrong or value is corrupted",
+            )
+        })?;
+    String::from_utf8(plaintext).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_ITERATIONS: u32 = 1_000;
+
+    fn encrypt_test(plaintext: &str, password: &str) -> String {
+        encrypt_with_iterations(plaintext, password, TEST_ITERATIONS).unwrap()
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let ciphertext = encrypt("supersecret", "password").unwrap();
+        assert!(ciphertext.starts_with(&format!("{}{}:", VAULT_PREFIX, ITERATIONS)));
+        assert!(!ciphertext.contains("supersecret"));
+        assert_eq!(decrypt(&ciphertext, "password").unwrap(), "supersecret");
+    }
+
+    #[test]
+    fn test_encrypt_random_salt_and_nonce() {
+        let first = encrypt_test("supersecret", "password");
+        let second = encrypt_test("supersecret", "password");
+        let prefix_len = format!("{}{}:", VAULT_PREFIX, TEST_ITERATIONS).len();
+        assert_ne!(
+            first[prefix_len..prefix_len + 2 * SALT_LEN],
+            second[prefix_len..prefix_len + 2 * SALT_LEN]
+        );
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_decrypt_wrong_password() {
+        let ciphertext = encrypt_test("supersecret", "password");
+        let error = decrypt(&ciphertext, "boo").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_decrypt_wrong_iterations() {
+        let ciphertext = encrypt_test("supersecret", "password").replacen(
+            &format!(":{}:", TEST_ITERATIONS),
+            &format!(":{}:", TEST_ITERATIONS + 1),
+            1,
+        );
+        let error = decrypt(&ciphertext, "password").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_decrypt_invalid() {
+        [
+            "supersecret",
+            "rash-vault:v1:zz",
+            "rash-vault:v1:0:00",
+            "rash-vault:v1:foo:00",
+            "rash-vault:v1:1000:zz",
+            "rash-vault:v1:1000:abc",
+            "rash-vault:v1:1000:00",
+        ]
+        .iter()
+        .for_each(|s| {
+            let error = decrypt(s, "password").unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidData, "{}", s);
+        });
+    }
+
+    #[test]
+    fn test_password_decryptor() {
+        let ciphertext = encrypt_test("supersecret", "password");
+        let decryptor = PasswordDecryptor::new("password");
+        assert_eq!(decryptor.decrypt(&ciphertext).unwrap(), "supersecret");
+    }
+
+    #[test]
+    fn test_read_password_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("password");
+        fs::write(&path, "password\n").unwrap();
+        assert_eq!(read_password_file(&path).unwrap(), "password");
+    }
+}
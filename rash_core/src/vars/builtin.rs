@@ -1,5 +1,8 @@
 use crate::error::Result;
+use crate::utils::tera::UndefinedBehavior;
+use crate::vars::Vars;
 
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use libc::{getgid, getuid};
@@ -15,6 +18,12 @@ pub struct Builtins {
     /// Script absolute path.
     path: PathBuf,
     user: UserInfo,
+    /// Extra vars passed from command line, which override any other vars.
+    extra_vars: HashMap<String, String>,
+    /// Behavior of undefined variables in templates: strict, lenient or chainable.
+    undefined: UndefinedBehavior,
+    /// Show changes made by modules which support it, like `template`.
+    diff: bool,
 }
 
 #[derive(Serialize)]
@@ -35,6 +44,9 @@ struct UserInfo {
 ///       - 'rash.path == "/builtins_example.rh"'
 ///       - 'rash.user.uid == 1000'
 ///       - 'rash.user.gid == 1000'
+///       - 'rash.extra_vars | length == 0'
+///       - 'rash.undefined == "strict"'
+///       - 'not rash.diff'
 /// ```
 // ANCHOR_END: examples
 
@@ -58,8 +70,37 @@ impl Builtins {
             dir,
             path: path.to_path_buf(),
             user: UserInfo { uid, gid },
+            extra_vars: HashMap::new(),
+            undefined: UndefinedBehavior::Strict,
+            diff: false,
         })
     }
+
+    /// Set extra vars passed from command line.
+    pub fn with_extra_vars(mut self, extra_vars: Vec<(String, String)>) -> Self {
+        self.extra_vars = extra_vars.into_iter().collect();
+        self
+    }
+
+    /// Set behavior of undefined variables in templates.
+    pub fn with_undefined(mut self, undefined: UndefinedBehavior) -> Self {
+        self.undefined = undefined;
+        self
+    }
+
+    /// Set if modules must return the changes they make.
+    pub fn with_diff(mut self, diff: bool) -> Self {
+        self.diff = diff;
+        self
+    }
+}
+
+/// Return true if `rash.diff` builtin is set in `vars`, so modules must return their changes.
+pub fn is_diff(vars: &Vars) -> bool {
+    vars.get("rash")
+        .and_then(|rash| rash.get("diff"))
+        .and_then(|diff| diff.as_bool())
+        .unwrap_or(false)
 }
 
 #[cfg(test)]
@@ -72,5 +113,29 @@ mod tests {
         assert_eq!(builtins.args.len(), 0);
         assert_eq!(builtins.path.as_os_str(), "/example.rh");
         assert_eq!(builtins.dir.as_os_str(), "/");
+        assert!(builtins.extra_vars.is_empty());
+        assert_eq!(builtins.undefined, UndefinedBehavior::Strict);
+        assert!(!builtins.diff);
+    }
+
+    #[test]
+    fn test_is_diff() {
+        let mut vars = Vars::new();
+        assert!(!is_diff(&vars));
+        vars.insert(
+            "rash",
+            &Builtins::new(vec![], Path::new("/example.rh"))
+                .unwrap()
+                .with_diff(true),
+        );
+        assert!(is_diff(&vars));
+    }
+
+    #[test]
+    fn test_builtin_with_extra_vars() {
+        let builtins = Builtins::new(vec![], Path::new("/example.rh"))
+            .unwrap()
+            .with_extra_vars(vec![("foo".to_string(), "boo".to_string())]);
+        assert_eq!(builtins.extra_vars.get("foo").unwrap(), "boo");
     }
 }
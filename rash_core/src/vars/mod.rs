@@ -1,6 +1,8 @@
 pub mod builtin;
 pub mod env;
 
+use serde::Serialize;
+use serde_json::{Map, Value};
 use tera::Context;
 
 /// Variables stored and accessible during execution, based on [`tera::Context`]
@@ -8,6 +10,164 @@ use tera::Context;
 /// [`tera::Context`]: ../../tera/struct.Context.html
 pub type Vars = Context;
 
+/// Sources of [`Vars`], from lowest to highest precedence.
+///
+/// [`Vars`]: type.Vars.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Precedence {
+    /// Initial values, like `env` and `rash` builtins.
+    Defaults,
+    /// Variables defined in task `vars` field, only visible in that task.
+    TaskVars,
+    /// Variables defined by `set_vars` module or registered by previous tasks.
+    SetVars,
+    /// Variables passed from command line, which can't be overridden.
+    ExtraVars,
+}
+
+/// Top level keys of [`Precedence::Defaults`] layer.
+///
+/// [`Precedence::Defaults`]: enum.Precedence.html#variant.Defaults
+const DEFAULTS_KEYS: &[&str] = &["env", "rash"];
+
+/// Merge `layers` into a single [`Vars`]. Top level keys of higher [`Precedence`] layers override
+/// lower ones, and layers with the same precedence are applied in order, so later ones win.
+///
+/// [`Vars`]: type.Vars.html
+/// [`Precedence`]: enum.Precedence.html
+pub fn merge_vars<I>(layers: I) -> Vars
+where
+    I: IntoIterator<Item = (Precedence, Vars)>,
+{
+    let mut layers = layers.into_iter().collect::<Vec<(Precedence, Vars)>>();
+    // stable sort keeps order of layers with the same precedence
+    layers.sort_by_key(|(precedence, _)| *precedence);
+    layers
+        .into_iter()
+        .fold(Vars::new(), |mut vars, (_, layer)| {
+            vars.extend(layer);
+            vars
+        })
+}
+
+/// Split `vars` into the layers it was merged from: `env` and `rash` builtins are
+/// [`Precedence::Defaults`], command line extra vars, kept in `rash.extra_vars`, are
+/// [`Precedence::ExtraVars`] and the rest are [`Precedence::SetVars`].
+///
+/// [`Precedence::Defaults`]: enum.Precedence.html#variant.Defaults
+/// [`Precedence::ExtraVars`]: enum.Precedence.html#variant.ExtraVars
+/// [`Precedence::SetVars`]: enum.Precedence.html#variant.SetVars
+pub fn split_layers(vars: &Vars) -> Vec<(Precedence, Vars)> {
+    let mut defaults = Vars::new();
+    let mut set_vars = Vars::new();
+    let mut extra_vars = Vars::new();
+    snapshot(vars).into_iter().for_each(|(key, value)| {
+        if DEFAULTS_KEYS.contains(&key.as_str()) {
+            if key == "rash" {
+                if let Some(Value::Object(extra)) = value.get("extra_vars") {
+                    extra.iter().for_each(|(extra_key, extra_value)| {
+                        extra_vars.insert(extra_key, extra_value)
+                    });
+                };
+            };
+            defaults.insert(key, &value);
+        } else {
+            set_vars.insert(key, &value);
+        };
+    });
+    vec![
+        (Precedence::Defaults, defaults),
+        (Precedence::SetVars, set_vars),
+        (Precedence::ExtraVars, extra_vars),
+    ]
+}
+
+/// Merge `layer` into `vars` with `precedence`, keeping the layers `vars` was merged from. E.g.:
+/// task vars don't override previous `set_vars`, and nothing overrides extra vars.
+pub fn merge_layer(vars: &Vars, precedence: Precedence, layer: Vars) -> Vars {
+    merge_vars(
+        split_layers(vars)
+            .into_iter()
+            .chain(std::iter::once((precedence, layer))),
+    )
+}
+
+/// Copy of [`Vars`] contents at some point of the execution, to be compared with [`diff_vars`].
+///
+/// [`Vars`]: type.Vars.html
+/// [`diff_vars`]: fn.diff_vars.html
+pub type Snapshot = Map<String, Value>;
+
+/// Change of a top level key between two [`Snapshot`]s.
+///
+/// [`Snapshot`]: type.Snapshot.html
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "lowercase")]
+pub enum VarChange {
+    Added {
+        key: String,
+        value: Value,
+    },
+    Changed {
+        key: String,
+        before: Value,
+        after: Value,
+    },
+    Removed {
+        key: String,
+        value: Value,
+    },
+}
+
+impl VarChange {
+    pub fn get_key(&self) -> &str {
+        match self {
+            VarChange::Added { key, .. }
+            | VarChange::Changed { key, .. }
+            | VarChange::Removed { key, .. } => key,
+        }
+    }
+}
+
+/// Return current contents of `vars`.
+pub fn snapshot(vars: &Vars) -> Snapshot {
+    match vars.clone().into_json() {
+        Value::Object(map) => map,
+        // tera::Context is always serialized as an object
+        _ => Snapshot::new(),
+    }
+}
+
+/// Return keys added, changed or removed from `before` to `after`, sorted by key.
+pub fn diff_vars(before: &Snapshot, after: &Snapshot) -> Vec<VarChange> {
+    let mut changes = after
+        .iter()
+        .filter_map(|(key, value)| match before.get(key) {
+            None => Some(VarChange::Added {
+                key: key.clone(),
+                value: value.clone(),
+            }),
+            Some(previous) if previous != value => Some(VarChange::Changed {
+                key: key.clone(),
+                before: previous.clone(),
+                after: value.clone(),
+            }),
+            Some(_) => None,
+        })
+        .chain(
+            before
+                .iter()
+                .filter(|(key, _)| !after.contains_key(*key))
+                .map(|(key, value)| VarChange::Removed {
+                    key: key.clone(),
+                    value: value.clone(),
+                }),
+        )
+        .collect::<Vec<VarChange>>();
+    changes.sort_by(|a, b| a.get_key().cmp(b.get_key()));
+    changes
+}
+
 #[cfg(test)]
 use std::collections::HashMap;
 
@@ -23,3 +183,196 @@ where
     )
     .unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::modules::MODULES;
+
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn test_merge_vars() {
+        let vars = merge_vars(vec![
+            (
+                Precedence::ExtraVars,
+                from_iter(vec![("foo", "extra")].into_iter()),
+            ),
+            (
+                Precedence::Defaults,
+                from_iter(vec![("foo", "default"), ("boo", "default")].into_iter()),
+            ),
+            (
+                Precedence::TaskVars,
+                from_iter(vec![("foo", "task"), ("zoo", "task")].into_iter()),
+            ),
+            (
+                Precedence::SetVars,
+                from_iter(vec![("zoo", "set_vars"), ("buu", "set_vars")].into_iter()),
+            ),
+        ]);
+        assert_eq!(
+            vars.into_json(),
+            json!({"foo": "extra", "boo": "default", "zoo": "set_vars", "buu": "set_vars"})
+        );
+    }
+
+    #[test]
+    fn test_merge_vars_precedence_pairs() {
+        let precedences = [
+            Precedence::Defaults,
+            Precedence::TaskVars,
+            Precedence::SetVars,
+            Precedence::ExtraVars,
+        ];
+        precedences.iter().enumerate().for_each(|(i, lower)| {
+            precedences[i + 1..].iter().for_each(|higher| {
+                let lower_layer = from_iter([("foo", "lower"), ("boo", "lower")].iter().copied());
+                let higher_layer = from_iter([("foo", "higher")].iter().copied());
+                let expected = json!({"foo": "higher", "boo": "lower"});
+                assert_eq!(
+                    merge_vars(vec![
+                        (*lower, lower_layer.clone()),
+                        (*higher, higher_layer.clone())
+                    ])
+                    .into_json(),
+                    expected,
+                    "{:?} must override {:?}",
+                    higher,
+                    lower
+                );
+                assert_eq!(
+                    merge_vars(vec![(*higher, higher_layer), (*lower, lower_layer)]).into_json(),
+                    expected,
+                    "{:?} must override {:?}",
+                    higher,
+                    lower
+                );
+            })
+        });
+    }
+
+    #[test]
+    fn test_split_layers() {
+        let mut vars = from_iter([("foo", "set_vars"), ("boo", "extra")].iter().copied());
+        vars.insert("env", &json!({"HOME": "/root"}));
+        vars.insert("rash", &json!({"extra_vars": {"boo": "extra"}}));
+        assert_eq!(
+            split_layers(&vars)
+                .into_iter()
+                .map(|(precedence, layer)| (precedence, layer.into_json()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    Precedence::Defaults,
+                    json!({"env": {"HOME": "/root"}, "rash": {"extra_vars": {"boo": "extra"}}})
+                ),
+                (
+                    Precedence::SetVars,
+                    json!({"foo": "set_vars", "boo": "extra"})
+                ),
+                (Precedence::ExtraVars, json!({"boo": "extra"})),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_layer() {
+        let mut vars = from_iter([("foo", "set_vars"), ("boo", "extra")].iter().copied());
+        vars.insert("env", &json!({"HOME": "/root"}));
+        vars.insert("rash", &json!({"extra_vars": {"boo": "extra"}}));
+        let layer = from_iter(
+            [("foo", "new"), ("boo", "new"), ("env", "new")]
+                .iter()
+                .copied(),
+        );
+
+        let task_vars = merge_layer(&vars, Precedence::TaskVars, layer.clone()).into_json();
+        assert_eq!(task_vars["foo"], json!("set_vars"));
+        assert_eq!(task_vars["boo"], json!("extra"));
+        assert_eq!(task_vars["env"], json!("new"));
+
+        let set_vars = merge_layer(&vars, Precedence::SetVars, layer).into_json();
+        assert_eq!(set_vars["foo"], json!("new"));
+        assert_eq!(set_vars["boo"], json!("extra"));
+        assert_eq!(set_vars["env"], json!("new"));
+    }
+
+    #[test]
+    fn test_merge_vars_same_precedence() {
+        let vars = merge_vars(vec![
+            (
+                Precedence::SetVars,
+                from_iter(vec![("foo", "first"), ("boo", "first")].into_iter()),
+            ),
+            (
+                Precedence::SetVars,
+                from_iter(vec![("foo", "second")].into_iter()),
+            ),
+        ]);
+        assert_eq!(vars.into_json(), json!({"foo": "second", "boo": "first"}));
+    }
+
+    #[test]
+    fn test_merge_vars_empty() {
+        assert_eq!(merge_vars(vec![]).into_json(), json!({}));
+    }
+
+    #[test]
+    fn test_diff_vars() {
+        let before = snapshot(&from_iter(
+            vec![("foo", "1"), ("boo", "1"), ("zoo", "1")].into_iter(),
+        ));
+        let after = snapshot(&from_iter(
+            vec![("foo", "1"), ("boo", "2"), ("buu", "1")].into_iter(),
+        ));
+        assert_eq!(
+            diff_vars(&before, &after),
+            vec![
+                VarChange::Changed {
+                    key: "boo".to_string(),
+                    before: json!("1"),
+                    after: json!("2"),
+                },
+                VarChange::Added {
+                    key: "buu".to_string(),
+                    value: json!("1"),
+                },
+                VarChange::Removed {
+                    key: "zoo".to_string(),
+                    value: json!("1"),
+                },
+            ]
+        );
+        assert_eq!(diff_vars(&after, &after), vec![]);
+    }
+
+    #[test]
+    fn test_diff_vars_set_vars() {
+        let vars = from_iter(vec![("foo", "boo")].into_iter());
+        let before = snapshot(&vars);
+        let yaml = YamlLoader::load_from_str("version: 1.0.0").unwrap()[0].clone();
+        let (_, new_vars) = MODULES.get("set_vars").unwrap().exec(yaml, vars).unwrap();
+        assert_eq!(
+            diff_vars(&before, &snapshot(&new_vars)),
+            vec![VarChange::Added {
+                key: "version".to_string(),
+                value: json!("1.0.0"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_var_change_serialize() {
+        let change = VarChange::Changed {
+            key: "foo".to_string(),
+            before: json!(1),
+            after: json!(2),
+        };
+        assert_eq!(
+            serde_json::to_value(change).unwrap(),
+            json!({"change": "changed", "key": "foo", "before": 1, "after": 2})
+        );
+    }
+}